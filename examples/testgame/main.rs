@@ -50,6 +50,7 @@ pub struct State {
     mouse_mode: MouseMode,
     mouse_grabber: MouseGrabber,
     physics: phys::Physics,
+    snapshot: Option<Vec<u8>>,
     camera: gx::camera::MouseDragCamera,
     shape_renderer: gx::ShapeRenderer,
 }
@@ -70,7 +71,9 @@ impl State {
                 smallest_obj_radius: 0.5,
                 largest_obj_radius: 3.0,
                 expected_obj_count: 100,
-            }),
+            })
+            .with_sleep_params(Some(Default::default())),
+            snapshot: None,
             camera: gx::camera::MouseDragCamera::new(
                 gx::camera::ScalingStrategy::ConstantDisplayArea {
                     width: 20.0,
@@ -84,6 +87,8 @@ impl State {
     fn reset(&mut self) {
         self.physics.clear_constraints();
         self.graph = MyGraph::new();
+        // the snapshot belongs to the previous scene
+        self.snapshot = None;
     }
 
     fn read_scene(&mut self, file_idx: usize) {
@@ -165,10 +170,17 @@ pub struct MyGraph {
     l_collider: graph::Layer<phys::Collider>,
     l_body: graph::Layer<phys::Body>,
     l_rope: graph::Layer<phys::Rope>,
+    l_soft_body: graph::Layer<phys::SoftBody>,
     l_shape: graph::Layer<gx::Shape>,
     l_player: graph::Layer<player::Player>,
     evt_graph: sf::event::EventGraph<MyGraph>,
 }
+impl Default for MyGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MyGraph {
     pub fn new() -> Self {
         let mut graph = graph::Graph::new();
@@ -176,6 +188,7 @@ impl MyGraph {
         let l_collider = graph.create_layer();
         let l_body = graph.create_layer();
         let l_rope = graph.create_layer();
+        let l_soft_body = graph.create_layer();
         let l_shape = graph.create_layer();
         let l_player = graph.create_layer();
         let evt_graph = sf::event::EventGraph::new(&mut graph);
@@ -185,6 +198,7 @@ impl MyGraph {
             l_collider,
             l_body,
             l_rope,
+            l_soft_body,
             l_shape,
             l_player,
             evt_graph,
@@ -274,6 +288,46 @@ impl game::GameState for State {
             self.instantiate_scene();
         }
 
+        // save and restore the physics state
+
+        if game.input.is_key_pressed(Key::F5, Some(0)) {
+            self.snapshot = Some(self.physics.snapshot(
+                &self.graph.graph,
+                &self.graph.l_pose,
+                &self.graph.l_body,
+                &self.graph.l_collider,
+                &self.graph.l_rope,
+                &self.graph.l_soft_body,
+                &[
+                    &self.graph.l_shape,
+                    &self.graph.l_player,
+                    &self.graph.evt_graph.sinks,
+                ],
+            ));
+            println!("Saved snapshot");
+        }
+        if game.input.is_key_pressed(Key::F9, Some(0)) {
+            if let Some(snapshot) = &self.snapshot {
+                match self.physics.restore_snapshot(
+                    snapshot,
+                    &mut self.graph.graph,
+                    &mut self.graph.l_pose,
+                    &mut self.graph.l_body,
+                    &mut self.graph.l_collider,
+                    &mut self.graph.l_rope,
+                    &mut self.graph.l_soft_body,
+                    &mut [
+                        &mut self.graph.l_shape,
+                        &mut self.graph.l_player,
+                        &mut self.graph.evt_graph.sinks,
+                    ],
+                ) {
+                    Ok(()) => println!("Restored snapshot"),
+                    Err(err) => eprintln!("{}", err),
+                }
+            }
+        }
+
         // spawn stuff also when paused
         let random_pos = || {
             let mut rng = rand::thread_rng();
@@ -335,6 +389,15 @@ impl game::GameState for State {
                     return Some(());
                 }
 
+                {
+                    self.player.tick(
+                        &mut self.graph,
+                        &mut self.physics,
+                        &game.input,
+                        self.scene.gravity.into(),
+                        dt,
+                    );
+                }
                 {
                     let grav = phys::forcefield::Gravity(self.scene.gravity.into());
                     self.physics.tick(
@@ -343,14 +406,12 @@ impl game::GameState for State {
                         &mut self.graph.l_body,
                         &self.graph.l_collider,
                         &self.graph.l_rope,
+                        &self.graph.l_soft_body,
                         &mut self.graph.evt_graph.sinks,
                         dt,
                         &grav,
                     );
                 }
-                {
-                    self.player.tick(&mut self.graph, &game.input);
-                }
 
                 self.graph.evt_graph.flush(&self.graph.graph)(&mut self.graph);
 
@@ -382,6 +443,7 @@ impl game::GameState for State {
         self.shape_renderer.draw(
             &self.graph.l_shape,
             &self.graph.l_pose,
+            &self.graph.l_collider,
             &self.graph.graph,
             &self.camera,
             &mut ctx,
@@ -27,7 +27,7 @@ impl MouseGrabber {
                 camera.point_screen_to_world(viewport_size, input.cursor_position().into());
             match self.constraint {
                 Some(handle) => {
-                    if let Some(constr) = physics.get_constraint_mut(handle) {
+                    if let Some(mut constr) = physics.get_constraint_mut(handle) {
                         constr.offsets[1] = target_point;
                     }
                 }
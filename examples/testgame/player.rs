@@ -5,19 +5,23 @@ use starframe::{
     math as m, physics as phys,
 };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Player {
     facing: Facing,
+    controller: phys::CharacterController,
+    velocity: m::Vec2,
 }
 impl Player {
-    fn new() -> Self {
+    fn new(body: sf::graph::Node<phys::Body>) -> Self {
         Player {
             facing: Facing::Left,
+            controller: phys::CharacterController::new(body),
+            velocity: m::Vec2::zero(),
         }
     }
 }
-#[derive(Clone, Copy, Debug)]
-pub(self) enum Facing {
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+enum Facing {
     Right,
     Left,
 }
@@ -50,11 +54,20 @@ impl PlayerRecipe {
             },
             &mut graph.graph,
         );
-        let coll = phys::Collider::new_rect(WIDTH, HEIGHT);
-        let body = phys::Body::new_dynamic(&coll, 3.0);
+        // upright capsule, capsules are horizontal by default
+        let coll = phys::Collider::new_capsule(HEIGHT - WIDTH, WIDTH / 2.0).with_offset(
+            m::PoseBuilder::new()
+                .with_rotation(m::Angle::Deg(90.0))
+                .build(),
+        );
+        let body = phys::Body::new_kinematic();
         let coll_node = graph.l_collider.insert(coll, &mut graph.graph);
         let body_node = graph.l_body.insert(body, &mut graph.graph);
-        let tag_node = graph.l_player.insert(Player::new(), &mut graph.graph);
+        let body_node = sf::graph::NodeRef::as_node(&body_node, &graph.graph);
+        let tag_node = graph
+            .l_player
+            .insert(Player::new(body_node), &mut graph.graph);
+        let body_node = body_node.check(&graph.graph).unwrap();
         graph.graph.connect(&pose_node, &body_node);
         graph.graph.connect(&pose_node, &coll_node);
         graph.graph.connect(&body_node, &coll_node);
@@ -68,16 +81,25 @@ impl PlayerRecipe {
 pub struct PlayerController {
     base_move_speed: f64,
     max_acceleration: f64,
+    jump_speed: f64,
 }
 impl PlayerController {
     pub fn new() -> Self {
         PlayerController {
             base_move_speed: 4.0,
             max_acceleration: 8.0,
+            jump_speed: 4.0,
         }
     }
 
-    pub fn tick(&mut self, g: &mut MyGraph, input: &sf::InputCache) {
+    pub fn tick(
+        &mut self,
+        g: &mut MyGraph,
+        physics: &mut phys::Physics,
+        input: &sf::InputCache,
+        gravity: m::Vec2,
+        dt: f64,
+    ) {
         let (target_facing, target_hdir) = match input.get_key_axis_state(Key::Right, Key::Left) {
             KeyAxisState::Zero => (None, 0.0),
             KeyAxisState::Pos => (Some(Facing::Right), 1.0),
@@ -86,9 +108,6 @@ impl PlayerController {
 
         let mut bullet_queue: Vec<(m::Pose, phys::Velocity)> = Vec::new();
         for mut player in g.l_player.iter_mut(&g.graph) {
-            let mut player_body = g.graph.get_neighbor_mut(&player, &mut g.l_body).unwrap();
-            let mut player_tr = g.graph.get_neighbor_mut(&player, &mut g.l_pose).unwrap();
-
             // move and orient
 
             if let Some(facing) = target_facing {
@@ -98,22 +117,32 @@ impl PlayerController {
             let move_speed = self.base_move_speed;
 
             let target_hvel = target_hdir * move_speed;
-            let accel_needed = target_hvel - player_body.velocity.linear.x;
+            let accel_needed = target_hvel - player.velocity.x;
             let accel = accel_needed.min(self.max_acceleration);
-            player_body.velocity.linear.x += accel;
-
-            // hacked up rotation locking
-
-            player_body.velocity.angular = 0.0;
-            player_tr.rotation = m::Rotor2::identity();
+            player.velocity.x += accel;
+            player.velocity += gravity * dt;
 
             // jump
 
-            if input.is_key_pressed(Key::LShift, Some(0)) {
-                // TODO: only on ground, double jump, custom curve
-                player_body.velocity.linear.y = 4.0;
+            if input.is_key_pressed(Key::LShift, Some(0)) && player.controller.try_jump() {
+                // TODO: double jump, custom curve
+                player.velocity.y = self.jump_speed;
             }
 
+            let velocity = player.velocity;
+            let state = player.controller.move_and_slide(
+                physics,
+                &g.graph,
+                &mut g.l_pose,
+                &mut g.l_body,
+                &g.l_collider,
+                velocity,
+                dt,
+            );
+            player.velocity = state.velocity;
+
+            let player_tr = *g.graph.get_neighbor(&player, &g.l_pose).unwrap();
+
             // shoot
 
             if input.is_key_pressed(Key::Z, Some(0)) {
@@ -149,18 +178,19 @@ impl PlayerController {
             &mut g.graph,
         );
         let coll = phys::Collider::new_circle(R);
-        let body = phys::Body::new_dynamic_const_mass(&coll, 1.0).with_velocity(vel);
+        let body = phys::Body::new_dynamic_const_mass(&coll, 1.0)
+            .with_velocity(vel)
+            .with_ccd(true);
         let coll_node = g.l_collider.insert(coll, &mut g.graph);
         let body_node = g.l_body.insert(body, &mut g.graph);
 
         let evt_sink_node = g.evt_graph.add_sink(
-            |g: &mut MyGraph, node, evt| match evt {
-                sf::Event::Contact(_) => {
+            |g: &mut MyGraph, node, evt| {
+                if let sf::Event::ContactBegin(_) = evt {
                     if let Some(checked) = node.check(&g.graph) {
                         g.graph.delete(checked);
                     }
                 }
-                _ => (),
             },
             &mut g.graph,
         );
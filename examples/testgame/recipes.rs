@@ -30,6 +30,21 @@ pub enum Recipe {
         block2: Block,
         offset2: [f64; 2],
     },
+    Rope {
+        points: Vec<[f64; 2]>,
+        bezier: bool,
+        anchored_start: bool,
+        anchored_end: bool,
+    },
+    Terrain {
+        points: Vec<[f64; 2]>,
+        closed: bool,
+    },
+    SoftBody {
+        points: Vec<[f64; 2]>,
+        spacing: f64,
+        area_compliance: f64,
+    },
 }
 
 #[derive(Clone, Copy, Debug, serde::Deserialize)]
@@ -288,7 +303,7 @@ impl Recipe {
                                 links
                                     .iter()
                                     .map(|p| m::Vec2::new(p[0], p[1]))
-                                    .last()
+                                    .next_back()
                                     .unwrap(),
                             )
                             .build_attachment(),
@@ -346,6 +361,8 @@ impl Recipe {
                 let rope = phys::spawn_rope_line(
                     phys::Rope {
                         spacing: 0.1,
+                        // computed from the endpoints
+                        length: 0.0,
                         thickness: 0.12,
                         compliance: 0.0000001,
                         bending_max_angle: m::Angle::Deg(15.0).rad(),
@@ -355,7 +372,9 @@ impl Recipe {
                             static_friction_coef: None,
                             dynamic_friction_coef: Some(1.5),
                             restitution_coef: 0.0,
+                            ..Default::default()
                         },
+                        rest_bends: Vec::new(),
                     },
                     rope_end_1,
                     rope_end_2,
@@ -367,41 +386,154 @@ impl Recipe {
                     &mut graph.l_shape,
                     &mut graph.graph,
                 );
-                match b1 {
-                    Some(b1) => {
-                        physics.add_constraint(
-                            phys::ConstraintBuilder::new(rope.first_particle)
-                                .with_target(b1)
-                                .with_target_origin(offset1.into())
-                                .build_attachment(),
-                        );
-                    }
-                    None => {
-                        physics.add_constraint(
-                            phys::ConstraintBuilder::new(rope.first_particle)
-                                .with_target_origin(rope_end_1)
-                                .build_attachment(),
-                        );
-                    }
+                for (end, body, offset, world_point) in [
+                    (phys::RopeEnd::Start, b1, offset1, rope_end_1),
+                    (phys::RopeEnd::End, b2, offset2, rope_end_2),
+                ] {
+                    let anchor = match body {
+                        Some(_) => m::Vec2::from(offset),
+                        None => world_point,
+                    };
+                    phys::attach_rope(
+                        physics,
+                        rope.rope_node,
+                        end,
+                        body,
+                        anchor,
+                        &graph.l_body,
+                        &graph.l_rope,
+                        &graph.graph,
+                    );
                 }
-                match b2 {
-                    Some(b2) => {
-                        physics.add_constraint(
-                            phys::ConstraintBuilder::new(rope.last_particle)
-                                .with_target(b2)
-                                .with_target_origin(offset2.into())
-                                .build_attachment(),
-                        );
-                    }
-                    None => {
-                        physics.add_constraint(
-                            phys::ConstraintBuilder::new(rope.last_particle)
-                                .with_target_origin(rope_end_2)
-                                .build_attachment(),
-                        );
-                    }
+            }
+            Recipe::Rope {
+                points,
+                bezier,
+                anchored_start,
+                anchored_end,
+            } => {
+                let points: Vec<m::Vec2> = points.iter().map(m::Vec2::from).collect();
+                if points.len() < 2 || (*bezier && points.len() < 4) {
+                    println!("Too few points in rope");
+                    return;
+                }
+
+                let path = if *bezier {
+                    phys::RopePath::CubicBezier(&points)
+                } else {
+                    phys::RopePath::Polyline(&points)
+                };
+                let rope = phys::spawn_rope_path(
+                    phys::Rope {
+                        spacing: 0.1,
+                        // computed from the path
+                        length: 0.0,
+                        thickness: 0.12,
+                        compliance: 0.0000001,
+                        bending_max_angle: m::Angle::Deg(15.0).rad(),
+                        bending_compliance: 0.08,
+                        damping: 20.0,
+                        material: Material::default(),
+                        // computed from the path
+                        rest_bends: Vec::new(),
+                    },
+                    path,
+                    0.02,
+                    &mut graph.l_body,
+                    &mut graph.l_pose,
+                    &mut graph.l_collider,
+                    &mut graph.l_rope,
+                    &mut graph.l_shape,
+                    &mut graph.graph,
+                );
+                if *anchored_start {
+                    phys::attach_rope(
+                        physics,
+                        rope.rope_node,
+                        phys::RopeEnd::Start,
+                        None,
+                        points[0],
+                        &graph.l_body,
+                        &graph.l_rope,
+                        &graph.graph,
+                    );
+                }
+                if *anchored_end {
+                    let end = graph
+                        .l_pose
+                        .get(rope.last_particle.check(&graph.graph).unwrap())
+                        .translation;
+                    phys::attach_rope(
+                        physics,
+                        rope.rope_node,
+                        phys::RopeEnd::End,
+                        None,
+                        end,
+                        &graph.l_body,
+                        &graph.l_rope,
+                        &graph.graph,
+                    );
                 }
             }
+            Recipe::Terrain { points, closed } => {
+                if points.len() < 2 {
+                    println!("Too few points in terrain");
+                    return;
+                }
+
+                let points: Vec<m::Vec2> = points.iter().map(m::Vec2::from).collect();
+                let segments = phys::spawn_edge_chain(
+                    &points,
+                    *closed,
+                    Material::default(),
+                    &mut graph.l_collider,
+                    &mut graph.graph,
+                );
+                // edges are in world space, give them an identity pose to render them
+                for segment in segments {
+                    let coll = *graph.l_collider.get(segment.check(&graph.graph).unwrap());
+                    let pose_node = graph.l_pose.insert(m::Pose::identity(), &mut graph.graph);
+                    let shape_node = graph
+                        .l_shape
+                        .insert(gx::Shape::from_collider(&coll, [0.5; 4]), &mut graph.graph);
+                    graph
+                        .graph
+                        .connect(&pose_node, &segment.check(&graph.graph).unwrap());
+                    graph.graph.connect(&pose_node, &shape_node);
+                }
+            }
+            Recipe::SoftBody {
+                points,
+                spacing,
+                area_compliance,
+            } => {
+                if points.len() < 3 {
+                    println!("Too few points in soft body");
+                    return;
+                }
+
+                let points: Vec<m::Vec2> = points.iter().map(m::Vec2::from).collect();
+                phys::spawn_soft_body(
+                    phys::SoftBody {
+                        spacing: *spacing,
+                        thickness: 0.1,
+                        compliance: 0.000001,
+                        rest_area: 0.0,
+                        area_compliance: *area_compliance,
+                        damping: 5.0,
+                        material: Material::default(),
+                        layer: 0,
+                    },
+                    &points,
+                    0.05,
+                    &mut graph.l_body,
+                    &mut graph.l_pose,
+                    &mut graph.l_collider,
+                    &mut graph.l_soft_body,
+                    &mut graph.l_shape,
+                    &mut graph.graph,
+                );
+            }
         }
     }
 }
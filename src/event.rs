@@ -10,11 +10,26 @@ use super::graph as g;
 ///
 /// This is heavily WIP, as not many things in Starframe exist that can produce events yet.
 /// Expect major changes here.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Event {
-    /// A contact happened in the physics system. Received if connected to a
+    /// Two colliders started touching. Received if connected to a
     /// [`Collider`][crate::physics::Collider].
-    Contact(crate::physics::ContactEvent),
+    ContactBegin(crate::physics::ContactEvent),
+    /// Two colliders that touched on the previous frame are still touching.
+    /// Received if connected to a [`Collider`][crate::physics::Collider].
+    ContactPersist(crate::physics::ContactEvent),
+    /// Two colliders stopped touching. Received if connected to a
+    /// [`Collider`][crate::physics::Collider].
+    ContactEnd(crate::physics::ContactEvent),
+    /// A collider started overlapping with a trigger. Received if connected to
+    /// either the trigger or the other [`Collider`][crate::physics::Collider].
+    TriggerEnter(crate::physics::TriggerEvent),
+    /// A collider stopped overlapping with a trigger. Received if connected to
+    /// either the trigger or the other [`Collider`][crate::physics::Collider].
+    TriggerExit(crate::physics::TriggerEvent),
+    /// A constraint broke because of too much force. Received if connected to the
+    /// [`Body`][crate::physics::Body] that owns the constraint.
+    ConstraintBroken(crate::physics::ConstraintBrokenEvent),
 }
 
 /// A function that consumes events.
@@ -52,7 +67,7 @@ impl<Params> EventGraph<Params> {
         &mut self,
         consumer: Consumer<Params>,
         graph: &mut g::Graph,
-    ) -> g::NodeRef<EventSink> {
+    ) -> g::NodeRef<'_, EventSink> {
         let sink = self.sinks.insert(EventSink::new(), graph);
 
         use g::UnsafeNode;
@@ -93,6 +108,7 @@ impl<Params> EventGraph<Params> {
 
 /// A component that gathers events that occur to the components
 /// it's connected to in the graph.
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct EventSink {
     events: Vec<Event>,
 }
@@ -106,6 +122,12 @@ impl EventSink {
     pub fn push(&mut self, evt: Event) {
         self.events.push(evt);
     }
+
+    /// Take the events gathered so far without going through an `EventGraph`.
+    #[cfg(test)]
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.events.drain(..)
+    }
 }
 
 impl Default for EventSink {
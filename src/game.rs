@@ -31,7 +31,7 @@ fn should_snap(dt: u128, target: u128) -> bool {
 /// and handles timing of the game loop.
 ///
 /// # Example
-/// ```
+/// ```no_run
 /// # use starframe::{game::{Game, GameState}, graphics::Renderer};
 /// # struct MyState;
 /// # impl MyState {
@@ -62,7 +62,9 @@ pub trait SafeNode: UnsafeNode + Sized {
 }
 
 /// The position in the graph of a node of any type.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
 pub struct NodePosition {
     pub(crate) layer_idx: LayerIdx,
     pub(crate) item_idx: ComponentIdx,
@@ -80,16 +82,20 @@ impl UnsafeNode for NodePosition {
 /// and a generation index to check that the node hasn't been deleted.
 /// An example use case could be to use them as a kind of "weak pointer" connecting together different objects
 /// where you don't want both to be deleted if one is.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound = "")]
 pub struct Node<T> {
     pos: NodePosition,
     gen: GenerationIdx,
-    _marker: PhantomData<*const T>,
+    // a node doesn't own or point to a T, so it can be sent between threads regardless of T
+    _marker: PhantomData<fn() -> T>,
 }
 impl<T> Node<T> {
     /// Returns a `CheckedNode`, which implements `SafeNode` and can be used in graph operations,
     /// or `None` if the node has been deleted from the graph.
     pub fn check(&self, graph: &Graph) -> Option<CheckedNode<'_, T>> {
-        if graph.generations[self.pos.layer_idx][self.pos.item_idx] == self.gen {
+        // the slot may not exist if the graph was restored from an older snapshot
+        if graph.generations[self.pos.layer_idx].get(self.pos.item_idx) == Some(&self.gen) {
             Some(CheckedNode { node: self })
         } else {
             None
@@ -104,11 +110,7 @@ impl<T> UnsafeNode for Node<T> {
 // blanket impls required because derive restricts type of T
 impl<T> Clone for Node<T> {
     fn clone(&self) -> Self {
-        Node {
-            pos: self.pos,
-            gen: self.gen,
-            _marker: PhantomData,
-        }
+        *self
     }
 }
 impl<T> Copy for Node<T> {}
@@ -126,6 +128,16 @@ impl<T> PartialEq for Node<T> {
     }
 }
 impl<T> Eq for Node<T> {}
+impl<T> PartialOrd for Node<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T> Ord for Node<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.pos, self.gen).cmp(&(other.pos, other.gen))
+    }
+}
 impl<T> std::hash::Hash for Node<T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.pos.hash(state);
@@ -153,7 +165,7 @@ impl<'a, T> SafeNode for CheckedNode<'a, T> {
 /// If all connections are already gone when you call unpin, the node will immediately be deleted.
 pub struct PinnedNode<T> {
     pos: NodePosition,
-    _marker: PhantomData<*const T>,
+    _marker: PhantomData<fn() -> T>,
 }
 impl<T> UnsafeNode for PinnedNode<T> {
     fn pos(&self) -> NodePosition {
@@ -175,7 +187,7 @@ impl<'a, T> std::ops::Deref for NodeRef<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        &self.item
+        self.item
     }
 }
 impl<'a, T> UnsafeNode for NodeRef<'a, T> {
@@ -207,12 +219,12 @@ impl<'a, T> std::ops::Deref for NodeRefMut<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        &self.item
+        self.item
     }
 }
 impl<'a, T> std::ops::DerefMut for NodeRefMut<'a, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.item
+        self.item
     }
 }
 impl<'a, T> UnsafeNode for NodeRefMut<'a, T> {
@@ -243,13 +255,13 @@ impl<'a, T> NodeRefMut<'a, T> {
 /// The usual way to use this is to define a struct containing a `Graph` and a `Layer` for each component type:
 /// ```
 /// # use starframe::graph::{Graph, Layer};
-/// # use starframe::physics::{Collider, RigidBody};
+/// # use starframe::physics::{Collider, Body};
 /// # use starframe::math::Pose;
 /// struct MyGraph {
 ///     graph: Graph,
 ///     l_pose: Layer<Pose>,
 ///     l_collider: Layer<Collider>,
-///     l_body: Layer<RigidBody>,
+///     l_body: Layer<Body>,
 ///     // etc.
 /// }
 /// impl MyGraph {
@@ -270,18 +282,20 @@ impl<'a, T> NodeRefMut<'a, T> {
 /// If multiple instances of `Graph` exist in one program,
 /// care must be taken not to mix nodes or layers from different instances.
 /// Doing so will either panic or cause strange behavior depending on what's in the two graphs.
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Graph {
     /// 3D array:
     /// * 1st dimension is the starting layer
     /// * 2nd dimension is the target layer
     /// * 3rd dimension is the component on the starting layer
     /// * and the stored value is the index of the component on the ending layer
+    ///
     /// used to connect nodes
     edge_layers: Vec<Vec<Vec<Option<ComponentIdx>>>>,
     /// 2D array:
     /// * 1st dimension is the layer
     /// * 2nd dimension is the component
+    ///
     /// used to determine if an object is dead or alive
     refcounts: Vec<Vec<Refcount>>,
     /// Same structure as above,
@@ -325,6 +339,11 @@ impl Graph {
         }
     }
 
+    /// Number of layers created in this graph.
+    pub(crate) fn layer_count(&self) -> usize {
+        self.edge_layers.len()
+    }
+
     /// Create two edges, one in both directions, between two nodes.
     ///
     /// This makes both nodes hierarchically equal parts of the same object.
@@ -391,6 +410,28 @@ impl Graph {
         self.refcounts[end.layer_idx][end.item_idx] += 1;
     }
 
+    /// Remove an edge from one node to another created with `connect_oneway`.
+    ///
+    /// Nothing is deleted even if this removes the last edge pointing to the end node,
+    /// so make sure it gets connected to something else or deleted afterwards.
+    ///
+    /// # Panics
+    /// Panics if there is no edge from `start` to `end`.
+    pub fn disconnect_oneway(&mut self, start: &impl SafeNode, end: &impl SafeNode) {
+        self.disconnect_oneway_unchecked(start, end)
+    }
+
+    pub fn disconnect_oneway_unchecked(&mut self, start: &impl UnsafeNode, end: &impl UnsafeNode) {
+        let start = start.pos();
+        let end = end.pos();
+        match self.edge_layers[start.layer_idx][end.layer_idx].get_mut(start.item_idx) {
+            Some(edge) if *edge == Some(end.item_idx) => *edge = None,
+            _ => panic!("Attempted to remove an edge that doesn't exist."),
+        }
+
+        self.refcounts[end.layer_idx][end.item_idx] -= 1;
+    }
+
     /// If an edge from the given node to the target layer exists, returns the node it points to.
     /// This method takes a node type that implements `SafeNode`, meaning it knows it's currently alive.
     /// See the docs for `SafeNode` and the available node types.
@@ -645,7 +686,7 @@ struct VisitedNode {
 /// Components are stored contiguously in a Vec<T>.
 #[derive(Debug)]
 pub struct Layer<T> {
-    index: LayerIdx,
+    pub(crate) index: LayerIdx,
     pub(crate) content: Vec<T>,
 }
 
@@ -714,12 +755,12 @@ impl<T> Layer<T> {
     ///
     /// You can use this with `Graph::get_neighbor` to iterate over patterns in the graph:
     /// ```
-    /// # use starframe::{math::Pose, physics::{Collider, RigidBody}, graph::{Graph, Layer}};
+    /// # use starframe::{math::Pose, physics::{Collider, Body}, graph::{Graph, Layer}};
     /// # let mut graph = Graph::new();
-    /// # let l_rigidbody: Layer<RigidBody> = graph.create_layer();
+    /// # let l_body: Layer<Body> = graph.create_layer();
     /// # let l_pose: Layer<Pose> = graph.create_layer();
     /// # let l_collider: Layer<Collider> = graph.create_layer();
-    /// for body in l_rigidbody.iter(&graph) {
+    /// for body in l_body.iter(&graph) {
     ///     let pose = match graph.get_neighbor(&body, &l_pose) {
     ///         Some(pose) => pose,
     ///         None => continue,
@@ -763,7 +804,7 @@ impl<'a, T> Iterator for LayerIter<'a, T> {
     type Item = NodeRef<'a, T>;
     fn next(&mut self) -> Option<Self::Item> {
         let (item_idx, item) = self.iter.next()?;
-        if self.refcounts[item_idx] > 0 {
+        if matches!(self.refcounts.get(item_idx), Some(&refcount) if refcount > 0) {
             Some(NodeRef {
                 item,
                 pos: NodePosition {
@@ -787,7 +828,7 @@ impl<'a, T> Iterator for LayerIterMut<'a, T> {
     type Item = NodeRefMut<'a, T>;
     fn next(&mut self) -> Option<Self::Item> {
         let (item_idx, item) = self.iter.next()?;
-        if self.refcounts[item_idx] > 0 {
+        if matches!(self.refcounts.get(item_idx), Some(&refcount) if refcount > 0) {
             Some(NodeRefMut {
                 item,
                 pos: NodePosition {
@@ -911,7 +952,7 @@ mod tests {
 
         let mut match_count = 0; // not including shape
         let mut full_match_count = 0; // including shape
-        for mut rb in rbs.iter_mut(&graph) {
+        for rb in rbs.iter_mut(&graph) {
             let pose = match graph.get_neighbor(&rb, &poses) {
                 Some(pose) => pose,
                 None => continue,
@@ -959,6 +1000,31 @@ mod tests {
         }
     }
 
+    /// Removed edges are no longer followed and can be replaced.
+    #[test]
+    fn disconnect_nodes() {
+        let mut graph = Graph::new();
+        let mut rbs: Layer<RigidBody> = graph.create_layer();
+        let mut shapes: Layer<Shape> = graph.create_layer();
+
+        let rb_node = rbs.insert(RigidBody(0), &mut graph).pin(&mut graph);
+        let shape_1 = shapes.insert(Shape(1), &mut graph).pin(&mut graph);
+        let shape_2 = shapes.insert(Shape(2), &mut graph).pin(&mut graph);
+
+        graph.connect_oneway(&rb_node, &shape_1);
+        assert_eq!(graph.get_refcount(&shape_1), 2);
+        graph.disconnect_oneway(&rb_node, &shape_1);
+        assert_eq!(graph.get_refcount(&shape_1), 1);
+        assert!(graph.get_neighbor(&rb_node, &shapes).is_none());
+
+        // this would panic if the old edge was still there
+        graph.connect_oneway(&rb_node, &shape_2);
+        assert_eq!(
+            graph.get_neighbor(&rb_node, &shapes).unwrap().item,
+            &Shape(2)
+        );
+    }
+
     #[test]
     fn delete() {
         let mut graph = Graph::new();
@@ -74,16 +74,13 @@ impl MouseDragCamera {
             (Some(DragState::InProgress { .. }), None) => self.drag_start = Some(self.pose),
             (Some(DragState::InProgress { start, .. }), Some(pose_at_start)) => {
                 let cursor_pos = input_cache.cursor_position().get();
-                let offset = m::Vec2::new(
-                    (cursor_pos.x - start.x) as f64,
-                    -(cursor_pos.y - start.y) as f64,
-                );
+                let offset = m::Vec2::new(cursor_pos.x - start.x, -(cursor_pos.y - start.y));
                 self.pose = pose_at_start;
                 self.pose
                     .append_translation(-offset * self.pose.scale / scaling_factor);
             }
             (Some(DragState::Completed { start, end, .. }), Some(pose_at_start)) => {
-                let offset = m::Vec2::new((end.x - start.x) as f64, -(end.y - start.y) as f64);
+                let offset = m::Vec2::new(end.x - start.x, -(end.y - start.y));
                 self.pose = pose_at_start;
                 self.pose
                     .append_translation(-offset * self.pose.scale / scaling_factor);
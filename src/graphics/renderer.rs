@@ -82,7 +82,7 @@ impl Renderer {
     }
 
     /// Begin drawing directly into the game window.
-    pub fn draw_to_window(&mut self) -> RenderContext {
+    pub fn draw_to_window(&mut self) -> RenderContext<'_> {
         let frame = self
             .swap_chain
             .get_current_frame()
@@ -106,6 +106,7 @@ impl Renderer {
 
 enum RenderTarget {
     Window(wgpu::SwapChainTexture),
+    #[allow(dead_code)]
     Texture(wgpu::TextureView),
 }
 impl RenderTarget {
@@ -148,7 +149,7 @@ impl<'a> RenderContext<'a> {
     }
 
     /// Begin a render pass.
-    pub fn pass(&mut self) -> wgpu::RenderPass {
+    pub fn pass(&mut self) -> wgpu::RenderPass<'_> {
         self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
             color_attachments: &[wgpu::RenderPassColorAttachment {
@@ -1,5 +1,6 @@
 use crate::{
     graphics::{self as gx, util::GlslMat3},
+    physics::Collider,
     {graph, math as m},
 };
 
@@ -10,7 +11,8 @@ type Color = [f32; 4];
 /// A flat-colored convex polygon shape.
 ///
 /// Concavity will not result in an error but will be rendered incorrectly.
-#[derive(Clone, Copy, Debug)]
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Shape {
     Circle {
         r: f64,
@@ -28,10 +30,18 @@ pub enum Shape {
         points_per_cap: usize,
         color: Color,
     },
+    Polygon {
+        poly: crate::physics::collision::Polygon,
+        color: Color,
+    },
 }
 
 impl Shape {
     /// Create a Shape that matches the given Collider.
+    ///
+    /// The shape is in the collider's local space. Connect it to the collider node
+    /// instead of a pose to draw it at the collider's [`offset`][crate::physics::Collider::offset],
+    /// e.g. for each collider of a [compound body][crate::physics::attach_compound].
     pub fn from_collider(coll: &crate::physics::Collider, color: Color) -> Self {
         use crate::physics::collision::ColliderShape;
         match coll.shape {
@@ -51,6 +61,20 @@ impl Shape {
                 points_per_cap: 8,
                 color,
             },
+            ColliderShape::Polygon(poly) => Shape::Polygon { poly, color },
+            ColliderShape::Edge(edge) => {
+                // edges have no area, draw them as a thin strip on the solid side
+                let solid_side = -0.05 * edge.normal();
+                Shape::Polygon {
+                    poly: crate::physics::collision::Polygon::new(&[
+                        edge.start,
+                        edge.end,
+                        edge.end + solid_side,
+                        edge.start + solid_side,
+                    ]),
+                    color,
+                }
+            }
         }
     }
 
@@ -128,6 +152,7 @@ impl Shape {
 
                 as_verts(verts.as_slice(), pose, *color)
             }
+            Shape::Polygon { poly, color } => as_verts(poly.vertices(), pose, *color),
         }
     }
 }
@@ -266,11 +291,15 @@ impl ShapeRenderer {
         }
     }
 
-    /// Draw all the alive `Shape`s that have associated `Transform`s.
+    /// Draw all the alive `Shape`s that have associated `Pose`s.
+    ///
+    /// Shapes connected to a `Collider` are drawn at the collider's pose and offset,
+    /// others at the pose they're connected to.
     pub fn draw(
         &mut self,
         l_shape: &graph::Layer<Shape>,
         l_pose: &graph::Layer<m::Pose>,
+        l_collider: &graph::Layer<Collider>,
         graph: &graph::Graph,
         camera: &impl gx::camera::Camera,
         ctx: &mut gx::RenderContext,
@@ -291,7 +320,13 @@ impl ShapeRenderer {
 
         let verts: Vec<Vertex> = l_shape
             .iter(graph)
-            .filter_map(|s| graph.get_neighbor(&s, l_pose).map(|tr| s.verts(&*tr)))
+            .filter_map(|s| {
+                let pose = match graph.get_neighbor(&s, l_collider) {
+                    Some(coll) => *graph.get_neighbor(&coll, l_pose)? * coll.offset,
+                    None => *graph.get_neighbor(&s, l_pose)?,
+                };
+                Some(s.verts(&pose))
+            })
             .flatten()
             .collect();
         if verts.is_empty() {
@@ -213,7 +213,7 @@ impl InputCache {
         use ev::MouseScrollDelta::*;
         match delta {
             LineDelta(_, y) => self.scroll_delta += PIXELS_PER_LINE * y as f64,
-            PixelDelta(PhysicalPosition { y, .. }) => self.scroll_delta += y as f64,
+            PixelDelta(PhysicalPosition { y, .. }) => self.scroll_delta += y,
         }
     }
 
@@ -47,7 +47,7 @@ impl From<Rotor2> for Angle {
 }
 
 /// A wrapper type to indicate a vector should always be normalized.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Unit<T>(T);
 
 impl Unit<Vec2> {
@@ -95,6 +95,64 @@ where
     }
 }
 
+/// Serde support for `Pose`, which `ultraviolet` doesn't implement.
+/// Use with `#[serde(with = "math::serde_pose")]`, or `math::serde_pose::option` for an `Option<Pose>`.
+///
+/// The rotation is stored as the components of the rotor rather than an angle
+/// so that poses come back bit for bit the same.
+pub mod serde_pose {
+    use super::{Pose, Rotor2, Vec2};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Clone, Copy, Serialize, Deserialize)]
+    pub(crate) struct PoseData {
+        translation: Vec2,
+        rotation: [f64; 2],
+    }
+    impl From<&Pose> for PoseData {
+        fn from(pose: &Pose) -> Self {
+            Self {
+                translation: pose.translation,
+                rotation: [pose.rotation.s, pose.rotation.bv.xy],
+            }
+        }
+    }
+    impl From<PoseData> for Pose {
+        fn from(data: PoseData) -> Self {
+            let mut rotation = Rotor2::identity();
+            rotation.s = data.rotation[0];
+            rotation.bv.xy = data.rotation[1];
+            Pose::new(data.translation, rotation)
+        }
+    }
+
+    pub fn serialize<S: Serializer>(pose: &Pose, serializer: S) -> Result<S::Ok, S::Error> {
+        PoseData::from(pose).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pose, D::Error> {
+        PoseData::deserialize(deserializer).map(Pose::from)
+    }
+
+    pub mod option {
+        use super::{Pose, PoseData};
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            pose: &Option<Pose>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            pose.as_ref().map(PoseData::from).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Pose>, D::Error> {
+            Option::<PoseData>::deserialize(deserializer).map(|data| data.map(Pose::from))
+        }
+    }
+}
+
 /// A builder useful for deserializing isometries from RON files.
 #[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
@@ -6,12 +6,15 @@ use crate::{
 
 use itertools::izip;
 use slotmap as sm;
+use std::collections::{BTreeMap, BTreeSet};
 
 //
 
 pub mod collision;
 use collision::{shape_shape::intersection_check, HGrid};
-pub use collision::{Collider, ColliderType, Contact, ContactResult, Material};
+pub use collision::{
+    Collider, ColliderType, CombineMode, Contact, ContactResult, Fluid, MassProperties, Material,
+};
 
 mod constraint;
 pub use constraint::*;
@@ -22,9 +25,31 @@ pub use forcefield::ForceField;
 mod body;
 pub use body::*;
 
+mod character;
+pub use character::*;
+
+mod compound;
+pub use compound::*;
+
+mod edge_chain;
+pub use edge_chain::*;
+
+mod fluid;
+
+mod island;
+
 mod rope;
 pub use rope::*;
 
+mod snapshot;
+pub use snapshot::{SnapshotError, SnapshotLayer};
+
+mod soft_body;
+pub use soft_body::*;
+
+#[cfg(test)]
+mod tests;
+
 //
 
 #[cfg(feature = "tracy")]
@@ -37,7 +62,7 @@ static CONTACTS_PLOT: tracy_client::Plot = tracy_client::create_plot!("contacts"
 /// Velocity of an object.
 ///
 // Equivalent to a Vec3 but with names for the translational and rotational part.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Velocity {
     /// Linear velocity in metres per second.
     pub linear: m::Vec2,
@@ -94,14 +119,103 @@ impl std::ops::Mul<f64> for Velocity {
     }
 }
 
-/// Events produced by the physics system when two physics objects collide.
+/// Events produced by the physics system when two solid colliders touch.
+/// See [`TriggerEvent`][self::TriggerEvent] for triggers.
 ///
-/// Note: for now this doesn't tell you anything except which body the contact was with.
-/// This isn't great, but that's the only thing I've needed so far.
-/// More info will be added when needed in a project.
-#[derive(Clone, Copy, Debug)]
+/// These are delivered as [`Event::ContactBegin`][crate::event::Event::ContactBegin]
+/// on the first frame of a contact, [`Event::ContactPersist`][crate::event::Event::ContactPersist]
+/// on every following frame, and [`Event::ContactEnd`][crate::event::Event::ContactEnd]
+/// when the colliders separate. Contacts between sleeping bodies persist silently.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct ContactEvent {
-    /// The collider that this body was in contact with.
+    /// The collider receiving this event. Useful when one sink is connected to
+    /// several colliders of a compound body.
+    pub collider: graph::Node<Collider>,
+    /// The collider that this collider was in contact with.
+    pub other_collider: graph::Node<Collider>,
+    /// Contact normal, pointing away from this collider towards the other.
+    pub normal: m::Unit<m::Vec2>,
+    /// Total impulse applied along the normal during the frame, in N⋅s.
+    ///
+    /// Zero in `ContactEnd` events.
+    pub impulse: f64,
+    points: [m::Vec2; 2],
+    point_count: usize,
+}
+
+impl ContactEvent {
+    /// Points of contact in world space. There are one or two of these.
+    ///
+    /// In `ContactEnd` events these are from the last frame the colliders touched.
+    pub fn points(&self) -> &[m::Vec2] {
+        &self.points[..self.point_count]
+    }
+
+    /// The same event from the other collider's point of view.
+    fn flipped(&self) -> Self {
+        Self {
+            collider: self.other_collider,
+            other_collider: self.collider,
+            normal: -self.normal,
+            ..*self
+        }
+    }
+}
+
+/// Event produced when a constraint's force or torque exceeds its
+/// [`break_force`][constraint::Constraint::break_force] or
+/// [`break_torque`][constraint::Constraint::break_torque] and it's removed from the system.
+/// Received by an [`EventSink`][crate::event::EventSink] connected to the constraint's owner body.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ConstraintBrokenEvent {
+    /// The handle the constraint had. It's no longer valid.
+    pub handle: ConstraintHandle,
+    /// The constraint as it was when it broke.
+    pub constraint: Constraint,
+}
+
+/// A hook for inspecting and modifying contacts between solid colliders
+/// after they're detected and before they're solved.
+///
+/// Set one with [`Physics::with_contact_modifier`][self::Physics::with_contact_modifier].
+/// Modifiers must be `Send` so that `Physics` can be moved to other threads.
+pub trait ContactModifier: Send {
+    /// Called every substep for every pair of colliders that are touching.
+    fn modify_contacts(
+        &mut self,
+        nodes: [graph::Node<Collider>; 2],
+        colliders: [&Collider; 2],
+        modification: &mut ContactModification,
+    );
+}
+
+/// Contacts between a pair of colliders given to a [`ContactModifier`][self::ContactModifier].
+#[derive(Clone, Copy, Debug)]
+pub struct ContactModification {
+    /// The contacts found by collision detection. Normals point away from the first collider.
+    /// Remove contacts by replacing this with a `ContactResult` with fewer of them.
+    pub contacts: ContactResult,
+    /// Friction and restitution used to solve the contacts.
+    pub coefficients: collision::MaterialPair,
+}
+
+impl ContactModification {
+    /// Disable the contacts entirely, letting the colliders pass through each other.
+    pub fn disable(&mut self) {
+        self.contacts = ContactResult::Zero;
+    }
+}
+
+/// Events produced by the physics system when a collider enters or exits a trigger.
+///
+/// These are delivered as [`Event::TriggerEnter`][crate::event::Event::TriggerEnter]
+/// and [`Event::TriggerExit`][crate::event::Event::TriggerExit]
+/// to both the trigger and the collider that entered it.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TriggerEvent {
+    /// The collider receiving this event.
+    pub collider: graph::Node<Collider>,
+    /// The other collider in the overlap.
     pub other_collider: graph::Node<Collider>,
 }
 
@@ -112,27 +226,112 @@ sm::new_key_type! {
 pub struct Physics {
     pub substeps: usize,
     pub mask_matrix: collision::MaskMatrix,
+    /// Coefficients for specific pairs of materials.
+    pub material_pairs: collision::MaterialPairTable,
+    /// Parameters for putting resting bodies to sleep. `None`, the default, disables sleeping.
+    pub sleep_params: Option<SleepParams>,
     user_constraints: sm::DenseSlotMap<ConstraintHandle, Constraint>,
+    // handles of user constraints in the order they were added, which is the order they're solved in.
+    // the slotmap's own iteration order depends on its history of removals
+    // and isn't preserved when it's deserialized, so it can't be relied on for determinism.
+    // removed constraints are only cleaned out of this at the start of the next tick
+    // so that removing many constraints doesn't take quadratic time
+    constraint_order: Vec<ConstraintHandle>,
+    // the following are ordered collections so that events come out in the same order every time.
+    // contacts from the previous frame, from the point of view of the first collider
+    active_contacts: BTreeMap<[graph::Node<Collider>; 2], ContactEvent>,
+    // pairs of overlapping colliders where at least one is a trigger
+    trigger_pairs: BTreeSet<[graph::Node<Collider>; 2]>,
+    // touching pairs involving a one-way collider, and whether they're passing through
+    one_way_pairs: BTreeMap<[graph::Node<Collider>; 2], bool>,
+    contact_modifier: Option<Box<dyn ContactModifier>>,
+    // bodies whose constraints were changed since the last tick, to be woken up
+    wake_queue: Vec<graph::Node<Body>>,
     spatial_index: HGrid,
     working_bufs: WorkingBuffers,
 }
 
+/// Parameters controlling when bodies go to sleep.
+///
+/// Bodies are grouped into islands of things that touch or are constrained together.
+/// When every body in an island has moved slower than the thresholds for long enough,
+/// the whole island goes to sleep and is skipped by the simulation
+/// until something awake touches it, a moving kinematic body touches it or is constrained to it,
+/// one of its constraints is changed, or one of its bodies is given a velocity.
+#[derive(Clone, Copy, Debug)]
+pub struct SleepParams {
+    /// Linear speed in metres per second below which a body is considered resting.
+    pub linear_threshold: f64,
+    /// Angular speed in radians per second below which a body is considered resting.
+    pub angular_threshold: f64,
+    /// Time in seconds an island has to be resting before it goes to sleep.
+    pub time_until_sleep: f64,
+}
+
+impl Default for SleepParams {
+    fn default() -> Self {
+        Self {
+            linear_threshold: 0.05,
+            angular_threshold: 0.05,
+            time_until_sleep: 0.5,
+        }
+    }
+}
+
 /// Cached buffers to avoid allocating a bunch of memory every frame
 struct WorkingBuffers {
     node_ref_map: Vec<usize>,
     rope_next_particles: Vec<Option<usize>>,
     rope_prev_particles: Vec<Option<usize>>,
     rope_lateral_corrections: Vec<Option<m::Vec2>>,
+    // rest length of the first segment of each rope, which changes when it's reeled in or out
+    rope_first_segment_lengths: Vec<f64>,
+    // particles of every soft body in order, and the range of each soft body in it
+    soft_body_particles: Vec<usize>,
+    soft_body_ranges: Vec<std::ops::Range<usize>>,
+    // index of the soft body each body is a particle of
+    soft_body_of: Vec<Option<usize>>,
+    // pairs of a soft body and a rigid collider near its surface,
+    // stored as (index into soft_body_ranges, collider item index)
+    soft_body_surface_pairs: Vec<(usize, usize)>,
     old_poses: Vec<m::Pose>,
     pre_contact_poses: Vec<m::Pose>,
     poses: Vec<m::Pose>,
     old_velocities: Vec<Velocity>,
     velocities: Vec<Velocity>,
     ext_f_accelerations: Vec<m::Vec2>,
+    // pairs of a fluid and a collider attached to a dynamic body,
+    // stored as (index into coll_pairs, index of the fluid within the pair)
+    fluid_pairs: Vec<(usize, usize)>,
+    // buoyancy and drag from fluids, force and torque for each body
+    fluid_forces: Vec<(m::Vec2, f64)>,
     constraint_body_pairs: Vec<(usize, Option<usize>)>,
+    // constraint offsets relative to each body's center of mass
+    constraint_offsets: Vec<[m::Vec2; 2]>,
+    constraints_broken: Vec<bool>,
     coll_ctxs: Vec<ColliderContext>,
+    // poses of colliders attached to bodies relative to the body's center of mass
+    coll_offsets: Vec<m::Pose>,
     contacts: Vec<ContactResult>,
     contact_lambdas: Vec<f64>,
+    // last nonzero contact of each pair during the frame, for events
+    frame_contacts: Vec<ContactResult>,
+    contact_impulses: Vec<f64>,
+    // coefficients for each pair after contact modification, used in the velocity step
+    contact_coefs: Vec<collision::MaterialPair>,
+    // whether pairs involving a one-way collider are passing through, None if undecided
+    one_way_states: Vec<Option<bool>>,
+    // whether pairs involving a one-way collider were touching in the last substep
+    one_way_touching: Vec<bool>,
+    // colliders attached to bodies with continuous collision detection,
+    // stored as (collider item index, body index)
+    ccd_colliders: Vec<(usize, usize)>,
+    ccd_times_of_impact: Vec<f64>,
+    islands: island::Islands,
+    island_flags: Vec<bool>,
+    island_timers: Vec<f64>,
+    sleeping: Vec<bool>,
+    sleep_timers: Vec<f64>,
 }
 impl WorkingBuffers {
     fn new() -> Self {
@@ -141,21 +340,68 @@ impl WorkingBuffers {
             rope_next_particles: Vec::new(),
             rope_prev_particles: Vec::new(),
             rope_lateral_corrections: Vec::new(),
+            rope_first_segment_lengths: Vec::new(),
+            soft_body_particles: Vec::new(),
+            soft_body_ranges: Vec::new(),
+            soft_body_of: Vec::new(),
+            soft_body_surface_pairs: Vec::new(),
             old_poses: Vec::new(),
             pre_contact_poses: Vec::new(),
             poses: Vec::new(),
             old_velocities: Vec::new(),
             velocities: Vec::new(),
             ext_f_accelerations: Vec::new(),
+            fluid_pairs: Vec::new(),
+            fluid_forces: Vec::new(),
             constraint_body_pairs: Vec::new(),
+            constraint_offsets: Vec::new(),
+            constraints_broken: Vec::new(),
             coll_ctxs: Vec::new(),
+            coll_offsets: Vec::new(),
             contacts: Vec::new(),
             contact_lambdas: Vec::new(),
+            frame_contacts: Vec::new(),
+            contact_impulses: Vec::new(),
+            contact_coefs: Vec::new(),
+            one_way_states: Vec::new(),
+            one_way_touching: Vec::new(),
+            ccd_colliders: Vec::new(),
+            ccd_times_of_impact: Vec::new(),
+            islands: Default::default(),
+            island_flags: Vec::new(),
+            island_timers: Vec::new(),
+            sleeping: Vec::new(),
+            sleep_timers: Vec::new(),
         }
     }
 }
 /// poses are in our temporary buffer for colliders attached to bodies,
 /// but for static colliders they're in the graph.
+/// A mutable reference to a user constraint that wakes up the constrained bodies
+/// the first time the constraint is modified through it.
+pub struct ConstraintMut<'a> {
+    constraint: &'a mut Constraint,
+    wake_queue: &'a mut Vec<graph::Node<Body>>,
+    woken: bool,
+}
+impl<'a> std::ops::Deref for ConstraintMut<'a> {
+    type Target = Constraint;
+
+    fn deref(&self) -> &Self::Target {
+        self.constraint
+    }
+}
+impl<'a> std::ops::DerefMut for ConstraintMut<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if !self.woken {
+            self.wake_queue.push(self.constraint.owner);
+            self.wake_queue.extend(self.constraint.target);
+            self.woken = true;
+        }
+        self.constraint
+    }
+}
+
 /// because we don't modify non-body poses, we can get the poses for static colliders just once
 #[derive(Clone, Copy, Debug)]
 enum ColliderContext {
@@ -168,7 +414,15 @@ impl Physics {
         Physics {
             substeps: 10,
             mask_matrix: Default::default(),
+            material_pairs: Default::default(),
+            sleep_params: None,
             user_constraints: sm::DenseSlotMap::with_key(),
+            constraint_order: Vec::new(),
+            active_contacts: BTreeMap::new(),
+            trigger_pairs: BTreeSet::new(),
+            one_way_pairs: BTreeMap::new(),
+            contact_modifier: None,
+            wake_queue: Vec::new(),
             spatial_index: HGrid::new(grid_params),
             working_bufs: WorkingBuffers::new(),
         }
@@ -180,9 +434,34 @@ impl Physics {
         self
     }
 
+    /// Enable sleeping with the given parameters, or disable it with `None`.
+    pub fn with_sleep_params(mut self, params: Option<SleepParams>) -> Self {
+        self.sleep_params = params;
+        self
+    }
+
+    /// Set a hook that can inspect and modify contacts before they're solved.
+    pub fn with_contact_modifier(mut self, modifier: impl ContactModifier + 'static) -> Self {
+        self.set_contact_modifier(Some(Box::new(modifier)));
+        self
+    }
+
+    /// Set or remove the hook that can inspect and modify contacts before they're solved.
+    pub fn set_contact_modifier(&mut self, modifier: Option<Box<dyn ContactModifier>>) {
+        self.contact_modifier = modifier;
+    }
+
+    fn wake_constraint_bodies(&mut self, constraint: &Constraint) {
+        self.wake_queue.push(constraint.owner);
+        self.wake_queue.extend(constraint.target);
+    }
+
     /// Add a user-defined constraint to the system. Returns a handle that can be used to remove it later.
     pub fn add_constraint(&mut self, constraint: Constraint) -> ConstraintHandle {
-        self.user_constraints.insert(constraint)
+        self.wake_constraint_bodies(&constraint);
+        let handle = self.user_constraints.insert(constraint);
+        self.constraint_order.push(handle);
+        handle
     }
 
     /// Access a constraint if it still exists.
@@ -191,8 +470,15 @@ impl Physics {
     }
 
     /// Mutably access a constraint if it still exists.
-    pub fn get_constraint_mut(&mut self, handle: ConstraintHandle) -> Option<&mut Constraint> {
-        self.user_constraints.get_mut(handle)
+    ///
+    /// Modifying the constraint through the returned reference
+    /// wakes up the constrained bodies if they were sleeping.
+    pub fn get_constraint_mut(&mut self, handle: ConstraintHandle) -> Option<ConstraintMut<'_>> {
+        Some(ConstraintMut {
+            constraint: self.user_constraints.get_mut(handle)?,
+            wake_queue: &mut self.wake_queue,
+            woken: false,
+        })
     }
 
     /// Remove a constraint from the system. Returns the constraint if it still existed.
@@ -201,15 +487,26 @@ impl Physics {
     /// are destroyed, so it's not guaranteed the constraint will exist
     /// even if it hasn't been explicitly removed before.
     pub fn remove_constraint(&mut self, handle: ConstraintHandle) -> Option<Constraint> {
-        self.user_constraints.remove(handle)
+        let constraint = self.user_constraints.remove(handle)?;
+        self.wake_constraint_bodies(&constraint);
+        Some(constraint)
     }
 
     /// Remove all constraints.
     pub fn clear_constraints(&mut self) {
-        self.user_constraints.clear();
+        for (_, constraint) in self.user_constraints.drain() {
+            self.wake_queue.push(constraint.owner);
+            self.wake_queue.extend(constraint.target);
+        }
+        self.constraint_order.clear();
     }
 
     /// Detect collisions, solve constraint forces and move bodies.
+    ///
+    /// Ticking is deterministic: the same state and inputs give bit for bit the same results
+    /// and events in the same order, on the same build and platform.
+    /// See [`snapshot`][Self::snapshot] for saving and restoring the state.
+    #[allow(clippy::too_many_arguments)]
     pub fn tick(
         &mut self,
         graph: &graph::Graph,
@@ -217,6 +514,7 @@ impl Physics {
         l_body: &mut graph::Layer<Body>,
         l_collider: &graph::Layer<Collider>,
         l_rope: &graph::Layer<Rope>,
+        l_soft_body: &graph::Layer<SoftBody>,
         l_evt_sink: &mut graph::Layer<EventSink>,
         frame_dt: f64,
         forcefield: &impl ForceField,
@@ -240,8 +538,14 @@ impl Physics {
 
         // node_ref_map maps from the position of a node in the graph layer
         // to the position of a node in body_refs
-        // we don't need to clear it because gaps will just never be touched
-        bufs.node_ref_map.resize(body_refs.len(), 0);
+        // we don't need to clear it because gaps will just never be touched.
+        // layers can have holes from deleted nodes, so size it by the highest index
+        let node_ref_map_len = body_refs
+            .iter()
+            .map(|node| node.pos().item_idx + 1)
+            .max()
+            .unwrap_or(0);
+        bufs.node_ref_map.resize(node_ref_map_len, 0);
         for (ref_pos, node) in body_refs.iter().enumerate() {
             bufs.node_ref_map[node.pos().item_idx] = ref_pos;
         }
@@ -250,28 +554,62 @@ impl Physics {
         bufs.rope_next_particles.resize(body_refs.len(), None);
         bufs.rope_prev_particles.clear();
         bufs.rope_prev_particles.resize(body_refs.len(), None);
+        bufs.rope_first_segment_lengths.clear();
         for rope_node in l_rope.iter(graph) {
+            let (length, spacing) = (rope_node.length, rope_node.spacing);
             let node_ref_map = &bufs.node_ref_map;
             let mut iter = RopeIter::new(rope_node, l_body, graph)
                 .map(|node| node_ref_map[node.pos().item_idx])
                 .peekable();
+            let mut segment_count = 0;
             while let Some(particle) = iter.next() {
                 if let Some(next_particle) = iter.peek() {
                     bufs.rope_next_particles[particle] = Some(*next_particle);
                     bufs.rope_prev_particles[*next_particle] = Some(particle);
+                    segment_count += 1;
                 }
             }
+            // the first segment takes up whatever length the others don't
+            let other_segments = segment_count.max(1) - 1;
+            bufs.rope_first_segment_lengths
+                .push((length - spacing * other_segments as f64).max(0.0));
         }
         // store lateral position corrections (bending resistance) for velocity correction
         bufs.rope_lateral_corrections.clear();
         bufs.rope_lateral_corrections.resize(body_refs.len(), None);
 
+        // gather soft body particles into a flat list
+        let soft_body_refs: Vec<graph::NodeRef<SoftBody>> = l_soft_body.iter(graph).collect();
+        bufs.soft_body_particles.clear();
+        bufs.soft_body_ranges.clear();
+        bufs.soft_body_of.clear();
+        bufs.soft_body_of.resize(body_refs.len(), None);
+        for (sbi, soft_body) in soft_body_refs.iter().enumerate() {
+            let start = bufs.soft_body_particles.len();
+            for particle in SoftBodyIter::new(*soft_body, l_body, graph) {
+                let particle = bufs.node_ref_map[particle.pos().item_idx];
+                bufs.soft_body_particles.push(particle);
+                bufs.soft_body_of[particle] = Some(sbi);
+            }
+            // a soft body needs at least a triangle to enclose any area,
+            // skip broken ones with particles deleted
+            if bufs.soft_body_particles.len() - start < 3 {
+                bufs.soft_body_particles.truncate(start);
+            }
+            bufs.soft_body_ranges
+                .push(start..bufs.soft_body_particles.len());
+        }
+
+        // the solver works with poses of the center of mass,
+        // these are converted back to body poses at the end
         bufs.old_poses.clear();
-        bufs.old_poses.extend(
-            body_refs
-                .iter()
-                .map(|b| *(graph.get_neighbor(b, l_pose)).expect("A Body didn't have a Pose")),
-        );
+        bufs.old_poses.extend(body_refs.iter().map(|b| {
+            let pose = *(graph.get_neighbor(b, l_pose)).expect("A Body didn't have a Pose");
+            m::Pose::new(
+                pose.translation + pose.rotation * b.center_of_mass,
+                pose.rotation,
+            )
+        }));
         // poses after velocity and constraints are applied, used for rope normal correction
         bufs.pre_contact_poses.clear();
         bufs.pre_contact_poses.extend_from_slice(&bufs.old_poses);
@@ -281,7 +619,28 @@ impl Physics {
         // old velocities used for restitution
         bufs.old_velocities.clear();
         bufs.old_velocities
-            .extend(body_refs.iter().map(|body| body.velocity));
+            .extend(body_refs.iter().zip(&bufs.old_poses).map(|(body, pose)| {
+                // kinematic bodies with a target get the velocity that takes them there this frame
+                match body.target_pose {
+                    Some(target) if !body.sees_forces() => {
+                        let target_rot = target.rotation;
+                        let target_pos = target.translation + target_rot * body.center_of_mass;
+                        Velocity {
+                            linear: (target_pos - pose.translation) / frame_dt,
+                            angular: Angle::from(target_rot * pose.rotation.reversed()).rad()
+                                / frame_dt,
+                        }
+                    }
+                    // body velocities are given at the pose origin,
+                    // the solver wants them at the center of mass
+                    _ => Velocity {
+                        linear: body
+                            .velocity
+                            .point_velocity(pose.rotation * body.center_of_mass),
+                        angular: body.velocity.angular,
+                    },
+                }
+            }));
 
         bufs.velocities.clear();
         bufs.velocities.extend_from_slice(&bufs.old_velocities);
@@ -294,24 +653,65 @@ impl Physics {
         // set up user-defined constraints
         //
 
-        // remove constraints where one or both participating bodies have been destroyed
+        // remove constraints where one or both participating bodies have been destroyed,
+        // along with the handles of removed constraints left in `constraint_order`
         self.user_constraints.retain(|_, c| {
-            c.owner.check(&graph).is_some()
-                && c.target.map(|t| t.check(&graph).is_some()).unwrap_or(true)
+            c.owner.check(graph).is_some()
+                && c.target.map(|t| t.check(graph).is_some()).unwrap_or(true)
         });
+        let user_constraints = &self.user_constraints;
+        self.constraint_order
+            .retain(|&handle| user_constraints.contains_key(handle));
 
-        // assuming here that slotmap's iteration order doesn't change if the
-        // contents don't change. it doesn't guarantee this in the docs so if
-        // constraints start jumping from one object to another this is why
+        // everything indexed by constraint is in the order of `constraint_order`
         bufs.constraint_body_pairs.clear();
         let node_ref_map = &bufs.node_ref_map;
         bufs.constraint_body_pairs
-            .extend(self.user_constraints.values().map(|c| {
+            .extend(self.constraint_order.iter().map(|&handle| {
+                let c = &user_constraints[handle];
                 (
                     node_ref_map[c.owner.pos().item_idx],
                     c.target.map(|t| node_ref_map[t.pos().item_idx]),
                 )
             }));
+        // offsets are given relative to body poses but the solver works with centers of mass
+        bufs.constraint_offsets.clear();
+        bufs.constraint_offsets.extend(
+            izip!(&self.constraint_order, &bufs.constraint_body_pairs).map(|(&handle, pair)| {
+                let c = &user_constraints[handle];
+                [
+                    c.offsets[0] - body_refs[pair.0].center_of_mass,
+                    pair.1
+                        .map(|p1| c.offsets[1] - body_refs[p1].center_of_mass)
+                        .unwrap_or(c.offsets[1]),
+                ]
+            }),
+        );
+        bufs.constraints_broken.clear();
+        bufs.constraints_broken
+            .resize(self.user_constraints.len(), false);
+        // force and torque are accumulated over the substeps
+        for constraint in self.user_constraints.values_mut() {
+            constraint.force = 0.0;
+            constraint.torque = 0.0;
+        }
+
+        // revolute joints without a rest angle take the one they were created in
+        for (&handle, pair) in izip!(&self.constraint_order, &bufs.constraint_body_pairs) {
+            if let ConstraintType::Revolute {
+                rest_angle: rest_angle @ None,
+                ..
+            } = &mut self.user_constraints[handle].ty
+            {
+                let cp = ConstraintPair {
+                    bodies: *pair,
+                    // masses don't matter for measuring angles
+                    inv_masses: [0.0; 2],
+                    inv_mom_inertias: [0.0; 2],
+                };
+                *rest_angle = Some(cp.relative_angle(&bufs.poses, 0.0));
+            }
+        }
 
         //
         // Set up collision detection
@@ -323,14 +723,22 @@ impl Physics {
             // we will not access these
             ColliderContext::Static(m::Pose::default()),
         );
+        bufs.coll_offsets
+            .resize(l_collider.content.len(), m::Pose::identity());
         for coll in l_collider.iter(graph) {
             bufs.coll_ctxs[coll.pos().item_idx] = match graph.get_neighbor_unchecked(&coll, l_body)
             {
-                Some(b) => ColliderContext::Body(bufs.node_ref_map[b.pos().item_idx]),
+                Some(b) => {
+                    bufs.coll_offsets[coll.pos().item_idx] = m::Pose::new(
+                        coll.offset.translation - b.center_of_mass,
+                        coll.offset.rotation,
+                    );
+                    ColliderContext::Body(bufs.node_ref_map[b.pos().item_idx])
+                }
                 None => {
                     ColliderContext::Static(match graph.get_neighbor_unchecked(&coll, l_pose) {
-                        Some(pose) => *pose,
-                        None => m::Pose::default(),
+                        Some(pose) => *pose * coll.offset,
+                        None => coll.offset,
                     })
                 }
             };
@@ -353,7 +761,7 @@ impl Physics {
             for coll in l_collider.iter(graph) {
                 let aabb = match bufs.coll_ctxs[coll.pos().item_idx] {
                     ColliderContext::Body(b) => coll
-                        .aabb(&bufs.poses[b])
+                        .aabb(&(bufs.poses[b] * bufs.coll_offsets[coll.pos().item_idx]))
                         .extended(bufs.velocities[b].linear * frame_dt)
                         .padded(max_expected_accel_over_frame),
                     ColliderContext::Static(p) => coll.aabb(&p),
@@ -361,11 +769,23 @@ impl Physics {
 
                 let spatial_index = &mut self.spatial_index;
                 let mask_matrix = &self.mask_matrix;
+                let coll_ctxs = &bufs.coll_ctxs;
+                let soft_body_of = &bufs.soft_body_of;
+                // particles of the same soft body are held apart by its constraints instead
+                let soft_body_of_coll =
+                    move |c: &graph::NodeRef<Collider>| match coll_ctxs[c.pos().item_idx] {
+                        ColliderContext::Body(b) => soft_body_of[b],
+                        ColliderContext::Static(_) => None,
+                    };
                 pairs.extend(
                     spatial_index
                         .test_and_insert(aabb, coll.pos().item_idx)
                         .map(move |other| [coll, l_collider.get_unchecked_by_item_idx(other)])
-                        .filter(|[c1, c2]| mask_matrix.get(c1.layer, c2.layer)),
+                        .filter(|[c1, c2]| mask_matrix.get(c1.layer, c2.layer))
+                        .filter(move |[c1, c2]| {
+                            soft_body_of_coll(c1).is_none()
+                                || soft_body_of_coll(c1) != soft_body_of_coll(c2)
+                        }),
                 );
             }
             pairs
@@ -377,6 +797,44 @@ impl Physics {
             PAIRS_PLOT.point(coll_pairs.len() as f64);
         }
 
+        // rigid colliders near the surface of each soft body
+        bufs.soft_body_surface_pairs.clear();
+        for (sbi, (soft_body, range)) in soft_body_refs
+            .iter()
+            .zip(&bufs.soft_body_ranges)
+            .enumerate()
+        {
+            let particle_aabb = |particle: usize| {
+                let pos = bufs.poses[particle].translation;
+                collision::AABB { min: pos, max: pos }
+                    .extended(bufs.velocities[particle].linear * frame_dt)
+            };
+            let particles = &bufs.soft_body_particles[range.clone()];
+            if particles.is_empty() {
+                continue;
+            }
+            let aabb = particles[1..]
+                .iter()
+                .fold(particle_aabb(particles[0]), |aabb, &particle| {
+                    aabb.union(&particle_aabb(particle))
+                });
+            let aabb = aabb.padded(soft_body.thickness / 2.0 + max_expected_accel_over_frame);
+            for idx in self.spatial_index.test_aabb(aabb) {
+                let coll = l_collider.get_unchecked_by_item_idx(idx);
+                let is_rigid = match bufs.coll_ctxs[idx] {
+                    ColliderContext::Body(b) => bufs.soft_body_of[b].is_none(),
+                    ColliderContext::Static(_) => true,
+                };
+                if is_rigid
+                    && coll.is_solid()
+                    && coll.one_way.is_none()
+                    && self.mask_matrix.get(soft_body.layer, coll.layer)
+                {
+                    bufs.soft_body_surface_pairs.push((sbi, idx));
+                }
+            }
+        }
+
         drop(spi_span);
 
         // store latest contacts for use in the velocity step
@@ -385,6 +843,167 @@ impl Physics {
         // store contact forces for friction purposes
         bufs.contact_lambdas.clear();
         bufs.contact_lambdas.resize(coll_pairs.len(), 0.0);
+        bufs.frame_contacts.clear();
+        bufs.frame_contacts
+            .resize(coll_pairs.len(), ContactResult::Zero);
+        bufs.contact_impulses.clear();
+        bufs.contact_impulses.resize(coll_pairs.len(), 0.0);
+        bufs.contact_coefs.clear();
+        bufs.contact_coefs
+            .resize(coll_pairs.len(), Default::default());
+        bufs.one_way_states.clear();
+        let one_way_pairs = &self.one_way_pairs;
+        bufs.one_way_states.extend(coll_pairs.iter().map(|colls| {
+            if colls[0].one_way.is_some() || colls[1].one_way.is_some() {
+                one_way_pairs.get(&ordered_pair(colls, graph).0).copied()
+            } else {
+                None
+            }
+        }));
+        bufs.one_way_touching.clear();
+        bufs.one_way_touching
+            .extend(bufs.one_way_states.iter().map(Option::is_some));
+
+        //
+        // Islands and sleeping
+        //
+
+        let spatial_index = &self.spatial_index;
+        let aabbs_touch = |colls: &[graph::NodeRef<Collider>; 2]| {
+            spatial_index
+                .get_aabb(colls[0].pos().item_idx)
+                .intersection(&spatial_index.get_aabb(colls[1].pos().item_idx))
+                .is_some()
+        };
+
+        bufs.sleeping.clear();
+        bufs.sleeping.extend(body_refs.iter().map(|body| {
+            // giving a sleeping body a velocity or a force through the graph wakes it up
+            body.sleeping
+                && body.sees_forces()
+                && body.velocity.linear == m::Vec2::zero()
+                && body.velocity.angular == 0.0
+                && body.force == m::Vec2::zero()
+                && body.torque == 0.0
+        }));
+        for body in self.wake_queue.drain(..) {
+            if body.check(graph).is_some() {
+                bufs.sleeping[bufs.node_ref_map[body.pos().item_idx]] = false;
+            }
+        }
+
+        // group bodies that touch or are constrained together into islands
+        bufs.islands.reset(body_refs.len());
+        for colls in &coll_pairs {
+            let ctxs = map_pair(colls, |c| bufs.coll_ctxs[c.pos().item_idx]);
+            if let [ColliderContext::Body(b0), ColliderContext::Body(b1)] = ctxs {
+                if body_refs[b0].sees_forces() && body_refs[b1].sees_forces() && aabbs_touch(colls)
+                {
+                    bufs.islands.join(b0, b1);
+                }
+            }
+        }
+        for pair in &bufs.constraint_body_pairs {
+            if let (b0, Some(b1)) = *pair {
+                // like contacts, kinematic bodies don't join islands,
+                // they wake up the ones they're attached to when moving instead
+                if body_refs[b0].sees_forces() && body_refs[b1].sees_forces() {
+                    bufs.islands.join(b0, b1);
+                }
+            }
+        }
+        for (particle, next) in bufs.rope_next_particles.iter().enumerate() {
+            if let Some(next) = next {
+                bufs.islands.join(particle, *next);
+            }
+        }
+        for range in &bufs.soft_body_ranges {
+            let particles = &bufs.soft_body_particles[range.clone()];
+            for pair in particles.windows(2) {
+                bufs.islands.join(pair[0], pair[1]);
+            }
+        }
+
+        // wake up whole islands if any body in them is awake
+        // or touched by or constrained to a moving kinematic body
+        bufs.island_flags.clear();
+        bufs.island_flags.resize(body_refs.len(), false);
+        for (bi, body) in body_refs.iter().enumerate() {
+            if body.sees_forces() && !bufs.sleeping[bi] {
+                let root = bufs.islands.root(bi);
+                bufs.island_flags[root] = true;
+            }
+        }
+        for colls in &coll_pairs {
+            let ctxs = map_pair(colls, |c| bufs.coll_ctxs[c.pos().item_idx]);
+            if let [ColliderContext::Body(b0), ColliderContext::Body(b1)] = ctxs {
+                for (kinematic, other) in [(b0, b1), (b1, b0)] {
+                    let kin_vel = bufs.velocities[kinematic];
+                    if !body_refs[kinematic].sees_forces()
+                        && (kin_vel.linear != m::Vec2::zero() || kin_vel.angular != 0.0)
+                        && aabbs_touch(colls)
+                    {
+                        let root = bufs.islands.root(other);
+                        bufs.island_flags[root] = true;
+                    }
+                }
+            }
+        }
+        for pair in &bufs.constraint_body_pairs {
+            if let (b0, Some(b1)) = *pair {
+                for (kinematic, other) in [(b0, b1), (b1, b0)] {
+                    let kin_vel = bufs.velocities[kinematic];
+                    if !body_refs[kinematic].sees_forces()
+                        && (kin_vel.linear != m::Vec2::zero() || kin_vel.angular != 0.0)
+                    {
+                        let root = bufs.islands.root(other);
+                        bufs.island_flags[root] = true;
+                    }
+                }
+            }
+        }
+        bufs.sleep_timers.clear();
+        for (bi, body) in body_refs.iter().enumerate() {
+            let root = bufs.islands.root(bi);
+            if bufs.island_flags[root] {
+                bufs.sleeping[bi] = false;
+            }
+            bufs.sleep_timers
+                .push(if body.sleeping && !bufs.sleeping[bi] {
+                    0.0
+                } else {
+                    body.sleep_timer
+                });
+        }
+
+        bufs.ccd_colliders.clear();
+        for coll in l_collider.iter(graph) {
+            // triggers never stop anything,
+            // and one-way colliders need to see the contact to decide whether to
+            if !coll.is_solid() || coll.one_way.is_some() {
+                continue;
+            }
+            if let ColliderContext::Body(bi) = bufs.coll_ctxs[coll.pos().item_idx] {
+                if body_refs[bi].ccd && body_refs[bi].sees_forces() && !bufs.sleeping[bi] {
+                    bufs.ccd_colliders.push((coll.pos().item_idx, bi));
+                }
+            }
+        }
+
+        // pairs where a solid collider attached to a dynamic body may be floating in a fluid
+        bufs.fluid_pairs.clear();
+        for (pair_idx, colls) in coll_pairs.iter().enumerate() {
+            let fluid_idx = match (colls[0].ty, colls[1].ty) {
+                (ColliderType::Fluid(_), ColliderType::Solid(_)) => 0,
+                (ColliderType::Solid(_), ColliderType::Fluid(_)) => 1,
+                _ => continue,
+            };
+            if let ColliderContext::Body(bi) = bufs.coll_ctxs[colls[1 - fluid_idx].pos().item_idx] {
+                if matches!(body_refs[bi].mass, Mass::Finite { .. }) && !bufs.sleeping[bi] {
+                    bufs.fluid_pairs.push((pair_idx, fluid_idx));
+                }
+            }
+        }
 
         //
         // Actual physics step
@@ -392,30 +1011,72 @@ impl Physics {
 
         for _substep in 0..self.substeps {
             let _substep_span = tracy_span!("substep", "tick");
+
+            //
+            // compute buoyancy and drag for bodies in fluids
+            //
+
+            bufs.compute_fluid_forces(&coll_pairs, forcefield);
+
             //
             // apply external forces and estimate post-step pose with explicit Euler step
             //
-            for (body, old_pose, pose, old_vel, vel, ext_accel) in izip!(
+            for (body, &sleeping, &fluid_force, old_pose, pose, old_vel, vel, ext_accel) in izip!(
                 &body_refs,
+                &bufs.sleeping,
+                &bufs.fluid_forces,
                 &mut bufs.old_poses,
                 &mut bufs.poses,
                 &mut bufs.old_velocities,
                 &mut bufs.velocities,
                 &mut bufs.ext_f_accelerations
             ) {
-                if let Mass::Finite { .. } = body.mass {
-                    // TODO: rename forcefield to accelerationfield or allow it to depend on mass
-                    let ff_accel = forcefield.value_at(pose.translation);
+                if sleeping {
+                    continue;
+                }
+                if let Mass::Finite { mass, inverse } = body.mass {
+                    let (fluid_force, fluid_torque) = fluid_force;
+                    let force = forcefield.force_at(pose.translation, vel.linear, mass)
+                        + body.force
+                        + fluid_force;
+                    let ff_accel = forcefield.value_at(pose.translation) * body.gravity_scale
+                        + force * inverse;
                     vel.linear += ff_accel * dt;
+                    vel.angular += (body.torque + fluid_torque) * body.moment_of_inertia.inv() * dt;
                     *ext_accel = ff_accel;
 
+                    vel.linear /= 1.0 + body.linear_damping * dt;
+                    vel.angular /= 1.0 + body.angular_damping * dt;
+                    if let Some(max_speed) = body.max_linear_speed {
+                        let speed = vel.linear.mag();
+                        if speed > max_speed {
+                            vel.linear *= max_speed / speed;
+                        }
+                    }
+                    if let Some(max_speed) = body.max_angular_speed {
+                        vel.angular = vel.angular.clamp(-max_speed, max_speed);
+                    }
+
                     // old_vel is velocity after external forces but before collisions
                     *old_vel = *vel;
                     *old_pose = *pose;
                     *pose = vel.apply_to_pose(dt, *pose);
+                } else {
+                    // kinematic bodies just move at their velocity
+                    *old_pose = *pose;
+                    *pose = vel.apply_to_pose(dt, *pose);
                 }
             }
 
+            //
+            // Continuous collision detection
+            //
+
+            if !bufs.ccd_colliders.is_empty() {
+                let _ccd_span = tracy_span!("continuous collision detection", "tick");
+                bufs.sweep_ccd_bodies(l_collider, &mut self.spatial_index, &self.mask_matrix);
+            }
+
             //
             // Rope constraints
             //
@@ -425,27 +1086,52 @@ impl Physics {
             bufs.rope_lateral_corrections.iter_mut().for_each(|c| {
                 *c = None;
             });
-            for rope in l_rope.iter(graph) {
-                let first_particle = graph.get_neighbor(&rope, l_body).unwrap().pos().item_idx;
+            for (rope, &first_segment_length) in
+                l_rope.iter(graph).zip(&bufs.rope_first_segment_lengths)
+            {
+                let first_particle = match graph.get_neighbor(&rope, l_body) {
+                    Some(particle) => bufs.node_ref_map[particle.pos().item_idx],
+                    None => continue,
+                };
+                // a rope is always in a single island, so it all sleeps together
+                if bufs.sleeping[first_particle] {
+                    continue;
+                }
                 // solve constraints
                 let mut curr_particle = first_particle;
-                let mut next_particle = bufs.rope_next_particles[curr_particle].unwrap();
+                // a rope cut down to a single particle has nothing to solve
+                let mut next_particle = match bufs.rope_next_particles[curr_particle] {
+                    Some(next) => next,
+                    None => continue,
+                };
+                // position of `next_particle` in the rope
+                let mut next_idx = 1;
                 loop {
                     let dist = bufs.poses[next_particle].translation
                         - bufs.poses[curr_particle].translation;
                     let dist_mag = dist.mag();
-                    let dir = dist / dist_mag;
-                    let error = rope.spacing - dist_mag;
+                    // particles on top of each other have no direction to be pushed apart in,
+                    // which is fine for a first segment reeled in to zero length
+                    if dist_mag != 0.0 {
+                        let dir = dist / dist_mag;
+                        let rest_length = if curr_particle == first_particle {
+                            first_segment_length
+                        } else {
+                            rope.spacing
+                        };
+                        let error = rest_length - dist_mag;
 
-                    let lambda = -error
-                        / (body_refs[curr_particle].mass.inv()
-                            + body_refs[next_particle].mass.inv()
-                            + rope.compliance * inv_dt_sq);
+                        let lambda = -error
+                            / (body_refs[curr_particle].mass.inv()
+                                + body_refs[next_particle].mass.inv()
+                                + rope.compliance * inv_dt_sq);
 
-                    bufs.poses[curr_particle]
-                        .append_translation(body_refs[curr_particle].mass.inv() * lambda * dir);
-                    bufs.poses[next_particle]
-                        .append_translation(-body_refs[next_particle].mass.inv() * lambda * dir);
+                        bufs.poses[curr_particle]
+                            .append_translation(body_refs[curr_particle].mass.inv() * lambda * dir);
+                        bufs.poses[next_particle].append_translation(
+                            -body_refs[next_particle].mass.inv() * lambda * dir,
+                        );
+                    }
 
                     let particle_after_next = match bufs.rope_next_particles[next_particle] {
                         Some(next) => next,
@@ -458,22 +1144,22 @@ impl Physics {
                         - bufs.poses[curr_particle].translation;
                     let next_to_after = bufs.poses[particle_after_next].translation
                         - bufs.poses[next_particle].translation;
-                    let angle = next_to_after
-                        .normalized()
-                        .dot(curr_to_next.normalized())
-                        .acos();
-                    let error = angle - rope.bending_max_angle;
-                    if error > 0.0 {
+                    let segments_have_length =
+                        curr_to_next != m::Vec2::zero() && next_to_after != m::Vec2::zero();
+                    let rest_bend = rope.rest_bends.get(next_idx).copied().unwrap_or(0.0);
+                    let mut deviation = rope::bend_angle(curr_to_next, next_to_after) - rest_bend;
+                    if deviation > std::f64::consts::PI {
+                        deviation -= std::f64::consts::TAU;
+                    } else if deviation < -std::f64::consts::PI {
+                        deviation += std::f64::consts::TAU;
+                    }
+                    let error = deviation.abs() - rope.bending_max_angle;
+                    if segments_have_length && error > 0.0 {
                         let lambda = -error
                             / (body_refs[particle_after_next].mass.inv()
                                 + rope.bending_compliance * inv_dt_sq);
 
-                        let lambda_oriented =
-                            if m::left_normal(curr_to_next).dot(next_to_after) > 0.0 {
-                                lambda
-                            } else {
-                                -lambda
-                            };
+                        let lambda_oriented = if deviation > 0.0 { lambda } else { -lambda };
                         let correction = m::Rotor2::from_angle(
                             lambda_oriented * body_refs[particle_after_next].mass.inv(),
                         );
@@ -487,27 +1173,43 @@ impl Physics {
 
                     curr_particle = next_particle;
                     next_particle = particle_after_next;
+                    next_idx += 1;
                 }
             }
 
+            //
+            // Soft body constraints
+            //
+
+            bufs.solve_soft_body_constraints(&soft_body_refs, &body_refs, inv_dt_sq);
+
             //
             // User-defined constraints
             //
 
-            for (constraint, pair) in
-                izip!(self.user_constraints.values(), &bufs.constraint_body_pairs)
+            for (ci, (&handle, pair)) in
+                izip!(&self.constraint_order, &bufs.constraint_body_pairs).enumerate()
             {
+                let constraint = &mut self.user_constraints[handle];
+                if constraint_asleep(*pair, &body_refs, &bufs.sleeping)
+                    || bufs.constraints_broken[ci]
+                {
+                    continue;
+                }
+                let offsets = bufs.constraint_offsets[ci];
                 let inv_masses = map_semi_pair(*pair, |b| body_refs[*b].mass.inv(), 0.0);
                 let inv_mom_inertias =
                     map_semi_pair(*pair, |b| body_refs[*b].moment_of_inertia.inv(), 0.0);
 
-                match constraint.ty {
+                // magnitudes of the positional and angular corrections holding the constraint,
+                // used to compute force and torque
+                let (lambda_pos, lambda_ang) = match constraint.ty {
                     ConstraintType::Distance { distance } => {
                         let offsets_worldspace = [
-                            bufs.poses[pair.0] * constraint.offsets[0],
+                            bufs.poses[pair.0] * offsets[0],
                             pair.1
-                                .map(|p1| bufs.poses[p1] * constraint.offsets[1])
-                                .unwrap_or(constraint.offsets[1]),
+                                .map(|p1| bufs.poses[p1] * offsets[1])
+                                .unwrap_or(offsets[1]),
                         ];
                         let actual_dist = offsets_worldspace[1] - offsets_worldspace[0];
                         let actual_dist_mag = actual_dist.mag();
@@ -529,7 +1231,7 @@ impl Physics {
                                 Some(p1) => {
                                     let pair = [pair.0, p1];
                                     let offsets_rotated = map_pair(&[0, 1], |i| {
-                                        bufs.poses[pair[*i]].rotation * constraint.offsets[*i]
+                                        bufs.poses[pair[*i]].rotation * offsets[*i]
                                     });
                                     let offsets_wedge_dir =
                                         map_pair(&[0, 1], |i| offsets_rotated[*i].wedge(dir).xy);
@@ -559,11 +1261,12 @@ impl Physics {
                                         )
                                         .into(),
                                     );
+
+                                    (lambda.abs(), 0.0)
                                 }
                                 None => {
                                     // this is repetitive but kind of hard to abstract :thinking:
-                                    let offset_rotated =
-                                        bufs.poses[pair.0].rotation * constraint.offsets[0];
+                                    let offset_rotated = bufs.poses[pair.0].rotation * offsets[0];
                                     let offset_wedge_dir = offset_rotated.wedge(dir).xy;
                                     let eff_inv_mass = inv_masses[0]
                                         + offset_wedge_dir.powi(2) * inv_mom_inertias[0];
@@ -577,10 +1280,163 @@ impl Physics {
                                         Angle::Rad(inv_mom_inertias[0] * lambda * offset_wedge_dir)
                                             .into(),
                                     );
+
+                                    (lambda.abs(), 0.0)
                                 }
                             }
+                        } else {
+                            (0.0, 0.0)
+                        }
+                    }
+                    ConstraintType::Revolute {
+                        rest_angle,
+                        angle_limits,
+                        motor,
+                    } => {
+                        let cp = ConstraintPair {
+                            bodies: *pair,
+                            inv_masses,
+                            inv_mom_inertias,
+                        };
+
+                        // the motor and limits turn the bodies around the joint
+                        // and don't count towards the torque holding it together
+
+                        if let Some(motor) = motor {
+                            let error = cp.relative_rotation_change(&bufs.old_poses, &bufs.poses)
+                                - motor.target_velocity * dt;
+                            let max_lambda = motor.max_force * dt * dt;
+                            let lambda =
+                                cp.angular_lambda(error, 0.0).clamp(-max_lambda, max_lambda);
+                            cp.apply_angular(&mut bufs.poses, lambda);
+                        }
+
+                        if let Some(limits) = angle_limits {
+                            let angle = cp.relative_angle(
+                                &bufs.poses,
+                                rest_angle.expect("rest angle set during setup"),
+                            );
+                            let error = if angle < limits.min {
+                                angle - limits.min
+                            } else if angle > limits.max {
+                                angle - limits.max
+                            } else {
+                                0.0
+                            };
+                            if error != 0.0 {
+                                let lambda = cp.angular_lambda(error, 0.0);
+                                cp.apply_angular(&mut bufs.poses, lambda);
+                            }
+                        }
+
+                        let lambda_pos = cp.attach_origins(
+                            &mut bufs.poses,
+                            offsets,
+                            constraint.compliance * inv_dt_sq,
+                        );
+                        (lambda_pos.abs(), 0.0)
+                    }
+                    ConstraintType::Prismatic {
+                        axis,
+                        rest_angle,
+                        translation_limits,
+                        motor,
+                    } => {
+                        let cp = ConstraintPair {
+                            bodies: *pair,
+                            inv_masses,
+                            inv_mom_inertias,
+                        };
+                        let compliance_term = constraint.compliance * inv_dt_sq;
+
+                        let error = cp.relative_angle(&bufs.poses, rest_angle);
+                        let lambda_ang = cp.angular_lambda(error, compliance_term);
+                        cp.apply_angular(&mut bufs.poses, lambda_ang);
+
+                        let axis_world = cp.rotations(&bufs.poses)[1] * *axis;
+
+                        // the motor and limits move the body along the axis
+                        // and don't count towards the force holding the joint together
+
+                        if let Some(motor) = motor {
+                            let translation_change = |b: usize| {
+                                bufs.poses[b].translation - bufs.old_poses[b].translation
+                            };
+                            let rel_change = translation_change(pair.0)
+                                - pair.1.map(translation_change).unwrap_or_else(m::Vec2::zero);
+                            let error = motor.target_velocity * dt - rel_change.dot(axis_world);
+                            let (_, offsets_rotated) = cp.world_points(&bufs.poses, offsets);
+                            let max_lambda = motor.max_force * dt * dt;
+                            let lambda = cp
+                                .positional_lambda(offsets_rotated, axis_world, error, 0.0)
+                                .clamp(-max_lambda, max_lambda);
+                            cp.apply_positional(
+                                &mut bufs.poses,
+                                offsets_rotated,
+                                axis_world,
+                                lambda,
+                            );
+                        }
+
+                        if let Some(limits) = translation_limits {
+                            let (points, offsets_rotated) = cp.world_points(&bufs.poses, offsets);
+                            let along_axis = (points[0] - points[1]).dot(axis_world);
+                            let error = if along_axis < limits.min {
+                                limits.min - along_axis
+                            } else if along_axis > limits.max {
+                                limits.max - along_axis
+                            } else {
+                                0.0
+                            };
+                            if error != 0.0 {
+                                let lambda =
+                                    cp.positional_lambda(offsets_rotated, axis_world, error, 0.0);
+                                cp.apply_positional(
+                                    &mut bufs.poses,
+                                    offsets_rotated,
+                                    axis_world,
+                                    lambda,
+                                );
+                            }
                         }
+
+                        // keep the owner's origin on the axis
+                        let (points, offsets_rotated) = cp.world_points(&bufs.poses, offsets);
+                        let normal = m::left_normal(axis_world);
+                        let error = (points[1] - points[0]).dot(normal);
+                        let lambda =
+                            cp.positional_lambda(offsets_rotated, normal, error, compliance_term);
+                        cp.apply_positional(&mut bufs.poses, offsets_rotated, normal, lambda);
+
+                        (lambda.abs(), lambda_ang.abs())
+                    }
+                    ConstraintType::Weld { rest_angle } => {
+                        let cp = ConstraintPair {
+                            bodies: *pair,
+                            inv_masses,
+                            inv_mom_inertias,
+                        };
+                        let compliance_term = constraint.compliance * inv_dt_sq;
+
+                        let error = cp.relative_angle(&bufs.poses, rest_angle);
+                        let lambda_ang = cp.angular_lambda(error, compliance_term);
+                        cp.apply_angular(&mut bufs.poses, lambda_ang);
+
+                        let lambda_pos =
+                            cp.attach_origins(&mut bufs.poses, offsets, compliance_term);
+                        (lambda_pos.abs(), lambda_ang.abs())
                     }
+                };
+
+                // a correction of lambda is an impulse of lambda / dt,
+                // summing these over the frame and dividing by frame_dt gives the average force
+                constraint.force += lambda_pos * inv_dt / frame_dt;
+                constraint.torque += lambda_ang * inv_dt / frame_dt;
+                let exceeds = |value: f64, limit: Option<f64>| limit.is_some_and(|l| value > l);
+                if exceeds(constraint.force, constraint.break_force)
+                    || exceeds(constraint.torque, constraint.break_torque)
+                {
+                    bufs.constraints_broken[ci] = true;
                 }
             }
 
@@ -598,30 +1454,58 @@ impl Physics {
                 *pre_cont_pose = *pose;
             }
 
-            for (colls, contact, lambda_n) in
-                izip!(&coll_pairs, &mut bufs.contacts, &mut bufs.contact_lambdas)
-            {
+            for (
+                colls,
+                contact,
+                lambda_n,
+                frame_contact,
+                impulse,
+                pair_coefs,
+                one_way_state,
+                one_way_touching,
+            ) in izip!(
+                &coll_pairs,
+                &mut bufs.contacts,
+                &mut bufs.contact_lambdas,
+                &mut bufs.frame_contacts,
+                &mut bufs.contact_impulses,
+                &mut bufs.contact_coefs,
+                &mut bufs.one_way_states,
+                &mut bufs.one_way_touching
+            ) {
                 let coll_ctxs = &bufs.coll_ctxs;
                 let ctxs = map_pair(colls, |c| coll_ctxs[c.pos().item_idx]);
                 if !match ctxs[0] {
-                    ColliderContext::Body(bi) => body_refs[bi].sees_forces(),
+                    ColliderContext::Body(bi) => body_refs[bi].sees_forces() && !bufs.sleeping[bi],
                     ColliderContext::Static(_) => false,
                 } && !match ctxs[1] {
-                    ColliderContext::Body(bi) => body_refs[bi].sees_forces(),
+                    ColliderContext::Body(bi) => body_refs[bi].sees_forces() && !bufs.sleeping[bi],
                     ColliderContext::Static(_) => false,
                 } {
-                    // both bodies are kinematic or static, skip this pair
+                    // both bodies are kinematic, static or sleeping, skip this pair
                     *contact = ContactResult::Zero;
                     continue;
                 }
+                let (mut coefs, surface_vel) = match (colls[0].ty, colls[1].ty) {
+                    (ColliderType::Solid(m0), ColliderType::Solid(m1)) => (
+                        self.material_pairs.coefficients(&m0, &m1),
+                        relative_surface_velocity(&m0, &m1),
+                    ),
+                    // one of the colliders was a trigger, these are checked once per frame later
+                    _ => {
+                        *contact = ContactResult::Zero;
+                        continue;
+                    }
+                };
 
                 // check for collision
                 let poses = &bufs.poses;
+                let coll_offsets = &bufs.coll_offsets;
+                let pair_poses = map_pair(&[0, 1], |i| match ctxs[*i] {
+                    ColliderContext::Body(b) => poses[b] * coll_offsets[colls[*i].pos().item_idx],
+                    ColliderContext::Static(pose) => pose,
+                });
                 *contact = {
-                    let poses = map_pair(&ctxs, |ctx| match ctx {
-                        ColliderContext::Body(b) => poses[*b],
-                        ColliderContext::Static(pose) => *pose,
-                    });
                     let aabb_isect = self
                         .spatial_index
                         .get_aabb(colls[0].pos().item_idx)
@@ -629,7 +1513,17 @@ impl Physics {
                     if aabb_isect.is_none() {
                         ContactResult::Zero
                     } else {
-                        intersection_check(&poses[0], &*colls[0], &poses[1], &*colls[1])
+                        intersection_check(&pair_poses[0], &colls[0], &pair_poses[1], &colls[1])
+                            // the solver wants offsets relative to centers of mass, not colliders
+                            .map(|mut c| {
+                                for i in 0..2 {
+                                    if let ColliderContext::Body(_) = ctxs[i] {
+                                        c.offsets[i] =
+                                            coll_offsets[colls[i].pos().item_idx] * c.offsets[i];
+                                    }
+                                }
+                                c
+                            })
                     }
                 };
 
@@ -672,13 +1566,61 @@ impl Physics {
                     }
                 }
 
-                let materials = match (colls[0].ty, colls[1].ty) {
-                    (ColliderType::Solid(m0), ColliderType::Solid(m1)) => [m0, m1],
-                    // one of the colliders was a trigger, no physics response
-                    _ => {
-                        continue;
+                // one-way colliders decide whether to block or let the other through
+                // at first touch and stick with it until the colliders are apart at the end of a frame.
+                // a substep without contact in the middle of the frame doesn't reset the decision,
+                // since a body passing through can briefly be in a gap between contacts
+                if colls[0].one_way.is_some() || colls[1].one_way.is_some() {
+                    *one_way_touching = !matches!(contact, ContactResult::Zero);
+                    if let Some(first) = contact.iter().next() {
+                        let velocities = &bufs.velocities;
+                        let velocities = map_pair(&ctxs, |ctx| match ctx {
+                            ColliderContext::Body(b) => velocities[*b].linear,
+                            ColliderContext::Static(_) => m::Vec2::zero(),
+                        });
+                        let passing = *one_way_state.get_or_insert_with(|| {
+                            !(0..2).all(|i| match colls[i].one_way {
+                                None => true,
+                                Some(dir) => {
+                                    let dir = pair_poses[i].rotation * *dir;
+                                    let normal_away = if i == 0 {
+                                        *first.normal
+                                    } else {
+                                        -*first.normal
+                                    };
+                                    let approach_vel = velocities[1 - i] - velocities[i];
+                                    normal_away.dot(dir) > 0.0 && approach_vel.dot(dir) <= 0.0
+                                }
+                            })
+                        });
+                        if passing {
+                            *contact = ContactResult::Zero;
+                            continue;
+                        }
                     }
-                };
+                }
+
+                if let (Some(modifier), false) = (
+                    &mut self.contact_modifier,
+                    matches!(contact, ContactResult::Zero),
+                ) {
+                    let mut modification = ContactModification {
+                        contacts: *contact,
+                        coefficients: coefs,
+                    };
+                    modifier.modify_contacts(
+                        map_pair(colls, |c| graph::NodeRef::as_node(c, graph)),
+                        [&colls[0], &colls[1]],
+                        &mut modification,
+                    );
+                    *contact = modification.contacts;
+                    coefs = modification.coefficients;
+                }
+                *pair_coefs = coefs;
+
+                if !matches!(contact, ContactResult::Zero) {
+                    *frame_contact = *contact;
+                }
 
                 for contact in contact.iter() {
                     // tangent for static friction
@@ -742,6 +1684,7 @@ impl Physics {
                     }
 
                     *lambda_n = -depth / (vars[0].eff_inv_mass_n + vars[1].eff_inv_mass_n);
+                    *impulse -= *lambda_n * inv_dt;
 
                     if let ColliderContext::Body(bi) = ctxs[0] {
                         let im = body_refs[bi].mass.inv();
@@ -762,18 +1705,20 @@ impl Physics {
 
                     // static friction
 
-                    if let Some(friction_coef) = materials[0].static_friction_with(&materials[1]) {
+                    if let Some(friction_coef) = coefs.static_friction_coef {
                         let offset_diff_motion = (vars[0].offset_worldspace
                             - vars[0].offset_worldspace_old)
                             - (vars[1].offset_worldspace - vars[1].offset_worldspace_old);
-                        let motion_along_tan = offset_diff_motion.dot(tangent);
+                        // moving surfaces slip even if the points don't move relative to each other
+                        let motion_along_tan = offset_diff_motion.dot(tangent) + surface_vel * dt;
 
                         let max_coulomb_dx = *lambda_n * friction_coef;
 
                         let lambda_t = -motion_along_tan
                             / (vars[0].eff_inv_mass_tan + vars[1].eff_inv_mass_tan);
 
-                        if lambda_t < max_coulomb_dx {
+                        // the correction can go either way along the tangent
+                        if lambda_t.abs() < max_coulomb_dx.abs() {
                             if let ColliderContext::Body(bi) = ctxs[0] {
                                 let im = body_refs[bi].mass.inv();
                                 let imi = body_refs[bi].moment_of_inertia.inv();
@@ -795,6 +1740,15 @@ impl Physics {
                 }
             }
 
+            // soft body surfaces against rigid colliders
+            bufs.solve_soft_body_contacts(
+                &soft_body_refs,
+                &body_refs,
+                l_collider,
+                &self.material_pairs,
+                &self.spatial_index,
+            );
+
             #[cfg(feature = "tracy")]
             CONTACTS_PLOT.point(contact_counter as f64);
 
@@ -817,11 +1771,18 @@ impl Physics {
 
             let vel_span = tracy_span!("velocity solve", "tick");
 
-            for (colls, contact, lambda_n) in
-                izip!(&coll_pairs, &bufs.contacts, &bufs.contact_lambdas)
+            for (pair_idx, (colls, contact, lambda_n, coefs)) in izip!(
+                &coll_pairs,
+                &bufs.contacts,
+                &bufs.contact_lambdas,
+                &bufs.contact_coefs
+            )
+            .enumerate()
             {
-                let materials = match (colls[0].ty, colls[1].ty) {
-                    (ColliderType::Solid(m0), ColliderType::Solid(m1)) => [m0, m1],
+                let surface_vel = match (colls[0].ty, colls[1].ty) {
+                    (ColliderType::Solid(m0), ColliderType::Solid(m1)) => {
+                        relative_surface_velocity(&m0, &m1)
+                    }
                     // one of the colliders was a trigger, no physics response
                     _ => {
                         continue;
@@ -875,16 +1836,16 @@ impl Physics {
                         // don't bounce if the normal velocity is very small to avoid jitter
                         0.0
                     } else {
-                        materials[0].restitution_with(&materials[1])
+                        coefs.restitution_coef
                     };
                     let delta_normal_vel = -normal_vel - restitution_coef * old_normal_vel.max(0.0);
 
                     // dynamic friction
 
                     let tangent = m::left_normal(*contact.normal);
-                    let delta_tan_vel = match materials[0].dynamic_friction_with(&materials[1]) {
+                    let delta_tan_vel = match coefs.dynamic_friction_coef {
                         Some(friction_coef) => {
-                            let tangent_vel = relative_vel_at_p.dot(tangent);
+                            let tangent_vel = relative_vel_at_p.dot(tangent) + surface_vel;
                             let max_coulomb_dv = inv_dt * lambda_n * friction_coef;
                             tangent_vel.abs().min(max_coulomb_dv.abs()) * -tangent_vel.signum()
                         }
@@ -908,6 +1869,8 @@ impl Physics {
                             + (offsets_wedge_dv[*i].powi(2) * vars[*i].inv_mom_inertia)
                     });
                     let impulse_mag = vel_update_mag / (eff_inv_masses[0] + eff_inv_masses[1]);
+                    bufs.contact_impulses[pair_idx] -=
+                        impulse_mag * vel_update_dir.dot(*contact.normal);
 
                     if let ColliderContext::Body(bi) = ctxs[0] {
                         bufs.velocities[bi].linear +=
@@ -926,9 +1889,16 @@ impl Physics {
 
             // damping
 
-            for (constraint, pair) in
-                izip!(self.user_constraints.values(), &bufs.constraint_body_pairs)
-            {
+            for (&handle, pair, offsets, broken) in izip!(
+                &self.constraint_order,
+                &bufs.constraint_body_pairs,
+                &bufs.constraint_offsets,
+                &bufs.constraints_broken
+            ) {
+                let constraint = &self.user_constraints[handle];
+                if constraint_asleep(*pair, &body_refs, &bufs.sleeping) || *broken {
+                    continue;
+                }
                 let inv_masses = map_semi_pair(*pair, |b| body_refs[*b].mass.inv(), 0.0);
                 let inv_mom_inertias =
                     map_semi_pair(*pair, |b| body_refs[*b].moment_of_inertia.inv(), 0.0);
@@ -936,9 +1906,8 @@ impl Physics {
                 match pair.1 {
                     Some(p1) => {
                         let pair = [pair.0, p1];
-                        let offsets_rotated = map_pair(&[0, 1], |i| {
-                            bufs.poses[pair[*i]].rotation * constraint.offsets[*i]
-                        });
+                        let offsets_rotated =
+                            map_pair(&[0, 1], |i| bufs.poses[pair[*i]].rotation * offsets[*i]);
 
                         let relative_vel = bufs.velocities[pair[0]]
                             .point_velocity(offsets_rotated[0])
@@ -982,7 +1951,7 @@ impl Physics {
                         };
                     }
                     None => {
-                        let offset_rotated = bufs.poses[pair.0].rotation * constraint.offsets[0];
+                        let offset_rotated = bufs.poses[pair.0].rotation * offsets[0];
 
                         let point_vel = bufs.velocities[pair.0].point_velocity(offset_rotated);
                         let point_vel_mag = point_vel.mag();
@@ -1019,9 +1988,18 @@ impl Physics {
             //
 
             for rope in l_rope.iter(graph) {
-                let first_particle = graph.get_neighbor(&rope, l_body).unwrap().pos().item_idx;
+                let first_particle = match graph.get_neighbor(&rope, l_body) {
+                    Some(particle) => bufs.node_ref_map[particle.pos().item_idx],
+                    None => continue,
+                };
+                if bufs.sleeping[first_particle] {
+                    continue;
+                }
                 let mut curr_particle = first_particle;
-                let mut next_particle = bufs.rope_next_particles[curr_particle].unwrap();
+                let mut next_particle = match bufs.rope_next_particles[curr_particle] {
+                    Some(next) => next,
+                    None => continue,
+                };
                 loop {
                     let relative_vel = bufs.velocities[curr_particle].linear
                         - bufs.velocities[next_particle].linear;
@@ -1073,31 +2051,81 @@ impl Physics {
                 }
             }
 
-            drop(vel_span);
-
             //
-            // Event gathering
+            // Damping for soft bodies
             //
 
-            for (colls, contact) in izip!(&coll_pairs, &bufs.contacts) {
-                match contact {
-                    ContactResult::Zero => (),
-                    _ => {
-                        if let Some(mut sink) =
-                            graph.get_neighbor_mut_unchecked(&colls[0], l_evt_sink)
-                        {
-                            sink.push(Event::Contact(ContactEvent {
-                                other_collider: graph::NodeRef::as_node(&colls[1], graph),
-                            }));
-                        }
-                        if let Some(mut sink) =
-                            graph.get_neighbor_mut_unchecked(&colls[1], l_evt_sink)
-                        {
-                            sink.push(Event::Contact(ContactEvent {
-                                other_collider: graph::NodeRef::as_node(&colls[0], graph),
-                            }));
-                        }
-                    }
+            bufs.damp_soft_bodies(&soft_body_refs, &body_refs, dt);
+
+            drop(vel_span);
+        }
+
+        //
+        // contact events
+        //
+
+        bufs.emit_contact_events(
+            &coll_pairs,
+            &body_refs,
+            graph,
+            l_evt_sink,
+            &mut self.active_contacts,
+        );
+
+        //
+        // trigger overlaps
+        //
+
+        bufs.emit_trigger_events(&coll_pairs, graph, l_evt_sink, &mut self.trigger_pairs);
+
+        self.one_way_pairs.clear();
+        for (colls, state, touching) in
+            izip!(&coll_pairs, &bufs.one_way_states, &bufs.one_way_touching)
+        {
+            if let (Some(passing), true) = (state, touching) {
+                self.one_way_pairs
+                    .insert(ordered_pair(colls, graph).0, *passing);
+            }
+        }
+
+        //
+        // put islands that have been resting long enough to sleep
+        //
+
+        if let Some(params) = self.sleep_params {
+            // island_flags now tells whether every body in the island is resting
+            bufs.island_flags.clear();
+            bufs.island_flags.resize(body_refs.len(), true);
+            bufs.island_timers.clear();
+            bufs.island_timers.resize(body_refs.len(), f64::MAX);
+            for (bi, (body, vel)) in body_refs.iter().zip(&bufs.velocities).enumerate() {
+                if body.sees_forces() {
+                    let resting = vel.linear.mag_sq() < params.linear_threshold.powi(2)
+                        && vel.angular.abs() < params.angular_threshold;
+                    let root = bufs.islands.root(bi);
+                    bufs.island_flags[root] &= resting;
+                }
+            }
+            for (bi, body) in body_refs.iter().enumerate() {
+                if body.sees_forces() && !bufs.sleeping[bi] {
+                    let root = bufs.islands.root(bi);
+                    let timer = &mut bufs.sleep_timers[bi];
+                    *timer = if bufs.island_flags[root] {
+                        *timer + frame_dt
+                    } else {
+                        0.0
+                    };
+                    bufs.island_timers[root] = bufs.island_timers[root].min(*timer);
+                }
+            }
+            for (bi, body) in body_refs.iter().enumerate() {
+                let root = bufs.islands.root(bi);
+                if body.sees_forces()
+                    && !bufs.sleeping[bi]
+                    && bufs.island_timers[root] >= params.time_until_sleep
+                {
+                    bufs.sleeping[bi] = true;
+                    bufs.velocities[bi] = Velocity::default();
                 }
             }
         }
@@ -1109,15 +2137,85 @@ impl Physics {
         // drop body_refs so we can get mutable references
         let body_nodes: Vec<graph::NodePosition> =
             body_refs.into_iter().map(|br| br.pos()).collect();
-        for (body, pose_result, vel_result) in izip!(body_nodes, &bufs.poses, &bufs.velocities) {
+        for (body, com_pose, vel_result, &sleeping, &sleep_timer) in izip!(
+            body_nodes,
+            &bufs.poses,
+            &bufs.velocities,
+            &bufs.sleeping,
+            &bufs.sleep_timers
+        ) {
             let mut body = l_body.get_mut_unchecked(body);
             let mut pose = graph.get_neighbor_mut_unchecked(&body, l_pose).unwrap();
-            body.velocity = *vel_result;
-            *pose = *pose_result;
+            body.sleeping = sleeping;
+            body.sleep_timer = sleep_timer;
+            body.force = m::Vec2::zero();
+            body.torque = 0.0;
+            let sees_forces = body.sees_forces();
+            match body.target_pose.take() {
+                // snap exactly to the target to avoid drifting from rounding errors.
+                // the velocity that took the body there was only for this frame,
+                // so the body keeps the one it had before the target was set
+                Some(target) if !sees_forces => *pose = target,
+                _ => {
+                    body.velocity = Velocity {
+                        linear: vel_result
+                            .point_velocity(-(com_pose.rotation * body.center_of_mass)),
+                        angular: vel_result.angular,
+                    };
+                    *pose = m::Pose::new(
+                        com_pose.translation - com_pose.rotation * body.center_of_mass,
+                        com_pose.rotation,
+                    );
+                }
+            }
+        }
+
+        //
+        // remove constraints that broke this frame
+        //
+
+        let broken_handles: Vec<ConstraintHandle> =
+            izip!(&self.constraint_order, &bufs.constraints_broken)
+                .filter(|(_, &broken)| broken)
+                .map(|(&handle, _)| handle)
+                .collect();
+        for handle in broken_handles {
+            let constraint = match self.remove_constraint(handle) {
+                Some(c) => c,
+                None => continue,
+            };
+            if let Some(mut sink) = graph.get_neighbor_mut_unchecked(&constraint.owner, l_evt_sink)
+            {
+                sink.push(Event::ConstraintBroken(ConstraintBrokenEvent {
+                    handle,
+                    constraint,
+                }));
+            }
         }
     }
 
-    /// Find the first rigid body that intersects with the given point.
+    /// Get the colliders overlapping with the given trigger as of the last tick.
+    ///
+    /// If the given collider is solid, this instead gets the triggers it's inside of.
+    pub fn trigger_overlaps(
+        &self,
+        collider: graph::Node<Collider>,
+    ) -> impl Iterator<Item = graph::Node<Collider>> + '_ {
+        self.trigger_pairs.iter().filter_map(move |pair| {
+            if pair[0] == collider {
+                Some(pair[1])
+            } else if pair[1] == collider {
+                Some(pair[0])
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Find the first rigid body that intersects with the given point,
+    /// along with its pose and the collider containing the point.
+    ///
+    /// Every collider of a compound body is checked.
     pub fn query_point_body<'g>(
         &self,
         graph: &'g graph::Graph,
@@ -1131,16 +2229,912 @@ impl Physics {
         graph::NodeRef<'g, Body>,
     )> {
         l_body.iter(graph).find_map(|rb| {
-            let coll = graph.get_neighbor(&rb, &l_collider)?;
-            let pose = graph.get_neighbor(&rb, &l_pose)?;
-            if collision::query::point_collider_bool(point, &*pose, &*coll) {
-                Some((pose, coll, rb))
+            let pose = graph.get_neighbor(&rb, l_pose)?;
+            let coll = body_colliders(&rb, graph, l_collider).find(|coll| {
+                collision::query::point_collider_bool(point, &(*pose * coll.offset), coll)
+            })?;
+            Some((pose, coll, rb))
+        })
+    }
+
+    /// Find the first collider hit by a ray, if any is hit before `max_dist`.
+    ///
+    /// The ray is treated as if it was a collider on the collision layer `layer`,
+    /// so it only hits colliders on layers that the [`MaskMatrix`][collision::MaskMatrix]
+    /// allows to collide with it. Colliders containing the start of the ray aren't hit.
+    ///
+    /// This uses the spatial index built during the last [`tick`][Self::tick],
+    /// so colliders created after that won't be found.
+    pub fn raycast<'g>(
+        &mut self,
+        graph: &'g graph::Graph,
+        l_pose: &'g graph::Layer<m::Pose>,
+        l_collider: &'g graph::Layer<Collider>,
+        ray: collision::query::Ray,
+        max_dist: f64,
+        layer: usize,
+    ) -> Option<CastHit<'g>> {
+        self.raycast_iter(graph, l_pose, l_collider, ray, max_dist, layer)
+            .min_by(|h1, h2| h1.distance.partial_cmp(&h2.distance).unwrap())
+    }
+
+    /// Find every collider hit by a ray before `max_dist`, sorted by distance from the start.
+    ///
+    /// See [`raycast`][Self::raycast] for details.
+    pub fn raycast_all<'g>(
+        &mut self,
+        graph: &'g graph::Graph,
+        l_pose: &'g graph::Layer<m::Pose>,
+        l_collider: &'g graph::Layer<Collider>,
+        ray: collision::query::Ray,
+        max_dist: f64,
+        layer: usize,
+    ) -> Vec<CastHit<'g>> {
+        let mut hits: Vec<CastHit<'g>> = self
+            .raycast_iter(graph, l_pose, l_collider, ray, max_dist, layer)
+            .collect();
+        hits.sort_by(|h1, h2| h1.distance.partial_cmp(&h2.distance).unwrap());
+        hits
+    }
+
+    fn raycast_iter<'s, 'g: 's>(
+        &'s mut self,
+        graph: &'g graph::Graph,
+        l_pose: &'g graph::Layer<m::Pose>,
+        l_collider: &'g graph::Layer<Collider>,
+        ray: collision::query::Ray,
+        max_dist: f64,
+        layer: usize,
+    ) -> impl 's + Iterator<Item = CastHit<'g>> {
+        let end = ray.point_at(max_dist);
+        let aabb = collision::AABB {
+            min: ray.start.min_by_component(end),
+            max: ray.start.max_by_component(end),
+        };
+        self.spatial_candidates(graph, l_collider, aabb, layer)
+            .filter_map(move |coll| {
+                let pose = collider_pose(graph, l_pose, &coll);
+                let isect = collision::query::ray_collider(ray, max_dist, &pose, &coll)?;
+                Some(CastHit {
+                    collider: coll,
+                    point: ray.point_at(isect.t),
+                    normal: isect.normal,
+                    distance: isect.t,
+                })
+            })
+    }
+
+    /// Move a collider along a vector without rotating it and find
+    /// the first other collider it touches, if any.
+    ///
+    /// The collider's own collision layer is used to filter what it can hit,
+    /// the same way it would be in the physics simulation.
+    /// Colliders already touching it at `start_pose` aren't hit,
+    /// which means you can cast a copy of an existing object's collider from its current pose
+    /// without it hitting itself. `distance` in the result is measured along `motion`.
+    ///
+    /// See [`shape_cast_collider`][collision::query::shape_cast_collider] for details,
+    /// and like [`raycast`][Self::raycast], this uses the spatial index from the last tick.
+    pub fn shapecast<'g>(
+        &mut self,
+        graph: &'g graph::Graph,
+        l_pose: &'g graph::Layer<m::Pose>,
+        l_collider: &'g graph::Layer<Collider>,
+        coll: &Collider,
+        start_pose: &m::Pose,
+        motion: m::Vec2,
+    ) -> Option<CastHit<'g>> {
+        let mut end_pose = *start_pose;
+        end_pose.append_translation(motion);
+        let aabb = coll.aabb(start_pose).union(&coll.aabb(&end_pose));
+        self.spatial_candidates(graph, l_collider, aabb, coll.layer)
+            .filter_map(|other| {
+                let other_pose = collider_pose(graph, l_pose, &other);
+                let isect = collision::query::shape_cast_collider(
+                    coll,
+                    start_pose,
+                    motion,
+                    &other_pose,
+                    &other,
+                )?;
+                Some(CastHit {
+                    collider: other,
+                    point: isect.point,
+                    normal: isect.normal,
+                    distance: isect.t,
+                })
+            })
+            .min_by(|h1, h2| h1.distance.partial_cmp(&h2.distance).unwrap())
+    }
+
+    /// Find every collider that intersects the given area
+    /// and that is allowed to collide with the given collision layer.
+    ///
+    /// This is a [`query_shape`][Self::query_shape] with a rectangle covering the area,
+    /// so colliders are only found if their actual shape reaches into it.
+    pub fn query_aabb<'g>(
+        &mut self,
+        graph: &'g graph::Graph,
+        l_pose: &'g graph::Layer<m::Pose>,
+        l_collider: &'g graph::Layer<Collider>,
+        aabb: collision::AABB,
+        layer: usize,
+    ) -> Vec<graph::NodeRef<'g, Collider>> {
+        let size = aabb.max - aabb.min;
+        let rect = Collider::new_rect(size.x, size.y).with_layer(layer);
+        let pose = m::Pose::new((aabb.min + aabb.max) / 2.0, Default::default());
+        self.query_shape(graph, l_pose, l_collider, &rect, &pose)
+    }
+
+    /// Find every collider that intersects the given collider placed at the given pose.
+    ///
+    /// The collider's own collision layer is used to filter what it can hit,
+    /// the same way it would be in the physics simulation.
+    /// Like [`raycast`][Self::raycast], this uses the spatial index from the last tick.
+    pub fn query_shape<'g>(
+        &mut self,
+        graph: &'g graph::Graph,
+        l_pose: &'g graph::Layer<m::Pose>,
+        l_collider: &'g graph::Layer<Collider>,
+        coll: &Collider,
+        pose: &m::Pose,
+    ) -> Vec<graph::NodeRef<'g, Collider>> {
+        self.spatial_candidates(graph, l_collider, coll.aabb(pose), coll.layer)
+            .filter(|other| {
+                let other_pose = collider_pose(graph, l_pose, other);
+                !matches!(
+                    intersection_check(pose, coll, &other_pose, other),
+                    ContactResult::Zero
+                )
+            })
+            .collect()
+    }
+
+    /// Get the live colliders whose bounding boxes in the spatial index intersect the given one
+    /// and which are allowed to collide with the given layer.
+    fn spatial_candidates<'s, 'g: 's>(
+        &'s mut self,
+        graph: &'g graph::Graph,
+        l_collider: &'g graph::Layer<Collider>,
+        aabb: collision::AABB,
+        layer: usize,
+    ) -> impl 's + Iterator<Item = graph::NodeRef<'g, Collider>> {
+        let mask_matrix = &self.mask_matrix;
+        self.spatial_index
+            .test_aabb(aabb)
+            // the index may be out of date if colliders were deleted since the last tick
+            .filter(move |&idx| {
+                idx < l_collider.content.len()
+                    && graph.get_refcount_unchecked(&l_collider.get_unchecked_by_item_idx(idx)) > 0
+            })
+            .map(move |idx| l_collider.get_unchecked_by_item_idx(idx))
+            .filter(move |coll| mask_matrix.get(layer, coll.layer))
+    }
+}
+
+/// The result of a successful raycast or shapecast.
+#[derive(Clone, Copy, Debug)]
+pub struct CastHit<'g> {
+    /// The collider that was hit.
+    pub collider: graph::NodeRef<'g, Collider>,
+    /// The point where the collider was hit, in world space.
+    pub point: m::Vec2,
+    /// Surface normal of the hit collider at `point`.
+    pub normal: m::Unit<m::Vec2>,
+    /// Distance travelled before the hit.
+    pub distance: f64,
+}
+
+impl WorkingBuffers {
+    /// Compute buoyancy and drag for bodies in fluids into `fluid_forces`.
+    fn compute_fluid_forces(
+        &mut self,
+        coll_pairs: &[[graph::NodeRef<Collider>; 2]],
+        forcefield: &impl ForceField,
+    ) {
+        self.fluid_forces.clear();
+        self.fluid_forces
+            .resize(self.poses.len(), (m::Vec2::zero(), 0.0));
+        for &(pair_idx, fluid_idx) in &self.fluid_pairs {
+            let colls = &coll_pairs[pair_idx];
+            let (fluid_coll, solid_coll) = (&colls[fluid_idx], &colls[1 - fluid_idx]);
+            let fluid = match fluid_coll.ty {
+                ColliderType::Fluid(fluid) => fluid,
+                _ => continue,
+            };
+            let fluid_pose = match self.coll_ctxs[fluid_coll.pos().item_idx] {
+                ColliderContext::Body(b) => {
+                    self.poses[b] * self.coll_offsets[fluid_coll.pos().item_idx]
+                }
+                ColliderContext::Static(pose) => pose,
+            };
+            let bi = match self.coll_ctxs[solid_coll.pos().item_idx] {
+                ColliderContext::Body(bi) => bi,
+                ColliderContext::Static(_) => continue,
+            };
+            let solid_pose = self.poses[bi] * self.coll_offsets[solid_coll.pos().item_idx];
+            if fluid_coll
+                .aabb(&fluid_pose)
+                .intersection(&solid_coll.aabb(&solid_pose))
+                .is_none()
+            {
+                continue;
+            }
+
+            // the surface is perpendicular to gravity
+            let gravity = forcefield.value_at(self.poses[bi].translation);
+            let up = if gravity == m::Vec2::zero() {
+                m::Vec2::unit_y()
             } else {
-                None
+                -gravity.normalized()
+            };
+            let (area, centroid) =
+                match fluid::submerged_part(solid_coll, &solid_pose, fluid_coll, &fluid_pose, up) {
+                    Some(part) => part,
+                    None => continue,
+                };
+
+            let vel = self.velocities[bi];
+            let offset = centroid - self.poses[bi].translation;
+            // buoyancy comes from the weight of the displaced fluid,
+            // which doesn't care about the body's gravity scale
+            let buoyancy = -gravity * fluid.density * area;
+            let drag =
+                (fluid.flow_velocity - vel.point_velocity(offset)) * (fluid.linear_drag * area);
+            let force = buoyancy + drag;
+            let torque = offset.wedge(force).xy - fluid.angular_drag * area * vel.angular;
+
+            let acc = &mut self.fluid_forces[bi];
+            acc.0 += force;
+            acc.1 += torque;
+        }
+    }
+
+    /// Sweep ccd bodies from their previous position to the predicted one
+    /// and move them back to the first time of impact if they hit something on the way.
+    ///
+    /// Bodies can be pushed much faster than their velocity at the start of the frame,
+    /// so obstacles are looked up along the actual path every substep
+    /// instead of relying on the pairs found when the spatial index was built.
+    fn sweep_ccd_bodies(
+        &mut self,
+        l_collider: &graph::Layer<Collider>,
+        spatial_index: &mut HGrid,
+        mask_matrix: &collision::MaskMatrix,
+    ) {
+        if !self.ccd_colliders.is_empty() {
+            let _ccd_span = tracy_span!("continuous collision detection", "tick");
+
+            self.ccd_times_of_impact.clear();
+            self.ccd_times_of_impact.resize(self.poses.len(), 1.0);
+            for &(coll_idx, bi) in &self.ccd_colliders {
+                let coll = l_collider.get_unchecked_by_item_idx(coll_idx);
+                let motion = self.poses[bi].translation - self.old_poses[bi].translation;
+                if motion == m::Vec2::zero() {
+                    continue;
+                }
+                let mut start_pose = self.poses[bi];
+                start_pose.translation = self.old_poses[bi].translation;
+                let start_pose = start_pose * self.coll_offsets[coll_idx];
+                let mut end_pose = start_pose;
+                end_pose.append_translation(motion);
+                let swept_aabb = coll.aabb(&start_pose).union(&coll.aabb(&end_pose));
+
+                for other_idx in spatial_index.test_aabb(swept_aabb) {
+                    let other = l_collider.get_unchecked_by_item_idx(other_idx);
+                    if !other.is_solid()
+                        || other.one_way.is_some()
+                        || !mask_matrix.get(coll.layer, other.layer)
+                    {
+                        continue;
+                    }
+                    let other_pose = match self.coll_ctxs[other_idx] {
+                        ColliderContext::Body(b) => {
+                            let same_soft_body = self.soft_body_of[bi].is_some()
+                                && self.soft_body_of[bi] == self.soft_body_of[b];
+                            if b == bi || same_soft_body {
+                                continue;
+                            }
+                            self.poses[b] * self.coll_offsets[other_idx]
+                        }
+                        ColliderContext::Static(pose) => pose,
+                    };
+                    // bodies resting against something start every substep touching it.
+                    // sweep those from just outside so they can't be pushed through it either
+                    let mut cast_start = start_pose;
+                    let deepest_contact =
+                        intersection_check(&start_pose, &coll, &other_pose, &other)
+                            .iter()
+                            .map(|c| {
+                                let depth = (start_pose * c.offsets[0] - other_pose * c.offsets[1])
+                                    .dot(*c.normal);
+                                (depth, c.normal)
+                            })
+                            .max_by(|(d1, _), (d2, _)| d1.partial_cmp(d2).unwrap());
+                    if let Some((depth, normal)) = deepest_contact {
+                        const SEPARATION: f64 = 0.0001;
+                        cast_start.append_translation(-(depth.max(0.0) + SEPARATION) * *normal);
+                    }
+                    if let Some(isect) = collision::query::shape_cast_collider(
+                        &coll,
+                        &cast_start,
+                        motion,
+                        &other_pose,
+                        &other,
+                    ) {
+                        let toi = &mut self.ccd_times_of_impact[bi];
+                        *toi = toi.min(isect.t / motion.mag());
+                    }
+                }
             }
-        })
+            for (old_pose, pose, toi) in
+                izip!(&self.old_poses, &mut self.poses, &self.ccd_times_of_impact)
+            {
+                if *toi < 1.0 {
+                    pose.translation =
+                        old_pose.translation + *toi * (pose.translation - old_pose.translation);
+                }
+            }
+        }
+    }
+
+    /// Keep soft bodies' edges at their length and their enclosed area at its rest area.
+    fn solve_soft_body_constraints(
+        &mut self,
+        soft_body_refs: &[graph::NodeRef<SoftBody>],
+        body_refs: &[graph::NodeRef<Body>],
+        inv_dt_sq: f64,
+    ) {
+        for (soft_body, range) in soft_body_refs.iter().zip(&self.soft_body_ranges) {
+            let particles = &self.soft_body_particles[range.clone()];
+            // a soft body is always in a single island, so it all sleeps together
+            if particles.is_empty() || self.sleeping[particles[0]] {
+                continue;
+            }
+            let particle_count = particles.len();
+
+            // edge lengths
+            for i in 0..particle_count {
+                let (curr, next) = (particles[i], particles[(i + 1) % particle_count]);
+                let dist = self.poses[next].translation - self.poses[curr].translation;
+                let dist_mag = dist.mag();
+                if dist_mag == 0.0 {
+                    continue;
+                }
+                let dir = dist / dist_mag;
+                let error = soft_body.spacing - dist_mag;
+
+                let lambda = -error
+                    / (body_refs[curr].mass.inv()
+                        + body_refs[next].mass.inv()
+                        + soft_body.compliance * inv_dt_sq);
+
+                self.poses[curr].append_translation(body_refs[curr].mass.inv() * lambda * dir);
+                self.poses[next].append_translation(-body_refs[next].mass.inv() * lambda * dir);
+            }
+
+            // enclosed area,
+            // the gradient of the area w.r.t. each particle is perpendicular to the line
+            // between its neighbors and half its length
+            let position = |i: usize| self.poses[particles[i % particle_count]].translation;
+            let area = 0.5
+                * (0..particle_count)
+                    .map(|i| position(i).wedge(position(i + 1)).xy)
+                    .sum::<f64>();
+            let gradient = |i: usize| {
+                0.5 * m::right_normal(position(i + 1) - position(i + particle_count - 1))
+            };
+            let weighted_grad_sq: f64 = (0..particle_count)
+                .map(|i| body_refs[particles[i]].mass.inv() * gradient(i).mag_sq())
+                .sum();
+            let error = area - soft_body.rest_area;
+            let lambda = -error / (weighted_grad_sq + soft_body.area_compliance * inv_dt_sq);
+            if lambda.is_finite() && lambda != 0.0 {
+                // gradients are computed from positions before any corrections,
+                // so remember the original positions of moved particles that are still needed
+                let first = position(0);
+                let mut prev = position(particle_count - 1);
+                for (i, &particle) in particles.iter().enumerate() {
+                    let curr = self.poses[particle].translation;
+                    let next = match particles.get(i + 1) {
+                        Some(&next) => self.poses[next].translation,
+                        None => first,
+                    };
+                    let gradient = 0.5 * m::right_normal(next - prev);
+                    self.poses[particle]
+                        .append_translation(body_refs[particle].mass.inv() * lambda * gradient);
+                    prev = curr;
+                }
+            }
+        }
+    }
+
+    /// Solve contacts between soft body surfaces and the rigid colliders near them.
+    /// Edges between particles are treated as capsules as thick as the particles.
+    fn solve_soft_body_contacts(
+        &mut self,
+        soft_body_refs: &[graph::NodeRef<SoftBody>],
+        body_refs: &[graph::NodeRef<Body>],
+        l_collider: &graph::Layer<Collider>,
+        material_pairs: &collision::MaterialPairTable,
+        spatial_index: &HGrid,
+    ) {
+        for &(sbi, coll_idx) in &self.soft_body_surface_pairs {
+            let soft_body = &soft_body_refs[sbi];
+            let particles = &self.soft_body_particles[self.soft_body_ranges[sbi].clone()];
+            if self.sleeping[particles[0]] {
+                continue;
+            }
+            let coll = l_collider.get_unchecked_by_item_idx(coll_idx);
+            let coll_mat = match coll.ty {
+                ColliderType::Solid(mat) => mat,
+                _ => continue,
+            };
+            let coefs = material_pairs.coefficients(&soft_body.material, &coll_mat);
+            // the other body is treated as static if it can't move this step
+            let other_body = match self.coll_ctxs[coll_idx] {
+                ColliderContext::Body(b) if body_refs[b].sees_forces() && !self.sleeping[b] => {
+                    Some(b)
+                }
+                _ => None,
+            };
+            let coll_aabb = spatial_index.get_aabb(coll_idx);
+
+            let particle_count = particles.len();
+            for i in 0..particle_count {
+                let (p0, p1) = (particles[i], particles[(i + 1) % particle_count]);
+                let start = self.poses[p0].translation;
+                let end = self.poses[p1].translation;
+                let edge = end - start;
+                let edge_len = edge.mag();
+                if edge_len == 0.0 {
+                    continue;
+                }
+                let edge_coll = Collider::new_capsule(edge_len, soft_body.thickness / 2.0);
+                let edge_pose = m::Pose::new(
+                    0.5 * (start + end),
+                    m::Rotor2::from_angle(edge.y.atan2(edge.x)),
+                );
+                if edge_coll
+                    .aabb(&edge_pose)
+                    .intersection(&coll_aabb)
+                    .is_none()
+                {
+                    continue;
+                }
+                let coll_pose = match self.coll_ctxs[coll_idx] {
+                    ColliderContext::Body(b) => self.poses[b] * self.coll_offsets[coll_idx],
+                    ColliderContext::Static(pose) => pose,
+                };
+
+                for contact in intersection_check(&edge_pose, &edge_coll, &coll_pose, &coll).iter()
+                {
+                    let normal = *contact.normal;
+                    let edge_point = edge_pose * contact.offsets[0];
+                    let coll_point = coll_pose * contact.offsets[1];
+                    let depth = (edge_point - coll_point).dot(normal);
+                    if depth <= 0.0 {
+                        continue;
+                    }
+                    // particles collide as circles too, leave contacts they touch to them
+                    // so they aren't resolved twice
+                    let particle_radius = soft_body.thickness / 2.0;
+                    if (coll_point - start).mag_sq() < particle_radius.powi(2)
+                        || (coll_point - end).mag_sq() < particle_radius.powi(2)
+                    {
+                        continue;
+                    }
+
+                    // the contact is split between the particles based on where it is
+                    let t =
+                        ((edge_point - start).dot(edge) / (edge_len * edge_len)).clamp(0.0, 1.0);
+                    let weights = [1.0 - t, t];
+                    let inv_masses = [body_refs[p0].mass.inv(), body_refs[p1].mass.inv()];
+                    let edge_eff_inv_mass = weights[0] * weights[0] * inv_masses[0]
+                        + weights[1] * weights[1] * inv_masses[1];
+                    let (other_offset, other_eff_inv_mass) = match other_body {
+                        Some(b) => {
+                            let offset = coll_point - self.poses[b].translation;
+                            let offset_wedge_normal = offset.wedge(normal).xy;
+                            (
+                                offset,
+                                body_refs[b].mass.inv()
+                                    + offset_wedge_normal.powi(2)
+                                        * body_refs[b].moment_of_inertia.inv(),
+                            )
+                        }
+                        None => (m::Vec2::zero(), 0.0),
+                    };
+                    let eff_inv_mass_sum = edge_eff_inv_mass + other_eff_inv_mass;
+                    if eff_inv_mass_sum == 0.0 {
+                        continue;
+                    }
+                    let lambda_n = -depth / eff_inv_mass_sum;
+
+                    let apply = |lambda: f64, dir: m::Vec2, poses: &mut [m::Pose]| {
+                        for (particle, weight, inv_mass) in izip!([p0, p1], weights, inv_masses) {
+                            poses[particle].append_translation(weight * inv_mass * lambda * dir);
+                        }
+                        if let Some(b) = other_body {
+                            poses[b].append_translation(-body_refs[b].mass.inv() * lambda * dir);
+                            poses[b].prepend_rotation(
+                                Angle::Rad(
+                                    -body_refs[b].moment_of_inertia.inv()
+                                        * lambda
+                                        * other_offset.wedge(dir).xy,
+                                )
+                                .into(),
+                            );
+                        }
+                    };
+                    apply(lambda_n, normal, &mut self.poses);
+
+                    // static friction
+                    if let Some(friction_coef) = coefs.static_friction_coef {
+                        let tangent = m::left_normal(normal);
+                        let edge_motion = weights[0]
+                            * (self.poses[p0].translation - self.old_poses[p0].translation)
+                            + weights[1]
+                                * (self.poses[p1].translation - self.old_poses[p1].translation);
+                        let other_motion = match other_body {
+                            Some(b) => {
+                                let local = self.poses[b].inversed() * coll_point;
+                                coll_point - self.old_poses[b] * local
+                            }
+                            None => m::Vec2::zero(),
+                        };
+                        let motion_along_tan = (edge_motion - other_motion).dot(tangent);
+                        let other_eff_inv_mass_tan = match other_body {
+                            Some(b) => {
+                                body_refs[b].mass.inv()
+                                    + other_offset.wedge(tangent).xy.powi(2)
+                                        * body_refs[b].moment_of_inertia.inv()
+                            }
+                            None => 0.0,
+                        };
+                        let lambda_t =
+                            -motion_along_tan / (edge_eff_inv_mass + other_eff_inv_mass_tan);
+                        if lambda_t.abs() < -lambda_n * friction_coef {
+                            apply(lambda_t, tangent, &mut self.poses);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Damp the deformation of soft bodies by pulling particle velocities towards the rigid motion
+    /// of the whole body, which leaves movement and rotation of the body untouched.
+    fn damp_soft_bodies(
+        &mut self,
+        soft_body_refs: &[graph::NodeRef<SoftBody>],
+        body_refs: &[graph::NodeRef<Body>],
+        dt: f64,
+    ) {
+        for (soft_body, range) in soft_body_refs.iter().zip(&self.soft_body_ranges) {
+            let particles = &self.soft_body_particles[range.clone()];
+            if particles.is_empty() || self.sleeping[particles[0]] {
+                continue;
+            }
+            // pinned particles don't count towards the motion of the body
+            let masses = particles.iter().map(|&p| match body_refs[p].mass {
+                Mass::Finite { mass, .. } => mass,
+                Mass::Infinite => 0.0,
+            });
+            let (total_mass, weighted_pos, weighted_vel) = izip!(particles, masses.clone()).fold(
+                (0.0, m::Vec2::zero(), m::Vec2::zero()),
+                |(total_mass, weighted_pos, weighted_vel), (&p, mass)| {
+                    (
+                        total_mass + mass,
+                        weighted_pos + self.poses[p].translation * mass,
+                        weighted_vel + self.velocities[p].linear * mass,
+                    )
+                },
+            );
+            if total_mass == 0.0 {
+                continue;
+            }
+            let center = weighted_pos / total_mass;
+            let center_vel = weighted_vel / total_mass;
+            let (ang_momentum, mom_inertia) = izip!(particles, masses.clone()).fold(
+                (0.0, 0.0),
+                |(ang_momentum, mom_inertia), (&p, mass)| {
+                    let offset = self.poses[p].translation - center;
+                    (
+                        ang_momentum + offset.wedge(self.velocities[p].linear * mass).xy,
+                        mom_inertia + offset.mag_sq() * mass,
+                    )
+                },
+            );
+            let ang_vel = if mom_inertia > 0.0 {
+                ang_momentum / mom_inertia
+            } else {
+                0.0
+            };
+            let damping = (soft_body.damping * dt).min(1.0);
+            for (&p, mass) in izip!(particles, masses) {
+                if mass == 0.0 {
+                    continue;
+                }
+                let offset = self.poses[p].translation - center;
+                let rigid_vel = center_vel + ang_vel * m::left_normal(offset);
+                let vel = &mut self.velocities[p].linear;
+                *vel += (rigid_vel - *vel) * damping;
+            }
+        }
+    }
+
+    /// Send contact events for this frame's contacts,
+    /// comparing them to the contacts that were active before the frame.
+    fn emit_contact_events(
+        &self,
+        coll_pairs: &[[graph::NodeRef<Collider>; 2]],
+        body_refs: &[graph::NodeRef<Body>],
+        graph: &graph::Graph,
+        l_evt_sink: &mut graph::Layer<EventSink>,
+        active_contacts: &mut BTreeMap<[graph::Node<Collider>; 2], ContactEvent>,
+    ) {
+        let mut prev_contacts = std::mem::take(active_contacts);
+        for (colls, contact, &impulse) in
+            izip!(coll_pairs, &self.frame_contacts, &self.contact_impulses)
+        {
+            let ctxs = map_pair(colls, |c| self.coll_ctxs[c.pos().item_idx]);
+            let (nodes, flip) = ordered_pair(colls, graph);
+            let prev = prev_contacts.remove(&nodes);
+
+            let awake = ctxs.iter().any(|ctx| match ctx {
+                ColliderContext::Body(bi) => body_refs[*bi].sees_forces() && !self.sleeping[*bi],
+                ColliderContext::Static(_) => false,
+            });
+            if !awake {
+                // contacts weren't checked, keep sleeping contacts alive without events
+                if let Some(prev) = prev {
+                    active_contacts.insert(nodes, prev);
+                }
+                continue;
+            }
+
+            let first_contact = match contact.iter().next() {
+                Some(c) => c,
+                None => {
+                    if let Some(prev) = prev {
+                        push_contact_events(graph, l_evt_sink, nodes, prev, Event::ContactEnd);
+                    }
+                    continue;
+                }
+            };
+            let poses = map_pair(&ctxs, |ctx| match ctx {
+                ColliderContext::Body(b) => self.poses[*b],
+                ColliderContext::Static(pose) => *pose,
+            });
+            let mut points = [m::Vec2::zero(); 2];
+            let mut point_count = 0;
+            for (point, c) in points.iter_mut().zip(contact.iter()) {
+                *point = 0.5 * (poses[0] * c.offsets[0] + poses[1] * c.offsets[1]);
+                point_count += 1;
+            }
+            let evt = ContactEvent {
+                collider: graph::NodeRef::as_node(&colls[0], graph),
+                other_collider: graph::NodeRef::as_node(&colls[1], graph),
+                normal: first_contact.normal,
+                impulse: impulse.max(0.0),
+                points,
+                point_count,
+            };
+            let evt = if flip { evt.flipped() } else { evt };
+
+            let variant = if prev.is_some() {
+                Event::ContactPersist
+            } else {
+                Event::ContactBegin
+            };
+            push_contact_events(graph, l_evt_sink, nodes, evt, variant);
+            active_contacts.insert(
+                nodes,
+                ContactEvent {
+                    impulse: 0.0,
+                    ..evt
+                },
+            );
+        }
+        // pairs that are no longer near each other or whose colliders were deleted
+        for (nodes, prev) in prev_contacts {
+            push_contact_events(graph, l_evt_sink, nodes, prev, Event::ContactEnd);
+        }
+    }
+
+    /// Send trigger events for pairs where a trigger started or stopped overlapping something.
+    fn emit_trigger_events(
+        &self,
+        coll_pairs: &[[graph::NodeRef<Collider>; 2]],
+        graph: &graph::Graph,
+        l_evt_sink: &mut graph::Layer<EventSink>,
+        trigger_pairs: &mut BTreeSet<[graph::Node<Collider>; 2]>,
+    ) {
+        let mut prev_overlaps = std::mem::take(trigger_pairs);
+        for colls in coll_pairs {
+            if let (ColliderType::Solid(_), ColliderType::Solid(_)) = (colls[0].ty, colls[1].ty) {
+                continue;
+            }
+            let ctxs = map_pair(colls, |c| self.coll_ctxs[c.pos().item_idx]);
+            // static triggers only detect bodies
+            if let [ColliderContext::Static(_), ColliderContext::Static(_)] = ctxs {
+                continue;
+            }
+            let poses = map_pair(&[0, 1], |i| match ctxs[*i] {
+                ColliderContext::Body(b) => {
+                    self.poses[b] * self.coll_offsets[colls[*i].pos().item_idx]
+                }
+                ColliderContext::Static(pose) => pose,
+            });
+            let overlapping = !matches!(
+                intersection_check(&poses[0], &colls[0], &poses[1], &colls[1]),
+                ContactResult::Zero
+            );
+
+            let (nodes, _) = ordered_pair(colls, graph);
+            let was_overlapping = prev_overlaps.remove(&nodes);
+            if overlapping {
+                if !was_overlapping {
+                    push_trigger_events(graph, l_evt_sink, nodes, Event::TriggerEnter);
+                }
+                trigger_pairs.insert(nodes);
+            } else if was_overlapping {
+                push_trigger_events(graph, l_evt_sink, nodes, Event::TriggerExit);
+            }
+        }
+        for nodes in prev_overlaps {
+            push_trigger_events(graph, l_evt_sink, nodes, Event::TriggerExit);
+        }
+    }
+}
+
+/// Get the pose of a collider, whether it's attached to a body or not.
+fn collider_pose(
+    graph: &graph::Graph,
+    l_pose: &graph::Layer<m::Pose>,
+    coll: &graph::NodeRef<Collider>,
+) -> m::Pose {
+    graph
+        .get_neighbor(coll, l_pose)
+        .map(|p| *p * coll.offset)
+        .unwrap_or(coll.offset)
+}
+
+/// The bodies participating in a user-defined constraint
+/// along with their inverse masses, with helpers for XPBD corrections.
+/// The second body is the static world if it's `None`.
+struct ConstraintPair {
+    bodies: (usize, Option<usize>),
+    inv_masses: [f64; 2],
+    inv_mom_inertias: [f64; 2],
+}
+
+impl ConstraintPair {
+    fn rotations(&self, poses: &[m::Pose]) -> [m::Rotor2; 2] {
+        [
+            poses[self.bodies.0].rotation,
+            self.bodies
+                .1
+                .map(|b| poses[b].rotation)
+                .unwrap_or_else(m::Rotor2::identity),
+        ]
+    }
+
+    /// The owner's angle minus the target's, minus the rest angle.
+    fn relative_angle(&self, poses: &[m::Pose], rest_angle: f64) -> f64 {
+        let [r0, r1] = self.rotations(poses);
+        Angle::from(r0 * r1.reversed() * m::Rotor2::from_angle(-rest_angle)).rad()
+    }
+
+    /// How much the relative angle has changed since the old poses.
+    fn relative_rotation_change(&self, old_poses: &[m::Pose], poses: &[m::Pose]) -> f64 {
+        let [r0, r1] = self.rotations(poses);
+        let [old_r0, old_r1] = self.rotations(old_poses);
+        Angle::from(r0 * old_r0.reversed()).rad() - Angle::from(r1 * old_r1.reversed()).rad()
+    }
+
+    /// Get the Lagrange multiplier for a position correction along `dir`
+    /// at the given rotated offsets. `error` is how far the first body should move
+    /// along `dir` relative to the second.
+    fn positional_lambda(
+        &self,
+        offsets_rotated: [m::Vec2; 2],
+        dir: m::Vec2,
+        error: f64,
+        compliance_term: f64,
+    ) -> f64 {
+        let eff_inv_masses = map_pair(&[0, 1], |i| {
+            self.inv_masses[*i]
+                + offsets_rotated[*i].wedge(dir).xy.powi(2) * self.inv_mom_inertias[*i]
+        });
+        error / (eff_inv_masses[0] + eff_inv_masses[1] + compliance_term)
+    }
+
+    /// Move the first body along `dir` and the second against it.
+    fn apply_positional(
+        &self,
+        poses: &mut [m::Pose],
+        offsets_rotated: [m::Vec2; 2],
+        dir: m::Vec2,
+        lambda: f64,
+    ) {
+        let b0 = self.bodies.0;
+        poses[b0].append_translation(self.inv_masses[0] * lambda * dir);
+        poses[b0].prepend_rotation(
+            Angle::Rad(self.inv_mom_inertias[0] * lambda * offsets_rotated[0].wedge(dir).xy).into(),
+        );
+        if let Some(b1) = self.bodies.1 {
+            poses[b1].append_translation(-self.inv_masses[1] * lambda * dir);
+            poses[b1].prepend_rotation(
+                Angle::Rad(-self.inv_mom_inertias[1] * lambda * offsets_rotated[1].wedge(dir).xy)
+                    .into(),
+            );
+        }
+    }
+
+    /// Get the Lagrange multiplier for a correction of the relative angle.
+    /// Positive `error` means the relative angle is too large.
+    fn angular_lambda(&self, error: f64, compliance_term: f64) -> f64 {
+        -error / (self.inv_mom_inertias[0] + self.inv_mom_inertias[1] + compliance_term)
+    }
+
+    /// Rotate the first body by `lambda` scaled by inverse moment of inertia
+    /// and the second in the opposite direction.
+    fn apply_angular(&self, poses: &mut [m::Pose], lambda: f64) {
+        poses[self.bodies.0].prepend_rotation(Angle::Rad(self.inv_mom_inertias[0] * lambda).into());
+        if let Some(b1) = self.bodies.1 {
+            poses[b1].prepend_rotation(Angle::Rad(-self.inv_mom_inertias[1] * lambda).into());
+        }
+    }
+
+    /// World-space positions of points given relative to each body
+    /// (or the world, for the second point if there's no target),
+    /// along with the offsets rotated to world orientation.
+    fn world_points(
+        &self,
+        poses: &[m::Pose],
+        offsets: [m::Vec2; 2],
+    ) -> ([m::Vec2; 2], [m::Vec2; 2]) {
+        let pose0 = poses[self.bodies.0];
+        let offsets_rotated = [
+            pose0.rotation * offsets[0],
+            self.bodies
+                .1
+                .map(|b| poses[b].rotation * offsets[1])
+                .unwrap_or_else(m::Vec2::zero),
+        ];
+        let points = [
+            pose0.translation + offsets_rotated[0],
+            self.bodies
+                .1
+                .map(|b| poses[b].translation + offsets_rotated[1])
+                .unwrap_or(offsets[1]),
+        ];
+        (points, offsets_rotated)
+    }
+
+    /// Pull the origin points of the constraint together,
+    /// returning the Lagrange multiplier used.
+    fn attach_origins(
+        &self,
+        poses: &mut [m::Pose],
+        offsets: [m::Vec2; 2],
+        compliance_term: f64,
+    ) -> f64 {
+        let (points, offsets_rotated) = self.world_points(poses, offsets);
+        let dist = points[1] - points[0];
+        let dist_mag = dist.mag();
+        if dist_mag == 0.0 {
+            return 0.0;
+        }
+        let dir = dist / dist_mag;
+        let lambda = self.positional_lambda(offsets_rotated, dir, dist_mag, compliance_term);
+        self.apply_positional(poses, offsets_rotated, dir, lambda);
+        lambda
     }
 }
+
 //
 // helpers to reduce duplication when fetching info for pairs of objects
 fn map_pair<T, R>(pair: &[T; 2], f: impl Fn(&T) -> R) -> [R; 2] {
@@ -1150,3 +3144,77 @@ fn map_pair<T, R>(pair: &[T; 2], f: impl Fn(&T) -> R) -> [R; 2] {
 fn map_semi_pair<T, R>(pair: (T, Option<T>), f: impl Fn(&T) -> R, snd_default: R) -> [R; 2] {
     [f(&pair.0), pair.1.map(|x| f(&x)).unwrap_or(snd_default)]
 }
+
+/// Whether the bodies held by a constraint are asleep and it doesn't need solving.
+///
+/// Only bodies that respond to forces sleep, and those constrained together
+/// are in the same island, so checking one of them is enough.
+/// Constraints between bodies that don't respond to forces are always solved.
+fn constraint_asleep(
+    pair: (usize, Option<usize>),
+    body_refs: &[graph::NodeRef<Body>],
+    sleeping: &[bool],
+) -> bool {
+    let dynamic_body = std::iter::once(pair.0)
+        .chain(pair.1)
+        .find(|&b| body_refs[b].sees_forces());
+    matches!(dynamic_body, Some(b) if sleeping[b])
+}
+
+/// Velocity of the first material's surface relative to the second's
+/// along the contact tangent, i.e. the left normal of the contact normal.
+fn relative_surface_velocity(mat0: &Material, mat1: &Material) -> f64 {
+    // surfaces move clockwise, which is the right normal of each object's outward normal.
+    // the contact normal points out of the first object and into the second
+    -mat0.surface_velocity - mat1.surface_velocity
+}
+
+/// Get the nodes of a pair of colliders in a consistent order
+/// so that the pair can be recognized across frames,
+/// and whether the order was flipped.
+fn ordered_pair(
+    colls: &[graph::NodeRef<Collider>; 2],
+    graph: &graph::Graph,
+) -> ([graph::Node<Collider>; 2], bool) {
+    let nodes = map_pair(colls, |c| graph::NodeRef::as_node(c, graph));
+    if nodes[0].pos().item_idx > nodes[1].pos().item_idx {
+        ([nodes[1], nodes[0]], true)
+    } else {
+        (nodes, false)
+    }
+}
+
+fn push_trigger_events(
+    graph: &graph::Graph,
+    l_evt_sink: &mut graph::Layer<EventSink>,
+    colls: [graph::Node<Collider>; 2],
+    variant: fn(TriggerEvent) -> Event,
+) {
+    for (collider, other_collider) in [(colls[0], colls[1]), (colls[1], colls[0])] {
+        if let Some(coll) = collider.check(graph) {
+            if let Some(mut sink) = graph.get_neighbor_mut(&coll, l_evt_sink) {
+                sink.push(variant(TriggerEvent {
+                    collider,
+                    other_collider,
+                }));
+            }
+        }
+    }
+}
+
+/// Send a contact event given from the first collider's point of view to both colliders.
+fn push_contact_events(
+    graph: &graph::Graph,
+    l_evt_sink: &mut graph::Layer<EventSink>,
+    colls: [graph::Node<Collider>; 2],
+    evt: ContactEvent,
+    variant: fn(ContactEvent) -> Event,
+) {
+    for (coll, evt) in [(colls[0], evt), (colls[1], evt.flipped())] {
+        if let Some(coll) = coll.check(graph) {
+            if let Some(mut sink) = graph.get_neighbor_mut(&coll, l_evt_sink) {
+                sink.push(variant(evt));
+            }
+        }
+    }
+}
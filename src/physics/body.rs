@@ -1,48 +1,140 @@
 use super::{Collider, Velocity};
+use crate::math as m;
 
 /// A body is something that moves, typically a physics-enabled rigid body or particle.
 /// Connect a Body with a Collider to make it collide with other things.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Body {
+    /// Velocity of the body's pose origin.
     pub velocity: Velocity,
     pub mass: Mass,
+    /// Moment of inertia around the center of mass.
     pub moment_of_inertia: Mass,
+    /// Position of the center of mass relative to the body's pose.
+    ///
+    /// The body rotates around this point. Its velocity and the offsets of user constraints
+    /// are still given relative to the pose.
+    pub center_of_mass: m::Vec2,
+    /// Damping coefficient slowing down linear velocity every step, in 1/s. Defaults to 0.
+    pub linear_damping: f64,
+    /// Damping coefficient slowing down angular velocity every step, in 1/s. Defaults to 0.
+    pub angular_damping: f64,
+    /// Multiplier for the acceleration from the [`ForceField`][super::ForceField]
+    /// given to [`Physics::tick`][super::Physics::tick].
+    /// Set to 0 to make the body float or a negative value to make it rise.
+    /// Defaults to 1.
+    pub gravity_scale: f64,
+    /// Maximum linear speed in m/s. Faster bodies are slowed down to this speed.
+    pub max_linear_speed: Option<f64>,
+    /// Maximum angular speed in rad/s. Faster bodies are slowed down to this speed.
+    pub max_angular_speed: Option<f64>,
+    /// Whether to use continuous collision detection for this body.
+    ///
+    /// This makes fast-moving bodies stop at the first surface in their path
+    /// instead of passing through thin objects, at some extra cost.
+    /// Only turn this on for small, fast things like bullets.
+    pub ccd: bool,
+    // sleep state is managed by `Physics`, users go through the methods below
+    pub(crate) sleeping: bool,
+    pub(crate) sleep_timer: f64,
+    // cleared by `Physics` every tick, set with `set_target_pose`
+    #[serde(with = "m::serde_pose::option")]
+    pub(crate) target_pose: Option<m::Pose>,
+    // accumulated with `apply_force` and friends, cleared by `Physics` every tick
+    pub(crate) force: m::Vec2,
+    pub(crate) torque: f64,
 }
 
 impl Body {
-    /// A particle responds to external forces but does not rotate.
-    pub fn new_particle(mass: f64) -> Self {
+    /// A resting body with the given mass properties and defaults for everything else,
+    /// which every constructor builds on.
+    fn with_mass_properties(mass: Mass, moment_of_inertia: Mass, center_of_mass: m::Vec2) -> Self {
         Self {
             velocity: Velocity::default(),
-            mass: Mass::from(mass),
-            moment_of_inertia: Mass::Infinite,
+            mass,
+            moment_of_inertia,
+            center_of_mass,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            gravity_scale: 1.0,
+            max_linear_speed: None,
+            max_angular_speed: None,
+            ccd: false,
+            sleeping: false,
+            sleep_timer: 0.0,
+            target_pose: None,
+            force: m::Vec2::zero(),
+            torque: 0.0,
         }
     }
 
+    /// A particle responds to external forces but does not rotate.
+    pub fn new_particle(mass: f64) -> Self {
+        Self::with_mass_properties(Mass::from(mass), Mass::Infinite, m::Vec2::zero())
+    }
+
     /// Dynamic bodies respond to external forces and are allowed to rotate.
-    /// This constructor calculates mass and moment of inertia from the given density and
-    /// collider shape.
+    /// This constructor calculates mass, moment of inertia and center of mass
+    /// from the given density and collider shape.
     pub fn new_dynamic(collider: &Collider, density: f64) -> Self {
         Self::new_dynamic_const_mass(collider, collider.area() * density)
     }
 
     /// Create a dynamic body with the given mass instead of using density.
-    /// The collider is still required to compute moment of inertia.
+    /// The collider is still required to compute moment of inertia and center of mass.
     pub fn new_dynamic_const_mass(collider: &Collider, mass: f64) -> Self {
-        Self {
-            velocity: Velocity::default(),
-            mass: Mass::from(mass),
-            moment_of_inertia: Mass::from(collider.moment_of_inertia_coef() * mass),
-        }
+        let props = collider.mass_properties();
+        Self::with_mass_properties(
+            Mass::from(mass),
+            Mass::from(props.moment_of_inertia_coef * mass),
+            props.centroid,
+        )
+    }
+
+    /// Create a dynamic body with several colliders,
+    /// see [`attach_compound`][super::attach_compound].
+    ///
+    /// Mass is summed up from every collider using the given density
+    /// and the center of mass is placed at their combined centroid.
+    /// Each collider's moment of inertia is moved to the center of mass
+    /// with the parallel axis theorem.
+    pub fn new_dynamic_compound(colliders: &[Collider], density: f64) -> Self {
+        let props: Vec<_> = colliders.iter().map(|c| c.mass_properties()).collect();
+        let (mass, weighted_centroids) =
+            props
+                .iter()
+                .fold((0.0, m::Vec2::zero()), |(mass, weighted_centroids), p| {
+                    let coll_mass = p.area * density;
+                    (
+                        mass + coll_mass,
+                        weighted_centroids + p.centroid * coll_mass,
+                    )
+                });
+        let center_of_mass = if mass > 0.0 {
+            weighted_centroids / mass
+        } else {
+            m::Vec2::zero()
+        };
+        let moment_of_inertia: f64 = props
+            .iter()
+            .map(|p| {
+                let parallel_axis = (p.centroid - center_of_mass).mag_sq();
+                p.area * density * (p.moment_of_inertia_coef + parallel_axis)
+            })
+            .sum();
+        Self::with_mass_properties(
+            Mass::from(mass),
+            Mass::from(moment_of_inertia),
+            center_of_mass,
+        )
     }
 
     /// Kinematic bodies are not affected by collision forces.
+    ///
+    /// They move at their velocity, which can also be computed for you
+    /// with [`set_target_pose`][Self::set_target_pose].
     pub fn new_kinematic() -> Self {
-        Self {
-            velocity: Velocity::default(),
-            mass: Mass::Infinite,
-            moment_of_inertia: Mass::Infinite,
-        }
+        Self::with_mass_properties(Mass::Infinite, Mass::Infinite, m::Vec2::zero())
     }
 
     /// Set the velocity of the body in a builder-like chain.
@@ -51,6 +143,126 @@ impl Body {
         self
     }
 
+    /// Move the center of mass to the given point relative to the body's pose
+    /// in a builder-like chain, e.g. to make a toy bottom-heavy.
+    ///
+    /// The moment of inertia is left as it is.
+    pub fn with_center_of_mass(mut self, point: m::Vec2) -> Self {
+        self.center_of_mass = point;
+        self
+    }
+
+    /// Set the linear and angular damping coefficients in a builder-like chain.
+    /// See [`linear_damping`][Self::linear_damping] and
+    /// [`angular_damping`][Self::angular_damping].
+    pub fn with_damping(mut self, linear: f64, angular: f64) -> Self {
+        self.linear_damping = linear;
+        self.angular_damping = angular;
+        self
+    }
+
+    /// Set the gravity scale in a builder-like chain.
+    /// See [`gravity_scale`][Self::gravity_scale].
+    pub fn with_gravity_scale(mut self, scale: f64) -> Self {
+        self.gravity_scale = scale;
+        self
+    }
+
+    /// Limit the linear and angular speed of the body in a builder-like chain.
+    /// See [`max_linear_speed`][Self::max_linear_speed] and
+    /// [`max_angular_speed`][Self::max_angular_speed].
+    pub fn with_max_speed(mut self, linear: f64, angular: f64) -> Self {
+        self.max_linear_speed = Some(linear);
+        self.max_angular_speed = Some(angular);
+        self
+    }
+
+    /// Enable or disable continuous collision detection in a builder-like chain.
+    /// See [`ccd`][Self::ccd] for details.
+    pub fn with_ccd(mut self, enabled: bool) -> Self {
+        self.ccd = enabled;
+        self
+    }
+
+    /// Make a kinematic body move to the given pose during the next tick.
+    ///
+    /// The physics system sets the body's velocity to exactly what's needed to get there
+    /// over the tick, so things touching the body get pushed and carried along properly,
+    /// and the body ends up exactly at the target. The target is cleared after the tick
+    /// and the body gets back the velocity it had before, which it keeps moving at
+    /// until it's given a new target. A body that's only ever moved with targets
+    /// comes to rest at the last one.
+    ///
+    /// This has no effect on bodies that respond to forces.
+    pub fn set_target_pose(&mut self, pose: m::Pose) {
+        self.target_pose = Some(pose);
+    }
+
+    /// Apply a force in newtons to the center of mass during the next tick.
+    ///
+    /// Forces add up until the next tick, which applies them over its whole duration
+    /// and clears them, so continuous forces need to be applied every frame.
+    pub fn apply_force(&mut self, force: m::Vec2) {
+        self.force += force;
+    }
+
+    /// Apply a torque in N⋅m during the next tick. See [`apply_force`][Self::apply_force].
+    pub fn apply_torque(&mut self, torque: f64) {
+        self.torque += torque;
+    }
+
+    /// Apply a force in newtons at a point in world space during the next tick,
+    /// which also applies a torque if the point isn't the center of mass.
+    /// `pose` is the pose of the body. See [`apply_force`][Self::apply_force].
+    pub fn apply_force_at_point(&mut self, pose: &m::Pose, force: m::Vec2, point: m::Vec2) {
+        let offset = point - *pose * self.center_of_mass;
+        self.force += force;
+        self.torque += offset.wedge(force).xy;
+    }
+
+    /// Apply an impulse in N⋅s to the center of mass, changing the velocity immediately.
+    pub fn apply_impulse(&mut self, impulse: m::Vec2) {
+        self.velocity.linear += impulse * self.mass.inv();
+    }
+
+    /// Apply an impulse in N⋅s at a point in world space, changing the velocity immediately.
+    /// `pose` is the pose of the body.
+    pub fn apply_impulse_at_point(&mut self, pose: &m::Pose, impulse: m::Vec2, point: m::Vec2) {
+        let com_offset = pose.rotation * self.center_of_mass;
+        let angular_change = (point - pose.translation - com_offset).wedge(impulse).xy
+            * self.moment_of_inertia.inv();
+        // the velocity is given at the pose origin, which also moves if the body rotates
+        self.velocity.linear +=
+            impulse * self.mass.inv() - m::left_normal(com_offset) * angular_change;
+        self.velocity.angular += angular_change;
+    }
+
+    /// Check whether the body is currently sleeping.
+    ///
+    /// Sleeping bodies have come to rest and are skipped by the physics simulation
+    /// until something wakes them up.
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    /// Put the body to sleep immediately, stopping it in place.
+    ///
+    /// The body is woken up by the same things that wake up naturally sleeping bodies,
+    /// including touching or being constrained to a body that is awake.
+    pub fn sleep(&mut self) {
+        self.sleeping = true;
+        self.velocity = Velocity::default();
+    }
+
+    /// Wake the body up if it's sleeping.
+    ///
+    /// Setting the velocity of a sleeping body to something nonzero also wakes it up,
+    /// so this is only needed to make a body respond to forces again.
+    pub fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.sleep_timer = 0.0;
+    }
+
     /// Check whether the body has finite mass or moment of inertia, allowing forces to have an
     /// effect on it.
     pub fn sees_forces(&self) -> bool {
@@ -65,7 +277,7 @@ impl Body {
 ///
 /// This stores both a mass value and its inverse, because calculating inverse mass
 /// is expensive and needed a lot in physics calculations.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Mass {
     Finite { mass: f64, inverse: f64 },
     Infinite,
@@ -89,3 +301,276 @@ impl Mass {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph,
+        physics::tests::{angle, World},
+    };
+
+    //
+    // center of mass
+    //
+
+    #[test]
+    fn body_velocity_is_given_at_pose_origin() {
+        let mut world = World::new();
+        world.gravity = m::Vec2::zero();
+        let com = m::Vec2::new(0.2, 0.0);
+        let angular = 2.0;
+        let coll = Collider::new_square(0.5);
+        // spinning around a stationary center of mass, which moves the pose origin
+        let b = world.spawn_body(
+            Body::new_dynamic(&coll, 1.0)
+                .with_center_of_mass(com)
+                .with_velocity(Velocity {
+                    linear: -m::left_normal(com) * angular,
+                    angular,
+                }),
+            coll,
+            m::Pose::default(),
+        );
+        for _ in 0..30 {
+            world.tick();
+        }
+        let pose = world.pose(b);
+        let vel = world.body(b).velocity;
+        assert!((pose * com - com).mag() < 1e-6);
+        assert!((angle(pose) - 1.0).abs() < 1e-6);
+        let expected = -m::left_normal(pose.rotation * com) * angular;
+        assert!((vel.linear - expected).mag() < 1e-6);
+    }
+
+    //
+    // kinematic bodies
+    //
+
+    #[test]
+    fn kinematic_body_lands_exactly_on_target_pose() {
+        let mut world = World::new();
+        let coll = Collider::new_square(0.5);
+        let b = world.spawn_body(Body::new_kinematic(), coll, m::Pose::default());
+        let target = m::Pose::new(m::Vec2::new(0.3, -0.2), m::Rotor2::from_angle(0.4));
+        world.body_mut(b).set_target_pose(target);
+        world.tick();
+        assert_eq!(world.pose(b), target);
+    }
+
+    /// The velocity that takes a body to its target only lasts for the tick,
+    /// so a body given a single target stops there.
+    #[test]
+    fn kinematic_body_comes_to_rest_at_target_pose() {
+        let mut world = World::new();
+        let coll = Collider::new_square(0.5);
+        let b = world.spawn_body(Body::new_kinematic(), coll, m::Pose::default());
+        let target = m::Pose::new(m::Vec2::new(0.3, -0.2), m::Rotor2::from_angle(0.4));
+        world.body_mut(b).set_target_pose(target);
+        world.tick();
+        let vel = world.body(b).velocity;
+        assert_eq!(vel.linear, m::Vec2::zero());
+        assert_eq!(vel.angular, 0.0);
+        for _ in 0..10 {
+            world.tick();
+        }
+        assert_eq!(world.pose(b), target);
+    }
+
+    /// A body moving at a velocity of its own goes back to it after reaching a target.
+    #[test]
+    fn kinematic_body_keeps_its_own_velocity_after_target_pose() {
+        let mut world = World::new();
+        let coll = Collider::new_square(0.5);
+        let velocity = Velocity {
+            linear: m::Vec2::new(1.0, 0.0),
+            angular: 0.0,
+        };
+        let b = world.spawn_body(
+            Body::new_kinematic().with_velocity(velocity),
+            coll,
+            m::Pose::default(),
+        );
+        let target = m::Pose::new(m::Vec2::new(0.0, 0.5), Default::default());
+        world.body_mut(b).set_target_pose(target);
+        world.tick();
+        assert_eq!(world.pose(b), target);
+        world.tick();
+        let pose = world.pose(b);
+        assert!((pose.translation - m::Vec2::new(1.0 / 60.0, 0.5)).mag() < 1e-9);
+        assert!(angle(pose).abs() < 1e-9);
+    }
+
+    #[test]
+    fn target_pose_is_ignored_by_dynamic_bodies() {
+        let mut world = World::new();
+        world.gravity = m::Vec2::zero();
+        let b = world.spawn_box(m::Vec2::zero(), 0.0);
+        world
+            .body_mut(b)
+            .set_target_pose(m::Pose::new(m::Vec2::new(1.0, 0.0), Default::default()));
+        world.tick();
+        assert_eq!(world.pose(b), m::Pose::default());
+    }
+
+    //
+    // damping, gravity scale and speed limits
+    //
+
+    fn spawn_moving_box(
+        world: &mut World,
+        x: f64,
+        body: impl FnOnce(Body) -> Body,
+    ) -> graph::Node<Body> {
+        let coll = Collider::new_square(0.5);
+        world.spawn_body(
+            body(Body::new_dynamic(&coll, 1.0)),
+            coll,
+            m::Pose::new(m::Vec2::new(x, 0.0), Default::default()),
+        )
+    }
+
+    #[test]
+    fn damping_slows_bodies_down_exponentially() {
+        let mut world = World::new();
+        world.gravity = m::Vec2::zero();
+        let vel = Velocity {
+            linear: m::Vec2::new(2.0, 0.0),
+            angular: 3.0,
+        };
+        let damped = spawn_moving_box(&mut world, -4.0, |b| {
+            b.with_velocity(vel).with_damping(1.0, 2.0)
+        });
+        let undamped = spawn_moving_box(&mut world, 4.0, |b| b.with_velocity(vel));
+        for _ in 0..60 {
+            world.tick();
+        }
+        let damped_vel = world.body(damped).velocity;
+        assert!((damped_vel.linear.x - 2.0 * (-1.0f64).exp()).abs() < 0.01);
+        assert!((damped_vel.angular - 3.0 * (-2.0f64).exp()).abs() < 0.01);
+        let undamped_vel = world.body(undamped).velocity;
+        assert!((undamped_vel.linear.x - 2.0).abs() < 1e-9);
+        assert!((undamped_vel.angular - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn gravity_scale_multiplies_gravity() {
+        let mut world = World::new();
+        let bodies: Vec<(f64, graph::Node<Body>)> = [0.0, 2.0, -1.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &scale)| {
+                let x = i as f64 * 2.0;
+                (
+                    scale,
+                    spawn_moving_box(&mut world, x, |b| b.with_gravity_scale(scale)),
+                )
+            })
+            .collect();
+        for _ in 0..30 {
+            world.tick();
+        }
+        for (scale, body) in bodies {
+            let vel = world.body(body).velocity.linear;
+            assert!((vel.y - -9.81 * 0.5 * scale).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn speed_limits_clamp_velocity() {
+        let mut world = World::new();
+        world.gravity = m::Vec2::zero();
+        let b = spawn_moving_box(&mut world, 0.0, |b| {
+            b.with_velocity(Velocity {
+                linear: m::Vec2::new(30.0, 40.0),
+                angular: -50.0,
+            })
+            .with_max_speed(5.0, 2.0)
+        });
+        world.tick();
+        let vel = world.body(b).velocity;
+        assert!((vel.linear - m::Vec2::new(3.0, 4.0)).mag() < 1e-9);
+        assert!((vel.angular - -2.0).abs() < 1e-9);
+        // falling bodies reach a terminal velocity
+        world.gravity = m::Vec2::new(0.0, -9.81);
+        world.body_mut(b).velocity = Velocity::default();
+        for _ in 0..60 {
+            world.tick();
+        }
+        let vel = world.body(b).velocity;
+        assert!((vel.linear - m::Vec2::new(0.0, -5.0)).mag() < 1e-9);
+    }
+
+    //
+    // forces and impulses
+    //
+
+    #[test]
+    fn impulse_at_point_spins_body() {
+        let mut world = World::new();
+        world.gravity = m::Vec2::zero();
+        let b = world.spawn_box(m::Vec2::new(1.0, 0.0), 0.0);
+        let pose = world.pose(b);
+        // a half-metre box with a density of 1 weighs 0.25 kg
+        let inertia = 0.25 * (0.5f64.powi(2) * 2.0) / 12.0;
+        world.body_mut(b).apply_impulse_at_point(
+            &pose,
+            m::Vec2::new(0.0, 1.0),
+            m::Vec2::new(1.25, 0.0),
+        );
+        let vel = world.body(b).velocity;
+        assert!((vel.linear - m::Vec2::new(0.0, 4.0)).mag() < 1e-9);
+        assert!((vel.angular - 0.25 / inertia).abs() < 1e-9);
+        world.tick();
+        let vel_after = world.body(b).velocity;
+        assert!((vel_after.linear - vel.linear).mag() < 1e-9);
+        assert!((angle(world.pose(b)) - vel.angular / 60.0).abs() < 1e-9);
+
+        // the same impulse as a force over a frame
+        let other = world.spawn_box(m::Vec2::new(-1.0, 0.0), 0.0);
+        let pose = world.pose(other);
+        world.body_mut(other).apply_force_at_point(
+            &pose,
+            m::Vec2::new(0.0, 60.0),
+            m::Vec2::new(-0.75, 0.0),
+        );
+        world.tick();
+        let other_vel = world.body(other).velocity;
+        assert!((other_vel.linear - vel.linear).mag() < 1e-9);
+        assert!((other_vel.angular - vel.angular).abs() < 1e-9);
+    }
+
+    #[test]
+    fn impulse_at_point_respects_center_of_mass() {
+        let mut world = World::new();
+        world.gravity = m::Vec2::zero();
+        let com = m::Vec2::new(0.2, 0.0);
+        let coll = Collider::new_square(0.5);
+        let b = world.spawn_body(
+            Body::new_dynamic(&coll, 1.0).with_center_of_mass(com),
+            coll,
+            m::Pose::new(m::Vec2::zero(), m::Rotor2::from_angle(0.5)),
+        );
+        let pose = world.pose(b);
+        world
+            .body_mut(b)
+            .apply_impulse_at_point(&pose, m::Vec2::new(1.0, 0.0), pose * com);
+        let vel = world.body(b).velocity;
+        assert!((vel.linear - m::Vec2::new(4.0, 0.0)).mag() < 1e-9);
+        assert!(vel.angular.abs() < 1e-9);
+
+        // off the center of mass the pose origin moves differently from the center
+        world.body_mut(b).velocity = Velocity::default();
+        world
+            .body_mut(b)
+            .apply_impulse_at_point(&pose, m::Vec2::new(0.0, 1.0), pose.translation);
+        world.tick();
+        let new_pose = world.pose(b);
+        let com_moved = new_pose * com - pose * com;
+        assert!((com_moved - m::Vec2::new(0.0, 4.0) / 60.0).mag() < 1e-9);
+        let vel = world.body(b).velocity;
+        assert!(vel.angular < 0.0);
+        let com_vel = vel.point_velocity(new_pose.rotation * com);
+        assert!((com_vel - m::Vec2::new(0.0, 4.0)).mag() < 1e-9);
+    }
+}
@@ -0,0 +1,797 @@
+//! Kinematic character movement.
+
+use super::{
+    body_colliders, collider_pose,
+    collision::{self, query::shape_cast_collider, shape_shape::intersection_check},
+    Body, Collider, Physics,
+};
+use crate::{
+    graph::{self, UnsafeNode},
+    math as m,
+};
+
+/// Moves a kinematic body like a platformer character,
+/// sliding along whatever it bumps into instead of going through it.
+///
+/// The controller doesn't own any nodes, it's given a body that should be
+/// [kinematic][Body::new_kinematic] and connected to a pose and a collider,
+/// typically a capsule stood upright with its [`offset`][Collider::offset].
+/// Bodies with a compound shape made with [`attach_compound`][super::attach_compound]
+/// move with all of their colliders.
+/// Call [`move_and_slide`][Self::move_and_slide] once per frame before
+/// [`Physics::tick`] to move it.
+///
+/// Configuration fields are public and can be changed at any time.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CharacterController {
+    /// The body being controlled.
+    pub body: graph::Node<Body>,
+    /// The direction considered up, used to tell ground, walls and ceilings apart.
+    /// Defaults to positive y.
+    pub up: m::Unit<m::Vec2>,
+    /// The steepest slope in radians that counts as ground. Defaults to 45 degrees.
+    pub max_slope: f64,
+    /// The tallest ledge the character walks onto without jumping. Defaults to 0.1.
+    pub step_height: f64,
+    /// How far down the character is pulled to stay on the ground
+    /// when walking down slopes and off small ledges. Defaults to 0.1.
+    pub snap_distance: f64,
+    /// Gap kept between the character and surfaces it touches,
+    /// needed because shape casts can't detect things they start out touching.
+    /// Defaults to 0.01.
+    pub skin_width: f64,
+    /// How long in seconds the character can still jump after walking off a ledge.
+    /// Defaults to 0.1.
+    pub coyote_time: f64,
+    /// The largest force in newtons the character pushes dynamic bodies with. Defaults to 200.
+    pub push_force: f64,
+    state: CharacterState,
+    time_since_grounded: f64,
+}
+
+/// What a [`CharacterController`][self::CharacterController] touched during its last move.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CharacterState {
+    /// Whether the character is standing on ground.
+    pub grounded: bool,
+    /// Whether the character bumped into a wall too steep to walk on.
+    pub on_wall: bool,
+    /// Whether the character bumped its head on a ceiling.
+    pub on_ceiling: bool,
+    /// The collider the character is standing on, if it's grounded.
+    pub ground: Option<graph::Node<Collider>>,
+    /// Surface normal of the ground, or `up` if the character isn't grounded.
+    pub ground_normal: m::Unit<m::Vec2>,
+    /// The velocity given to [`move_and_slide`][CharacterController::move_and_slide]
+    /// with the parts going into surfaces removed.
+    /// Give this back to the next move to keep gravity from building up while grounded.
+    pub velocity: m::Vec2,
+}
+
+impl CharacterController {
+    /// Create a controller for a kinematic body with default settings.
+    pub fn new(body: graph::Node<Body>) -> Self {
+        Self {
+            body,
+            up: m::Unit::unit_y(),
+            max_slope: std::f64::consts::FRAC_PI_4,
+            step_height: 0.1,
+            snap_distance: 0.1,
+            skin_width: 0.01,
+            coyote_time: 0.1,
+            push_force: 200.0,
+            state: CharacterState {
+                grounded: false,
+                on_wall: false,
+                on_ceiling: false,
+                ground: None,
+                ground_normal: m::Unit::unit_y(),
+                velocity: m::Vec2::zero(),
+            },
+            time_since_grounded: f64::INFINITY,
+        }
+    }
+
+    /// Get the state of the character as of the last move.
+    pub fn state(&self) -> &CharacterState {
+        &self.state
+    }
+
+    /// Check whether the character is allowed to jump, i.e. it's grounded
+    /// or left the ground less than [`coyote_time`][Self::coyote_time] ago.
+    pub fn can_jump(&self) -> bool {
+        self.time_since_grounded <= self.coyote_time
+    }
+
+    /// Use up the ability to jump if the character has it,
+    /// returning whether it did. Jumping again requires landing first.
+    ///
+    /// This only keeps track of the state. To actually jump,
+    /// give the next move some upwards velocity.
+    pub fn try_jump(&mut self) -> bool {
+        if self.can_jump() {
+            self.time_since_grounded = f64::INFINITY;
+            self.state.grounded = false;
+            self.state.ground = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move the character with the given velocity over the time `dt`,
+    /// sliding along surfaces, climbing steps and snapping to the ground.
+    ///
+    /// Standing on a collider attached to a body carries the character along with it,
+    /// and dynamic bodies in the way are pushed with at most [`push_force`][Self::push_force].
+    /// The pose is moved immediately, but other bodies react during the next tick.
+    ///
+    /// Like [`Physics::shapecast`], this uses the spatial index from the last tick.
+    /// Does nothing and returns the previous state if the body has been deleted.
+    #[allow(clippy::too_many_arguments)]
+    pub fn move_and_slide(
+        &mut self,
+        physics: &mut Physics,
+        graph: &graph::Graph,
+        l_pose: &mut graph::Layer<m::Pose>,
+        l_body: &mut graph::Layer<Body>,
+        l_collider: &graph::Layer<Collider>,
+        velocity: m::Vec2,
+        dt: f64,
+    ) -> CharacterState {
+        let body_node = self.body;
+        let (body, start_pose) = match body_node.check(graph) {
+            Some(body) => match graph.get_neighbor(&body, l_pose) {
+                Some(pose) if graph.get_neighbor(&body, l_collider).is_some() => (body, *pose),
+                _ => return self.state,
+            },
+            None => return self.state,
+        };
+        let body_pos = body.pos();
+
+        let platform_velocity = self.platform_velocity(graph, &*l_pose, &*l_body, &start_pose);
+
+        let mut pushes: Vec<(graph::NodePosition, m::Vec2, m::Unit<m::Vec2>)> = Vec::new();
+        let was_grounded = self.state.grounded;
+        let mut state = CharacterState {
+            grounded: false,
+            on_wall: false,
+            on_ceiling: false,
+            ground: None,
+            ground_normal: self.up,
+            velocity,
+        };
+        let mut char_pose = start_pose;
+        {
+            let mut caster = Caster {
+                physics,
+                graph,
+                l_pose: &*l_pose,
+                l_collider,
+                body: &body,
+                skin_width: self.skin_width,
+            };
+            caster.depenetrate(&mut char_pose);
+
+            let up = *self.up;
+            let min_ground_dot = self.max_slope.cos();
+            let mut motion = (velocity + platform_velocity) * dt;
+            for _ in 0..MAX_SLIDES {
+                if motion.mag_sq() < f64::EPSILON {
+                    break;
+                }
+                let hit = match caster.cast(&char_pose, motion) {
+                    Some(hit) => hit,
+                    None => {
+                        char_pose.append_translation(motion);
+                        break;
+                    }
+                };
+                let motion_len = motion.mag();
+                let dir = motion / motion_len;
+                let travel = (hit.distance - self.skin_width).max(0.0);
+                char_pose.append_translation(dir * travel);
+                motion = dir * (motion_len - travel);
+
+                let normal = *hit.normal;
+                let normal_up = normal.dot(up);
+                if normal_up >= min_ground_dot {
+                    state.grounded = true;
+                    state.ground = Some(hit.collider);
+                    state.ground_normal = hit.normal;
+                    // follow the slope without losing horizontal speed or sliding down it
+                    let horizontal = motion - up * motion.dot(up);
+                    motion = horizontal - up * (horizontal.dot(normal) / normal_up);
+                    let vel_up = state.velocity.dot(up);
+                    if vel_up < 0.0 {
+                        state.velocity -= up * vel_up;
+                    }
+                    continue;
+                }
+
+                if normal_up <= -min_ground_dot {
+                    state.on_ceiling = true;
+                } else if was_grounded || state.grounded {
+                    if let Some((stepped_pose, rest)) =
+                        self.try_step(&mut caster, &char_pose, motion)
+                    {
+                        char_pose = stepped_pose;
+                        motion = rest;
+                        continue;
+                    }
+                    state.on_wall = true;
+                } else {
+                    state.on_wall = true;
+                }
+                pushes.push((hit.collider.pos(), hit.point, hit.normal));
+                motion = slide(motion, normal, up);
+                state.velocity = slide(state.velocity, normal, up);
+            }
+
+            // stick to the ground or check if we landed on it
+            if !state.grounded && state.velocity.dot(up) <= 0.0 {
+                let snap = if was_grounded {
+                    self.snap_distance
+                } else {
+                    0.0
+                };
+                let probe = -up * (snap + 2.0 * self.skin_width);
+                if let Some(hit) = caster
+                    .cast(&char_pose, probe)
+                    .filter(|hit| hit.normal.dot(up) >= min_ground_dot)
+                {
+                    char_pose.append_translation(-up * (hit.distance - self.skin_width).max(0.0));
+                    state.grounded = true;
+                    state.ground = Some(hit.collider);
+                    state.ground_normal = hit.normal;
+                    let vel_up = state.velocity.dot(up);
+                    if vel_up < 0.0 {
+                        state.velocity -= up * vel_up;
+                    }
+                }
+            }
+        }
+
+        for (coll_pos, point, normal) in pushes {
+            self.push(
+                graph, &*l_pose, l_body, coll_pos, point, normal, velocity, dt,
+            );
+        }
+
+        let mut pose = l_pose.get_mut_unchecked(
+            graph
+                .get_neighbor_unchecked(&body_pos, l_pose)
+                .expect("Character lost its pose")
+                .pos(),
+        );
+        pose.translation += char_pose.translation - start_pose.translation;
+
+        if state.grounded {
+            self.time_since_grounded = 0.0;
+        } else {
+            self.time_since_grounded += dt;
+        }
+        self.state = state;
+        state
+    }
+
+    /// Velocity of the body the character was standing on at the character's position.
+    fn platform_velocity(
+        &self,
+        graph: &graph::Graph,
+        l_pose: &graph::Layer<m::Pose>,
+        l_body: &graph::Layer<Body>,
+        char_pose: &m::Pose,
+    ) -> m::Vec2 {
+        let ground = match self.state.ground.as_ref().and_then(|g| g.check(graph)) {
+            Some(ground) => ground,
+            None => return m::Vec2::zero(),
+        };
+        let body = match graph.get_neighbor(&ground, l_body) {
+            Some(body) => body,
+            None => return m::Vec2::zero(),
+        };
+        match graph.get_neighbor(&body, l_pose) {
+            Some(pose) => body
+                .velocity
+                .point_velocity(char_pose.translation - pose.translation),
+            None => m::Vec2::zero(),
+        }
+    }
+
+    /// Try to climb over an obstacle by moving up, forward and back down.
+    /// Returns the new pose and the motion left over if the character landed on ground.
+    fn try_step(
+        &self,
+        caster: &mut Caster,
+        char_pose: &m::Pose,
+        motion: m::Vec2,
+    ) -> Option<(m::Pose, m::Vec2)> {
+        let up = *self.up;
+        let horizontal = motion - up * motion.dot(up);
+        let horizontal_len = horizontal.mag();
+        if self.step_height <= 0.0 || horizontal_len < f64::EPSILON {
+            return None;
+        }
+        let dir = horizontal / horizontal_len;
+
+        let mut pose = *char_pose;
+        let rise = caster
+            .cast(&pose, up * self.step_height)
+            .map(|hit| (hit.distance - self.skin_width).max(0.0))
+            .unwrap_or(self.step_height);
+        pose.append_translation(up * rise);
+
+        let forward = caster
+            .cast(&pose, horizontal)
+            .map(|hit| (hit.distance - self.skin_width).max(0.0))
+            .unwrap_or(horizontal_len);
+        if forward < self.skin_width {
+            return None;
+        }
+        pose.append_translation(dir * forward);
+
+        let landing = caster
+            .cast(&pose, -up * (rise + self.skin_width))
+            .filter(|hit| hit.normal.dot(up) >= self.max_slope.cos())?;
+        pose.append_translation(-up * (landing.distance - self.skin_width).max(0.0));
+        Some((pose, dir * (horizontal_len - forward)))
+    }
+
+    /// Push a dynamic body the character ran into,
+    /// trying to get the touched point moving as fast as the character.
+    #[allow(clippy::too_many_arguments)]
+    fn push(
+        &self,
+        graph: &graph::Graph,
+        l_pose: &graph::Layer<m::Pose>,
+        l_body: &mut graph::Layer<Body>,
+        coll_pos: graph::NodePosition,
+        point: m::Vec2,
+        normal: m::Unit<m::Vec2>,
+        char_velocity: m::Vec2,
+        dt: f64,
+    ) {
+        let body_pos = match graph.get_neighbor_unchecked(&coll_pos, l_body) {
+            Some(body) if body.sees_forces() => body.pos(),
+            _ => return,
+        };
+        let pose = match graph.get_neighbor_unchecked(&body_pos, l_pose) {
+            Some(pose) => *pose,
+            None => return,
+        };
+        let mut body = l_body.get_mut_unchecked(body_pos);
+        let push_dir = -*normal;
+        let offset = point - pose * body.center_of_mass;
+        let speed_diff = char_velocity.dot(push_dir)
+            - body
+                .velocity
+                .point_velocity(point - pose.translation)
+                .dot(push_dir);
+        if speed_diff <= 0.0 {
+            return;
+        }
+        let arm = offset.wedge(push_dir).xy;
+        let eff_inv_mass = body.mass.inv() + body.moment_of_inertia.inv() * arm * arm;
+        let impulse = (speed_diff / eff_inv_mass).min(self.push_force * dt);
+        body.apply_impulse_at_point(&pose, push_dir * impulse, point);
+        body.wake_up();
+    }
+}
+
+/// How many times a move can be redirected by surfaces in one frame.
+const MAX_SLIDES: usize = 4;
+
+/// Remove the part of `v` going into a surface that isn't ground.
+///
+/// Sliding along a slope too steep to stand on would carry the character up it,
+/// so in that case only the horizontal part going into the slope is removed.
+fn slide(v: m::Vec2, normal: m::Vec2, up: m::Vec2) -> m::Vec2 {
+    let slid = v - normal * v.dot(normal).min(0.0);
+    if slid.dot(up) <= v.dot(up).max(0.0) {
+        return slid;
+    }
+    let wall_normal = (normal - up * normal.dot(up)).normalized();
+    v - wall_normal * v.dot(wall_normal).min(0.0)
+}
+
+/// A surface hit by a character's shape cast.
+#[derive(Clone, Copy, Debug)]
+struct CharacterHit {
+    collider: graph::Node<Collider>,
+    point: m::Vec2,
+    normal: m::Unit<m::Vec2>,
+    distance: f64,
+}
+
+/// Shape casts and overlap checks for the character's colliders
+/// that ignore the character itself, triggers and one-way colliders it's passing through.
+///
+/// Poses given to these are the pose of the character's body,
+/// each collider is placed relative to it by its offset.
+struct Caster<'a> {
+    physics: &'a mut Physics,
+    graph: &'a graph::Graph,
+    l_pose: &'a graph::Layer<m::Pose>,
+    l_collider: &'a graph::Layer<Collider>,
+    body: &'a graph::CheckedNode<'a, Body>,
+    skin_width: f64,
+}
+
+impl<'a> Caster<'a> {
+    /// Solid colliders that aren't part of the character in the given area,
+    /// which are allowed to collide with the given layer.
+    fn candidates<'s>(
+        &'s mut self,
+        aabb: collision::AABB,
+        layer: usize,
+    ) -> impl 's + Iterator<Item = graph::NodeRef<'a, Collider>> {
+        let (graph, l_collider, body) = (self.graph, self.l_collider, self.body);
+        self.physics
+            .spatial_candidates(graph, l_collider, aabb, layer)
+            .filter(move |other| {
+                other.is_solid()
+                    && body_colliders(body, graph, l_collider).all(|own| own.pos() != other.pos())
+            })
+    }
+
+    fn cast(&mut self, pose: &m::Pose, motion: m::Vec2) -> Option<CharacterHit> {
+        body_colliders(self.body, self.graph, self.l_collider)
+            .filter_map(|own| self.cast_collider(&own, &(*pose * own.offset), motion))
+            .min_by(|h1, h2| h1.distance.partial_cmp(&h2.distance).unwrap())
+    }
+
+    /// Cast one of the character's colliders, placed at `own_pose`.
+    fn cast_collider(
+        &mut self,
+        own: &Collider,
+        own_pose: &m::Pose,
+        motion: m::Vec2,
+    ) -> Option<CharacterHit> {
+        let (graph, l_pose) = (self.graph, self.l_pose);
+        let mut end_pose = *own_pose;
+        end_pose.append_translation(motion);
+        let aabb = own.aabb(own_pose).union(&own.aabb(&end_pose));
+        self.candidates(aabb, own.layer)
+            .filter_map(|other| {
+                let other_pose = collider_pose(graph, l_pose, &other);
+                // one-way colliders only block things moving against their direction
+                if let Some(dir) = other.one_way {
+                    if (other_pose.rotation * dir).dot(motion) >= 0.0 {
+                        return None;
+                    }
+                }
+                // shape casts ignore colliders they start out overlapping, so if depenetration
+                // didn't get us out of one, block moving deeper into it to avoid going through
+                if other.one_way.is_none() {
+                    if let Some((point, normal)) =
+                        deepest_overlap(own_pose, own, &other_pose, &other)
+                    {
+                        return if normal.dot(motion) < 0.0 {
+                            Some(CharacterHit {
+                                collider: graph::NodeRef::as_node(&other, graph),
+                                point,
+                                normal,
+                                distance: 0.0,
+                            })
+                        } else {
+                            None
+                        };
+                    }
+                }
+                let isect = shape_cast_collider(own, own_pose, motion, &other_pose, &other)?;
+                Some(CharacterHit {
+                    collider: graph::NodeRef::as_node(&other, graph),
+                    point: isect.point,
+                    normal: isect.normal,
+                    distance: isect.t,
+                })
+            })
+            .min_by(|h1, h2| h1.distance.partial_cmp(&h2.distance).unwrap())
+    }
+
+    /// Move the character out of anything it's overlapping with.
+    fn depenetrate(&mut self, pose: &mut m::Pose) {
+        const MAX_ITERATIONS: usize = 4;
+        let (graph, l_pose, l_collider, body) =
+            (self.graph, self.l_pose, self.l_collider, self.body);
+        let skin_width = self.skin_width;
+        for _ in 0..MAX_ITERATIONS {
+            let mut correction = m::Vec2::zero();
+            for own in body_colliders(body, graph, l_collider) {
+                let own_pose = *pose * own.offset;
+                for other in self
+                    .candidates(own.aabb(&own_pose), own.layer)
+                    .filter(|other| other.one_way.is_none())
+                {
+                    let other_pose = collider_pose(graph, l_pose, &other);
+                    for contact in intersection_check(&own_pose, &own, &other_pose, &other).iter() {
+                        let depth = (own_pose * contact.offsets[0]
+                            - other_pose * contact.offsets[1])
+                            .dot(*contact.normal);
+                        let push = -*contact.normal * (depth + skin_width);
+                        if push.mag_sq() > correction.mag_sq() {
+                            correction = push;
+                        }
+                    }
+                }
+            }
+            if correction.mag_sq() == 0.0 {
+                return;
+            }
+            pose.append_translation(correction);
+        }
+    }
+}
+
+/// Find the deepest point where a collider overlaps another one
+/// and the direction pointing out of the other collider there.
+fn deepest_overlap(
+    pose: &m::Pose,
+    coll: &Collider,
+    other_pose: &m::Pose,
+    other: &Collider,
+) -> Option<(m::Vec2, m::Unit<m::Vec2>)> {
+    intersection_check(pose, coll, other_pose, other)
+        .iter()
+        .map(|contact| {
+            let depth = (*pose * contact.offsets[0] - *other_pose * contact.offsets[1])
+                .dot(*contact.normal);
+            (depth, *other_pose * contact.offsets[1], -contact.normal)
+        })
+        .max_by(|c1, c2| c1.0.partial_cmp(&c2.0).unwrap())
+        .map(|(_, point, normal)| (point, normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{attach_compound, tests::World, Velocity};
+
+    const DT: f64 = 1.0 / 60.0;
+
+    impl World {
+        /// A character with a one metre tall upright capsule standing on the ground.
+        fn spawn_character(&mut self, x: f64) -> CharacterController {
+            self.spawn_compound_character(x, &[])
+        }
+
+        /// A character with the usual capsule and some extra colliders attached to it.
+        fn spawn_compound_character(&mut self, x: f64, extra: &[Collider]) -> CharacterController {
+            let mut colliders = vec![Collider::new_capsule(0.5, 0.25).with_offset(m::Pose::new(
+                m::Vec2::zero(),
+                m::Rotor2::from_angle(std::f64::consts::FRAC_PI_2),
+            ))];
+            colliders.extend_from_slice(extra);
+            let body = self.l_body.insert(Body::new_kinematic(), &mut self.graph);
+            let body = graph::NodeRef::as_node(&body, &self.graph);
+            let pose = self.l_pose.insert(
+                m::Pose::new(m::Vec2::new(x, 0.01), Default::default()),
+                &mut self.graph,
+            );
+            let pose = graph::NodeRef::as_node(&pose, &self.graph);
+            self.graph.connect(
+                &body.check(&self.graph).unwrap(),
+                &pose.check(&self.graph).unwrap(),
+            );
+            attach_compound(
+                body,
+                pose,
+                &colliders,
+                &mut self.l_collider,
+                &mut self.graph,
+            );
+            // build the spatial index shape casts use
+            self.tick();
+            CharacterController::new(body)
+        }
+
+        /// Walk at a constant horizontal speed while falling under gravity.
+        fn walk(
+            &mut self,
+            controller: &mut CharacterController,
+            speed: f64,
+            frames: usize,
+        ) -> CharacterState {
+            let mut state = *controller.state();
+            for _ in 0..frames {
+                let velocity = m::Vec2::new(speed, state.velocity.y + self.gravity.y * DT);
+                state = controller.move_and_slide(
+                    &mut self.physics,
+                    &self.graph,
+                    &mut self.l_pose,
+                    &mut self.l_body,
+                    &self.l_collider,
+                    velocity,
+                    DT,
+                );
+                self.tick();
+            }
+            state
+        }
+
+        /// A slope rising to the right from x = 1 at the given angle.
+        fn spawn_slope(&mut self, angle: f64) {
+            self.spawn_static(
+                Collider::new_polygon(&[
+                    m::Vec2::new(1.0, -0.5),
+                    m::Vec2::new(7.0, -0.5),
+                    m::Vec2::new(7.0, -0.5 + 6.0 * angle.tan()),
+                ]),
+                m::Pose::default(),
+            );
+        }
+    }
+
+    #[test]
+    fn walks_up_gentle_slopes() {
+        let mut world = World::new();
+        world.spawn_ground();
+        world.spawn_slope(0.5);
+        let mut controller = world.spawn_character(0.0);
+        let state = world.walk(&mut controller, 3.0, 60);
+        let pose = world.pose(controller.body);
+        assert!(state.grounded && !state.on_wall);
+        assert!(pose.translation.y > 1.0);
+    }
+
+    #[test]
+    fn steep_slopes_are_walls() {
+        let mut world = World::new();
+        world.spawn_ground();
+        world.spawn_slope(1.2);
+        let mut controller = world.spawn_character(0.0);
+        let state = world.walk(&mut controller, 3.0, 60);
+        let pose = world.pose(controller.body);
+        assert!(state.on_wall);
+        assert!(pose.translation.x < 1.0);
+        assert!(pose.translation.y < 0.1);
+    }
+
+    #[test]
+    fn climbs_steps_up_to_step_height() {
+        for &(height, climbs) in &[(0.08, true), (0.3, false)] {
+            let mut world = World::new();
+            world.spawn_ground();
+            world.spawn_static(
+                Collider::new_rect(2.0, height),
+                m::Pose::new(m::Vec2::new(2.0, -0.5 + height / 2.0), Default::default()),
+            );
+            let mut controller = world.spawn_character(0.0);
+            let state = world.walk(&mut controller, 3.0, 30);
+            let pose = world.pose(controller.body);
+            assert_eq!(pose.translation.x > 1.0, climbs);
+            assert_eq!(state.on_wall, !climbs);
+            assert!(state.grounded);
+        }
+    }
+
+    #[test]
+    fn fast_fall_lands_on_thin_ground() {
+        let mut world = World::new();
+        world.spawn_static(
+            Collider::new_rect(4.0, 0.02),
+            m::Pose::new(m::Vec2::new(0.0, -3.0), Default::default()),
+        );
+        let mut controller = world.spawn_character(0.0);
+        let state = controller.move_and_slide(
+            &mut world.physics,
+            &world.graph,
+            &mut world.l_pose,
+            &mut world.l_body,
+            &world.l_collider,
+            m::Vec2::new(0.0, -500.0),
+            DT,
+        );
+        let pose = world.pose(controller.body);
+        assert!(state.grounded);
+        assert!((pose.translation.y - (-3.0 + 0.01 + 0.5)).abs() < 0.02);
+    }
+
+    #[test]
+    fn cant_walk_into_walls_it_couldnt_get_out_of() {
+        let mut world = World::new();
+        world.spawn_ground();
+        // a gap too narrow for the character, which overlaps both walls
+        for &x in &[-0.45, 0.05] {
+            world.spawn_static(
+                Collider::new_rect(0.1, 3.0),
+                m::Pose::new(m::Vec2::new(x, 1.0), Default::default()),
+            );
+        }
+        let mut controller = world.spawn_character(-0.2);
+        world.walk(&mut controller, 10.0, 30);
+        assert!(world.pose(controller.body).translation.x < 0.0);
+        world.walk(&mut controller, -10.0, 30);
+        assert!(world.pose(controller.body).translation.x > -0.4);
+    }
+
+    #[test]
+    fn rides_moving_platforms() {
+        let mut world = World::new();
+        let platform = Collider::new_rect(4.0, 0.2);
+        world.spawn_body(
+            Body::new_kinematic().with_velocity(Velocity {
+                linear: m::Vec2::new(2.0, 0.0),
+                angular: 0.0,
+            }),
+            platform,
+            m::Pose::new(m::Vec2::new(0.0, -0.6), Default::default()),
+        );
+        let mut controller = world.spawn_character(0.0);
+        let start = world.pose(controller.body).translation;
+        let state = world.walk(&mut controller, 0.0, 30);
+        let moved = world.pose(controller.body).translation - start;
+        assert!(state.grounded);
+        assert!((moved.x - 1.0).abs() < 0.05);
+        assert!(moved.y.abs() < 0.02);
+    }
+
+    #[test]
+    fn pushes_dynamic_bodies() {
+        let mut world = World::new();
+        world.spawn_ground();
+        let crate_body = world.spawn_box(m::Vec2::new(1.0, -0.25), 0.0);
+        let mut controller = world.spawn_character(0.0);
+        world.walk(&mut controller, 2.0, 60);
+        let crate_x = world.pose(crate_body).translation.x;
+        let char_x = world.pose(controller.body).translation.x;
+        assert!(crate_x > 2.0);
+        // the character stays behind the crate instead of passing through it
+        assert!(char_x < crate_x - 0.45);
+    }
+
+    #[test]
+    fn can_jump_for_coyote_time_after_leaving_ground() {
+        let mut world = World::new();
+        // ground ending at x = 0.5
+        world.spawn_static(
+            Collider::new_rect(4.0, 1.0),
+            m::Pose::new(m::Vec2::new(-1.5, -1.0), Default::default()),
+        );
+        let mut controller = world.spawn_character(0.0);
+        assert!(world.walk(&mut controller, 0.0, 1).grounded);
+        assert!(controller.can_jump());
+
+        let mut frames = 0;
+        while world.walk(&mut controller, 3.0, 1).grounded {
+            frames += 1;
+            assert!(frames < 60, "never left the ground");
+        }
+        assert!(controller.can_jump());
+        // coyote time is 0.1 seconds, which has passed after 7 more frames
+        world.walk(&mut controller, 3.0, 7);
+        assert!(!controller.can_jump());
+        assert!(!controller.try_jump());
+    }
+
+    #[test]
+    fn jumping_uses_up_the_jump() {
+        let mut world = World::new();
+        world.spawn_ground();
+        let mut controller = world.spawn_character(0.0);
+        world.walk(&mut controller, 0.0, 1);
+        assert!(controller.try_jump());
+        assert!(!controller.can_jump());
+        assert!(!controller.try_jump());
+    }
+
+    #[test]
+    fn compound_characters_collide_with_every_collider() {
+        let mut world = World::new();
+        world.spawn_ground();
+        world.spawn_static(
+            Collider::new_rect(0.1, 3.0),
+            m::Pose::new(m::Vec2::new(1.05, 1.0), Default::default()),
+        );
+        // something held out in front of the character
+        let held = Collider::new_square(0.2)
+            .with_offset(m::Pose::new(m::Vec2::new(0.5, 0.0), Default::default()));
+        let mut controller = world.spawn_compound_character(0.0, &[held]);
+        let state = world.walk(&mut controller, 3.0, 30);
+        let x = world.pose(controller.body).translation.x;
+        assert!(state.on_wall && state.grounded);
+        // the held box touches the wall long before the capsule would
+        assert!((x - 0.4).abs() < 0.03);
+    }
+}
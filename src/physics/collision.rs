@@ -4,6 +4,8 @@ pub use hgrid::{HGrid, HGridParams};
 mod collider;
 pub use collider::*;
 
+use std::collections::HashMap;
+
 pub mod shape_shape;
 pub use shape_shape::{Contact, ContactIterator, ContactResult};
 
@@ -14,7 +16,7 @@ pub mod query;
 use crate::math as m;
 
 /// Axis-aligned bounding box.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct AABB {
     pub min: m::Vec2,
     pub max: m::Vec2,
@@ -123,3 +125,242 @@ impl MaskMatrix {
         self.0[layer1] & (1 << layer2) != 0
     }
 }
+
+/// Friction and restitution coefficients for a specific pair of materials.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaterialPair {
+    /// Coefficient of static friction. None disables static friction.
+    pub static_friction_coef: Option<f64>,
+    /// Coefficient of dynamic friction. None disables dynamic friction.
+    pub dynamic_friction_coef: Option<f64>,
+    pub restitution_coef: f64,
+}
+
+/// A table of coefficients for pairs of [`Material`][self::Material] IDs
+/// that override the coefficients combined from the individual materials.
+#[derive(Clone, Debug, Default)]
+pub struct MaterialPairTable(HashMap<(usize, usize), MaterialPair>);
+
+impl MaterialPairTable {
+    /// Set the coefficients used between two material IDs.
+    pub fn set(&mut self, id1: usize, id2: usize, pair: MaterialPair) {
+        self.0.insert(Self::key(id1, id2), pair);
+    }
+
+    /// Remove the override between two material IDs,
+    /// going back to combining the materials' own coefficients.
+    pub fn remove(&mut self, id1: usize, id2: usize) -> Option<MaterialPair> {
+        self.0.remove(&Self::key(id1, id2))
+    }
+
+    /// Get the override between two material IDs if there is one.
+    pub fn get(&self, id1: usize, id2: usize) -> Option<&MaterialPair> {
+        self.0.get(&Self::key(id1, id2))
+    }
+
+    /// Get the coefficients to use in a contact between two materials.
+    pub fn coefficients(&self, mat1: &Material, mat2: &Material) -> MaterialPair {
+        match self.get(mat1.id, mat2.id) {
+            Some(pair) => *pair,
+            None => MaterialPair {
+                static_friction_coef: mat1.static_friction_with(mat2),
+                dynamic_friction_coef: mat1.dynamic_friction_with(mat2),
+                restitution_coef: mat1.restitution_with(mat2),
+            },
+        }
+    }
+
+    fn key(id1: usize, id2: usize) -> (usize, usize) {
+        (id1.min(id2), id1.max(id2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{tests::World, Body};
+
+    fn material(id: usize, friction: f64, restitution: f64, combine: CombineMode) -> Material {
+        Material {
+            id,
+            static_friction_coef: Some(friction),
+            dynamic_friction_coef: Some(friction),
+            restitution_coef: restitution,
+            friction_combine: combine,
+            restitution_combine: combine,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn combine_modes() {
+        let cases = [
+            (CombineMode::Average, 0.5),
+            (CombineMode::GeometricMean, 0.4),
+            (CombineMode::Min, 0.2),
+            (CombineMode::Multiply, 0.16),
+            (CombineMode::Max, 0.8),
+        ];
+        for (mode, expected) in cases {
+            assert!(
+                (mode.combine(0.2, 0.8) - expected).abs() < 1e-9,
+                "{:?}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn later_combine_mode_wins() {
+        let avg = material(0, 0.2, 0.2, CombineMode::Average);
+        let max = material(1, 0.8, 0.8, CombineMode::Max);
+        let min = material(2, 0.5, 0.5, CombineMode::Min);
+        for (a, b, expected) in [(&avg, &max, 0.8), (&avg, &min, 0.2), (&min, &max, 0.8)] {
+            assert_eq!(a.static_friction_with(b), Some(expected));
+            assert_eq!(b.dynamic_friction_with(a), Some(expected));
+            assert_eq!(a.restitution_with(b), expected);
+        }
+        let frictionless = Material {
+            static_friction_coef: None,
+            ..avg
+        };
+        assert_eq!(frictionless.static_friction_with(&max), None);
+        assert_eq!(frictionless.dynamic_friction_with(&max), Some(0.8));
+    }
+
+    #[test]
+    fn pair_table_overrides_in_either_order() {
+        let ice = material(1, 0.1, 0.0, CombineMode::Average);
+        let rubber = material(2, 1.0, 0.8, CombineMode::Average);
+        let mut table = MaterialPairTable::default();
+        assert_eq!(
+            table.coefficients(&ice, &rubber).static_friction_coef,
+            Some(0.55)
+        );
+
+        table.set(
+            2,
+            1,
+            MaterialPair {
+                static_friction_coef: None,
+                dynamic_friction_coef: Some(0.3),
+                restitution_coef: 0.5,
+            },
+        );
+        for (a, b) in [(&ice, &rubber), (&rubber, &ice)] {
+            let pair = table.coefficients(a, b);
+            assert_eq!(pair.static_friction_coef, None);
+            assert_eq!(pair.dynamic_friction_coef, Some(0.3));
+            assert_eq!(pair.restitution_coef, 0.5);
+        }
+        // other pairs aren't affected
+        assert_eq!(table.coefficients(&ice, &ice).restitution_coef, 0.0);
+
+        assert!(table.remove(1, 2).is_some());
+        assert!(table.get(2, 1).is_none());
+        assert_eq!(table.coefficients(&ice, &rubber).restitution_coef, 0.4);
+    }
+
+    #[test]
+    fn material_pair_table_is_used_in_contacts() {
+        let mut world = World::new();
+        world.spawn_ground();
+        world.physics.material_pairs.set(
+            0,
+            1,
+            MaterialPair {
+                restitution_coef: 0.9,
+                ..Default::default()
+            },
+        );
+        let ball = |id| {
+            Collider::new_circle(0.1).with_material(Material {
+                id,
+                ..Default::default()
+            })
+        };
+        let spawn = |world: &mut World, x, coll: Collider| {
+            world.spawn_body(
+                Body::new_dynamic(&coll, 1.0),
+                coll,
+                m::Pose::new(m::Vec2::new(x, 1.0), Default::default()),
+            )
+        };
+        let bouncy = spawn(&mut world, -1.0, ball(1));
+        let dull = spawn(&mut world, 1.0, ball(2));
+        // both hit the ground about 33 frames after being dropped
+        let mut max_rebound = [f64::MIN; 2];
+        for frame in 0..70 {
+            world.tick();
+            if frame >= 40 {
+                for (rebound, b) in max_rebound.iter_mut().zip([bouncy, dull]) {
+                    *rebound = rebound.max(world.pose(b).translation.y);
+                }
+            }
+        }
+        // dropped from 1.5 metres above the ground, the bouncy ball comes back most of the way
+        assert!(
+            max_rebound[0] > 0.5,
+            "bouncy ball reached {}",
+            max_rebound[0]
+        );
+        assert!(
+            max_rebound[1] < -0.35,
+            "dull ball reached {}",
+            max_rebound[1]
+        );
+    }
+
+    #[test]
+    fn conveyor_carries_bodies_clockwise() {
+        let mut world = World::new();
+        let conveyor = |surface_velocity| {
+            Collider::new_rect(6.0, 1.0).with_material(Material {
+                surface_velocity,
+                ..Default::default()
+            })
+        };
+        world.spawn_static(
+            conveyor(2.0),
+            m::Pose::new(m::Vec2::new(-4.0, -1.0), Default::default()),
+        );
+        world.spawn_static(
+            conveyor(-2.0),
+            m::Pose::new(m::Vec2::new(4.0, -1.0), Default::default()),
+        );
+        let on_forward = world.spawn_box(m::Vec2::new(-5.0, -0.25), 0.0);
+        let on_backward = world.spawn_box(m::Vec2::new(5.0, -0.25), 0.0);
+        for _ in 0..60 {
+            world.tick();
+        }
+        let vel = |b| world.body(b).velocity.linear.x;
+        assert!((vel(on_forward) - 2.0).abs() < 0.05, "{}", vel(on_forward));
+        assert!(
+            (vel(on_backward) + 2.0).abs() < 0.05,
+            "{}",
+            vel(on_backward)
+        );
+    }
+
+    /// A body with a moving surface drives itself along still ground like a tank tread.
+    #[test]
+    fn surface_velocity_on_body_drives_it() {
+        let mut world = World::new();
+        world.spawn_ground();
+        let coll = Collider::new_square(0.5).with_material(Material {
+            surface_velocity: 1.0,
+            ..Default::default()
+        });
+        let tread = world.spawn_body(
+            Body::new_dynamic(&coll, 1.0),
+            coll,
+            m::Pose::new(m::Vec2::new(0.0, -0.25), Default::default()),
+        );
+        for _ in 0..30 {
+            world.tick();
+        }
+        // the bottom surface moves clockwise, i.e. backwards, pushing the body forwards
+        let vel = world.body(tread).velocity.linear.x;
+        assert!((vel - 1.0).abs() < 0.05, "{}", vel);
+    }
+}
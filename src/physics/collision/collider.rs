@@ -3,13 +3,21 @@ use crate::math as m;
 
 /// A component that allows a game object to collide with others
 /// or act as a trigger.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Collider {
     pub shape: ColliderShape,
     pub ty: ColliderType,
     /// Collision layer, see [`MaskMatrix`][super::MaskMatrix] for info.
     /// Defaults to 0.
     pub layer: usize,
+    /// If set, makes the collider one-way, only blocking things that come at it
+    /// from the side this collider-local direction points to and letting anything else through.
+    pub one_way: Option<m::Unit<m::Vec2>>,
+    /// Pose of the collider relative to the body or pose it's attached to.
+    /// Defaults to identity. See [`attach_compound`][crate::physics::attach_compound]
+    /// for giving a body several colliders.
+    #[serde(with = "m::serde_pose")]
+    pub offset: m::Pose,
 }
 
 impl Collider {
@@ -19,6 +27,8 @@ impl Collider {
             shape: ColliderShape::Circle { r: radius },
             ty: ColliderType::default(),
             layer: 0,
+            one_way: None,
+            offset: m::Pose::identity(),
         }
     }
 
@@ -35,6 +45,8 @@ impl Collider {
             shape: ColliderShape::Rect { hw, hh },
             ty: ColliderType::default(),
             layer: 0,
+            one_way: None,
+            offset: m::Pose::identity(),
         }
     }
 
@@ -47,6 +59,50 @@ impl Collider {
             },
             ty: ColliderType::default(),
             layer: 0,
+            one_way: None,
+            offset: m::Pose::identity(),
+        }
+    }
+
+    /// Create a solid convex polygon collider from a set of points relative to the collider's origin.
+    ///
+    /// The collider is the convex hull of the given points, so they can be given in any order.
+    ///
+    /// # Panics
+    /// Panics if the hull has fewer than 3 or more than
+    /// [`MAX_POLYGON_VERTICES`][self::MAX_POLYGON_VERTICES] vertices.
+    pub fn new_polygon(points: &[m::Vec2]) -> Self {
+        Collider {
+            shape: ColliderShape::Polygon(Polygon::new(points)),
+            ty: ColliderType::default(),
+            layer: 0,
+            one_way: None,
+            offset: m::Pose::identity(),
+        }
+    }
+
+    /// Create a solid one-sided line segment collider.
+    ///
+    /// Only the left side of the segment (when looking from `start` towards `end`) collides,
+    /// so terrain drawn from left to right is solid from above.
+    /// Edges are meant for static terrain and have no area, so they can't be attached to bodies.
+    /// See [`spawn_edge_chain`][crate::physics::spawn_edge_chain] for building whole chains of these.
+    ///
+    /// # Panics
+    /// Panics if `start` and `end` are the same point.
+    pub fn new_edge(start: m::Vec2, end: m::Vec2) -> Self {
+        assert!(start != end, "An edge needs two distinct points");
+        Collider {
+            shape: ColliderShape::Edge(EdgeSegment {
+                start,
+                end,
+                ghost_prev: None,
+                ghost_next: None,
+            }),
+            ty: ColliderType::default(),
+            layer: 0,
+            one_way: None,
+            offset: m::Pose::identity(),
         }
     }
 
@@ -56,12 +112,33 @@ impl Collider {
         self
     }
 
+    /// Make the collider one-way, like a platform that can be jumped through from below.
+    ///
+    /// Things touching the collider are blocked only if they're on the side
+    /// the given collider-local direction points to and moving against it when they first touch.
+    pub fn with_one_way(mut self, direction: m::Vec2) -> Self {
+        self.one_way = Some(m::Unit::new_normalize(direction));
+        self
+    }
+
+    /// Set the pose of the collider relative to the body or pose it's attached to.
+    pub fn with_offset(mut self, offset: m::Pose) -> Self {
+        self.offset = offset;
+        self
+    }
+
     /// Turn the collider into a trigger.
     pub fn trigger(mut self) -> Self {
         self.ty = ColliderType::Trigger;
         self
     }
 
+    /// Turn the collider into a volume of the given fluid.
+    pub fn with_fluid(mut self, fluid: Fluid) -> Self {
+        self.ty = ColliderType::Fluid(fluid);
+        self
+    }
+
     pub fn with_layer(mut self, layer: usize) -> Self {
         self.layer = layer;
         self
@@ -72,17 +149,50 @@ impl Collider {
             ColliderShape::Circle { r } => std::f64::consts::PI * r * r,
             ColliderShape::Rect { hw, hh } => 4.0 * hw * hh,
             ColliderShape::Capsule { hl, r } => (std::f64::consts::PI * r * r) + (4.0 * hl * r),
+            ColliderShape::Polygon(poly) => poly.area(),
+            ColliderShape::Edge(_) => 0.0,
         }
     }
 
+    /// Moment of inertia per unit mass around the collider's origin, ignoring its offset.
     pub fn moment_of_inertia_coef(&self) -> f64 {
         // from https://en.wikipedia.org/wiki/List_of_moments_of_inertia
         match self.shape {
             ColliderShape::Circle { r } => r * r / 2.0,
             ColliderShape::Rect { hw, hh } => (hw * hw + hh * hh) / 3.0,
-            // rough estimation as a rectangle, since an accurate formula is not on wikipedia.
-            // TODO: calculate a formula by hand
-            ColliderShape::Capsule { hl, r } => (hl * hl + r * r) / 3.0,
+            ColliderShape::Capsule { hl, r } => {
+                // a rectangle plus two half-discs, each moved from its own centroid
+                // to the capsule's center with the parallel axis theorem
+                let rect_mass = 4.0 * hl * r;
+                let disc_mass = std::f64::consts::PI * r * r;
+                let rect_moment = rect_mass * (hl * hl + r * r) / 3.0;
+                let half_disc_centroid = 4.0 * r / (3.0 * std::f64::consts::PI);
+                let disc_moment =
+                    disc_mass * (r * r / 2.0 + hl * hl + 2.0 * hl * half_disc_centroid);
+                (rect_moment + disc_moment) / (rect_mass + disc_mass)
+            }
+            ColliderShape::Polygon(poly) => poly.moment_of_inertia_coef(),
+            ColliderShape::Edge(_) => 0.0,
+        }
+    }
+
+    /// Compute the area, centroid and moment of inertia of the collider, assuming uniform density.
+    /// See [`MassProperties`][self::MassProperties].
+    pub fn mass_properties(&self) -> MassProperties {
+        let (centroid, moment_of_inertia_coef) = match self.shape {
+            ColliderShape::Polygon(poly) => {
+                let centroid = poly.centroid();
+                // move the moment from the origin to the centroid
+                (centroid, poly.moment_of_inertia_coef() - centroid.mag_sq())
+            }
+            ColliderShape::Edge(edge) => ((edge.start + edge.end) / 2.0, 0.0),
+            // the rest are symmetrical around their origin
+            _ => (m::Vec2::zero(), self.moment_of_inertia_coef()),
+        };
+        MassProperties {
+            area: self.area(),
+            centroid: self.offset * centroid,
+            moment_of_inertia_coef,
         }
     }
 
@@ -93,8 +203,12 @@ impl Collider {
     pub fn bounding_sphere_r(&self) -> f64 {
         match self.shape {
             ColliderShape::Circle { r } => r,
-            ColliderShape::Rect { hw, hh } => hw * hw + hh * hh,
+            ColliderShape::Rect { hw, hh } => (hw * hw + hh * hh).sqrt(),
             ColliderShape::Capsule { hl, r } => hl + r,
+            ColliderShape::Polygon(poly) => {
+                poly.vertices().iter().map(|v| v.mag()).fold(0.0, f64::max)
+            }
+            ColliderShape::Edge(edge) => edge.start.mag().max(edge.end.mag()),
         }
     }
 
@@ -109,6 +223,28 @@ impl Collider {
             ColliderShape::Capsule { hl, r } => {
                 (pose.rotation * m::Vec2::new(hl, 0.0)).abs() + m::Vec2::new(r, r)
             }
+            ColliderShape::Polygon(poly) => {
+                // polygons aren't symmetrical, so transform every vertex and take the extremes
+                let first = *pose * poly.vertices()[0];
+                let (min, max) = poly.vertices()[1..]
+                    .iter()
+                    .map(|v| *pose * *v)
+                    .fold((first, first), |(min, max), v| {
+                        (min.min_by_component(v), max.max_by_component(v))
+                    });
+                return AABB { min, max };
+            }
+            ColliderShape::Edge(edge) => {
+                let start = *pose * edge.start;
+                let end = *pose * edge.end;
+                // give horizontal and vertical edges some thickness,
+                // a flat box doesn't intersect anything
+                return AABB {
+                    min: start.min_by_component(end),
+                    max: start.max_by_component(end),
+                }
+                .padded(EDGE_AABB_PADDING);
+            }
         };
         AABB {
             min: pose.translation - extent,
@@ -117,8 +253,24 @@ impl Collider {
     }
 }
 
-/// The physical shape of a collider.
+/// Mass distribution of a collider with uniform density,
+/// computed with [`Collider::mass_properties`][self::Collider::mass_properties].
+///
+/// Multiply area and moment of inertia by density to get mass and moment of inertia.
 #[derive(Clone, Copy, Debug)]
+pub struct MassProperties {
+    pub area: f64,
+    /// Center of mass relative to whatever the collider is attached to,
+    /// i.e. with the collider's offset applied.
+    pub centroid: m::Vec2,
+    /// Moment of inertia per unit mass around the centroid.
+    pub moment_of_inertia_coef: f64,
+}
+
+/// The physical shape of a collider.
+// polygons are stored inline to keep this Copy, the size difference is intentional
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub enum ColliderShape {
     Circle {
         r: f64,
@@ -134,14 +286,210 @@ pub enum ColliderShape {
         hl: f64,
         r: f64,
     },
+    /// A convex polygon, see [`Polygon`][self::Polygon].
+    Polygon(Polygon),
+    /// A one-sided line segment, see [`EdgeSegment`][self::EdgeSegment].
+    Edge(EdgeSegment),
+}
+
+/// A one-sided line segment, typically one link in a chain of static terrain.
+///
+/// The colliding side is on the left when looking from `start` towards `end`.
+/// Ghost vertices are the neighboring points in the chain. They're used to smooth out collisions
+/// at the joints between segments, so that things sliding along the chain don't catch on them.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct EdgeSegment {
+    pub start: m::Vec2,
+    pub end: m::Vec2,
+    /// The point before `start` in the chain, if any.
+    pub ghost_prev: Option<m::Vec2>,
+    /// The point after `end` in the chain, if any.
+    pub ghost_next: Option<m::Vec2>,
+}
+
+impl EdgeSegment {
+    /// The unit normal of the colliding side.
+    pub fn normal(&self) -> m::Vec2 {
+        m::left_normal(self.end - self.start).normalized()
+    }
+}
+
+/// Margin around the bounding box of an edge.
+const EDGE_AABB_PADDING: f64 = 0.001;
+
+/// The maximum number of vertices a [`Polygon`][self::Polygon] can have.
+pub const MAX_POLYGON_VERTICES: usize = 8;
+
+/// A convex polygon with vertices in counterclockwise order.
+///
+/// Vertices are stored inline in a fixed-size array so that colliders can stay `Copy`,
+/// which limits the vertex count to [`MAX_POLYGON_VERTICES`][self::MAX_POLYGON_VERTICES].
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Polygon {
+    verts: [m::Vec2; MAX_POLYGON_VERTICES],
+    /// Outward normals of the edges, the edge at index `i` going from vertex `i` to `i + 1`.
+    normals: [m::Vec2; MAX_POLYGON_VERTICES],
+    count: usize,
+}
+
+impl Polygon {
+    /// Create a polygon that is the convex hull of the given points.
+    ///
+    /// # Panics
+    /// Panics if the hull has fewer than 3 or more than
+    /// [`MAX_POLYGON_VERTICES`][self::MAX_POLYGON_VERTICES] vertices.
+    pub fn new(points: &[m::Vec2]) -> Self {
+        let hull = convex_hull(points);
+        assert!(
+            hull.len() >= 3,
+            "A polygon needs at least 3 non-collinear points"
+        );
+        assert!(
+            hull.len() <= MAX_POLYGON_VERTICES,
+            "A polygon can have at most {} vertices, got {}",
+            MAX_POLYGON_VERTICES,
+            hull.len()
+        );
+        Self::from_ccw_unchecked(&hull)
+    }
+
+    /// A polygon matching a rect collider, used to share collision code between the two.
+    pub(crate) fn from_rect(hw: f64, hh: f64) -> Self {
+        Self::from_ccw_unchecked(&[
+            m::Vec2::new(-hw, -hh),
+            m::Vec2::new(hw, -hh),
+            m::Vec2::new(hw, hh),
+            m::Vec2::new(-hw, hh),
+        ])
+    }
+
+    /// A degenerate two-vertex polygon along the x-axis,
+    /// used as the core of a capsule in polygon collision code.
+    /// Its two "edges" are the same line segment facing opposite directions.
+    pub(crate) fn segment(hl: f64) -> Self {
+        Self::segment_between(m::Vec2::new(-hl, 0.0), m::Vec2::new(hl, 0.0))
+    }
+
+    /// A degenerate two-vertex polygon between two arbitrary points.
+    pub(crate) fn segment_between(start: m::Vec2, end: m::Vec2) -> Self {
+        Self::from_ccw_unchecked(&[start, end])
+    }
+
+    fn from_ccw_unchecked(points: &[m::Vec2]) -> Self {
+        let count = points.len();
+        let mut verts = [m::Vec2::zero(); MAX_POLYGON_VERTICES];
+        verts[..count].copy_from_slice(points);
+        let mut normals = [m::Vec2::zero(); MAX_POLYGON_VERTICES];
+        for i in 0..count {
+            let edge = verts[(i + 1) % count] - verts[i];
+            normals[i] = m::right_normal(edge).normalized();
+        }
+        Self {
+            verts,
+            normals,
+            count,
+        }
+    }
+
+    /// The vertices of the polygon in counterclockwise order.
+    #[inline]
+    pub fn vertices(&self) -> &[m::Vec2] {
+        &self.verts[..self.count]
+    }
+
+    /// The outward-facing unit normals of the polygon's edges.
+    /// The edge at index `i` goes from vertex `i` to vertex `i + 1`.
+    #[inline]
+    pub fn normals(&self) -> &[m::Vec2] {
+        &self.normals[..self.count]
+    }
+
+    pub fn area(&self) -> f64 {
+        let verts = self.vertices();
+        let doubled: f64 = (0..self.count)
+            .map(|i| verts[i].wedge(verts[(i + 1) % self.count]).xy)
+            .sum();
+        doubled / 2.0
+    }
+
+    /// The center of area of the polygon.
+    pub fn centroid(&self) -> m::Vec2 {
+        // weighted average of the centroids of triangles formed by the origin and each edge
+        let verts = self.vertices();
+        let (sum, doubled_area) =
+            (0..self.count).fold((m::Vec2::zero(), 0.0), |(sum, doubled_area), i| {
+                let v1 = verts[i];
+                let v2 = verts[(i + 1) % self.count];
+                let cross = v1.wedge(v2).xy;
+                (sum + (v1 + v2) * cross, doubled_area + cross)
+            });
+        sum / (3.0 * doubled_area)
+    }
+
+    /// Moment of inertia per unit mass around the polygon's origin.
+    pub fn moment_of_inertia_coef(&self) -> f64 {
+        // sum over triangles formed by the origin and each edge,
+        // from https://en.wikipedia.org/wiki/Second_moment_of_area#Any_polygon
+        let verts = self.vertices();
+        let (numer, denom) = (0..self.count).fold((0.0, 0.0), |(numer, denom), i| {
+            let v1 = verts[i];
+            let v2 = verts[(i + 1) % self.count];
+            let cross = v1.wedge(v2).xy;
+            (
+                numer + cross * (v1.dot(v1) + v1.dot(v2) + v2.dot(v2)),
+                denom + cross,
+            )
+        });
+        numer / (6.0 * denom)
+    }
+}
+
+/// Andrew's monotone chain algorithm. Returns the hull in counterclockwise order,
+/// with collinear and duplicate points removed.
+fn convex_hull(points: &[m::Vec2]) -> Vec<m::Vec2> {
+    let mut sorted: Vec<m::Vec2> = points.to_vec();
+    sorted.sort_by(|a, b| {
+        (a.x, a.y)
+            .partial_cmp(&(b.x, b.y))
+            .expect("There was a NaN somewhere")
+    });
+    sorted.dedup_by(|a, b| (*a - *b).mag_sq() < f64::EPSILON);
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let turns_left = |o: m::Vec2, a: m::Vec2, b: m::Vec2| (a - o).wedge(b - o).xy > f64::EPSILON;
+    let mut hull: Vec<m::Vec2> = Vec::with_capacity(sorted.len() * 2);
+    // lower hull
+    for &p in &sorted {
+        while hull.len() >= 2 && !turns_left(hull[hull.len() - 2], hull[hull.len() - 1], p) {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // upper hull
+    let lower_len = hull.len() + 1;
+    for &p in sorted.iter().rev().skip(1) {
+        while hull.len() >= lower_len && !turns_left(hull[hull.len() - 2], hull[hull.len() - 1], p)
+        {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // last point is the same as the first
+    hull.pop();
+    hull
 }
 
 /// Type of a collider. Solid ones respond to collisions when attached to bodies.
-/// Triggers only cause an event to be sent.
-#[derive(Clone, Copy, Debug)]
+/// Triggers only cause events to be sent when things enter and exit them.
+/// Triggers without a body only detect colliders attached to bodies.
+/// Fluids are triggers that also push bodies inside them up and slow them down.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub enum ColliderType {
     Solid(Material),
     Trigger,
+    Fluid(Fluid),
 }
 
 impl Default for ColliderType {
@@ -153,10 +501,14 @@ impl Default for ColliderType {
 /// Determines how the surface of a collider affects collisions.
 ///
 /// Using a simplified friction model where each material has its own friction
-/// coefficients (rather than the realistic model where every pair of materials
-/// would have its own coefficients).
-#[derive(Clone, Copy, Debug)]
+/// coefficients, which are combined for each contact according to the materials'
+/// [`CombineMode`][self::CombineMode]s. Specific pairs of materials can be given their own
+/// coefficients with a [`MaterialPairTable`][super::MaterialPairTable].
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Material {
+    /// Identifier used to look up overrides in a
+    /// [`MaterialPairTable`][super::MaterialPairTable].
+    pub id: usize,
     /// Coefficient of static friction.
     /// Set to None to opt out of static friction.
     pub static_friction_coef: Option<f64>,
@@ -164,14 +516,28 @@ pub struct Material {
     /// Set to None to opt out of dynamic friction.
     pub dynamic_friction_coef: Option<f64>,
     pub restitution_coef: f64,
+    /// Speed at which the surface moves along itself in m/s, like a conveyor belt.
+    /// Friction carries things touching the surface along with it.
+    ///
+    /// Positive values move the surface clockwise around the collider,
+    /// so something on top of an unrotated collider is carried in the positive x direction.
+    pub surface_velocity: f64,
+    /// How friction coefficients are combined with another material's.
+    pub friction_combine: CombineMode,
+    /// How restitution coefficients are combined with another material's.
+    pub restitution_combine: CombineMode,
 }
 
 impl Default for Material {
     fn default() -> Self {
         Material {
+            id: 0,
             static_friction_coef: Some(1.6),
             dynamic_friction_coef: Some(1.5),
             restitution_coef: 0.0,
+            surface_velocity: 0.0,
+            friction_combine: CombineMode::Average,
+            restitution_combine: CombineMode::Max,
         }
     }
 }
@@ -179,28 +545,101 @@ impl Default for Material {
 impl Material {
     /// Get the static friction coefficient between this material and another.
     ///
-    /// It is computed as the average between the two materials' friction coefficients.
+    /// It is computed according to the materials' friction combine modes.
     pub fn static_friction_with(&self, other: &Self) -> Option<f64> {
+        let mode = self.friction_combine.with(other.friction_combine);
         match (self.static_friction_coef, other.static_friction_coef) {
-            (Some(mine), Some(theirs)) => Some((mine + theirs) / 2.0),
+            (Some(mine), Some(theirs)) => Some(mode.combine(mine, theirs)),
             _ => None,
         }
     }
 
     /// Get the dynamic friction coefficient between this material and another.
     ///
-    /// It is computed as the average between the two materials' friction coefficients.
+    /// It is computed according to the materials' friction combine modes.
     pub fn dynamic_friction_with(&self, other: &Self) -> Option<f64> {
+        let mode = self.friction_combine.with(other.friction_combine);
         match (self.dynamic_friction_coef, other.dynamic_friction_coef) {
-            (Some(mine), Some(theirs)) => Some((mine + theirs) / 2.0),
+            (Some(mine), Some(theirs)) => Some(mode.combine(mine, theirs)),
             _ => None,
         }
     }
 
     /// Get the restitution coefficient between this material and another.
     ///
-    /// It is computed as the largest coefficient between the two bodies.
+    /// It is computed according to the materials' restitution combine modes.
     pub fn restitution_with(&self, other: &Self) -> f64 {
-        self.restitution_coef.max(other.restitution_coef)
+        self.restitution_combine
+            .with(other.restitution_combine)
+            .combine(self.restitution_coef, other.restitution_coef)
+    }
+}
+
+/// A body of fluid like water that solid colliders attached to bodies can float in.
+///
+/// The fluid's surface is flat and at the highest point of its collider,
+/// where up is the opposite of the acceleration from the
+/// [`ForceField`][crate::physics::ForceField] given to the physics system.
+/// The submerged part of each collider overlapping the fluid is computed every substep,
+/// and buoyancy and drag forces are applied at its centroid.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Fluid {
+    /// Density of the fluid, in the same units as the densities of bodies.
+    /// Bodies less dense than this float.
+    pub density: f64,
+    /// Coefficient of drag slowing down movement relative to the flow,
+    /// scaled by the submerged area.
+    pub linear_drag: f64,
+    /// Coefficient of drag slowing down rotation, scaled by the submerged area.
+    pub angular_drag: f64,
+    /// Velocity the fluid flows at in m/s, dragging bodies along with it.
+    pub flow_velocity: m::Vec2,
+}
+
+impl Default for Fluid {
+    fn default() -> Self {
+        Fluid {
+            density: 1.0,
+            linear_drag: 1.0,
+            angular_drag: 0.5,
+            flow_velocity: m::Vec2::zero(),
+        }
+    }
+}
+
+/// Rule for combining a coefficient of two materials into one.
+///
+/// If two materials have different modes, the one listed later here is used.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum CombineMode {
+    /// The mean of the two coefficients.
+    Average,
+    /// The square root of the product of the two coefficients.
+    GeometricMean,
+    /// The smaller of the two coefficients.
+    Min,
+    /// The product of the two coefficients.
+    Multiply,
+    /// The larger of the two coefficients.
+    Max,
+}
+
+impl CombineMode {
+    /// Combine two coefficients using this mode.
+    pub fn combine(self, a: f64, b: f64) -> f64 {
+        match self {
+            CombineMode::Average => (a + b) / 2.0,
+            CombineMode::GeometricMean => (a * b).sqrt(),
+            CombineMode::Min => a.min(b),
+            CombineMode::Multiply => a * b,
+            CombineMode::Max => a.max(b),
+        }
+    }
+
+    /// Pick the mode to use between two materials.
+    fn with(self, other: Self) -> Self {
+        self.max(other)
     }
 }
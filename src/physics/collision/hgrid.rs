@@ -11,6 +11,7 @@ use crate::math as m;
 /// If the object count or world size is very large, it will eat up a lot of memory.
 #[derive(Clone, Debug)]
 pub struct HGrid {
+    #[allow(dead_code)]
     bounds: AABB,
     // bitsets for grid cells are stored as a contiguous buffer,
     // interpreted in chunks of <mask_size> u64s.
@@ -23,6 +24,8 @@ pub struct HGrid {
     timestamps: Vec<u16>,
     // cache AABBs that colliders were inserted with
     aabbs: Vec<AABB>,
+    // ids inserted since the last prepare, to tell which cached AABBs are current
+    inserted: Vec<usize>,
 }
 
 #[derive(Clone, Debug)]
@@ -89,6 +92,7 @@ impl HGrid {
             curr_timestamp: 0,
             timestamps: vec![0; params.expected_obj_count],
             aabbs: vec![AABB::zero(); params.expected_obj_count],
+            inserted: Vec::with_capacity(params.expected_obj_count),
         }
     }
 
@@ -127,10 +131,30 @@ impl HGrid {
         }
         self.timestamps.resize(collider_count, 0);
         self.aabbs.resize(collider_count, AABB::zero());
+        self.inserted.clear();
+    }
+
+    /// Ids and bounding boxes of everything inserted since the last `prepare`,
+    /// in the order they were inserted.
+    pub(crate) fn contents(&self) -> impl '_ + Iterator<Item = (usize, AABB)> {
+        self.inserted.iter().map(move |&id| (id, self.aabbs[id]))
+    }
+
+    /// Recreate the index from scratch with the given contents, as returned by `contents`.
+    pub(crate) fn restore(
+        &mut self,
+        collider_count: usize,
+        contents: impl IntoIterator<Item = (usize, AABB)>,
+    ) {
+        self.prepare(collider_count);
+        for (id, aabb) in contents {
+            self.insert(aabb, id);
+        }
     }
 
     pub(crate) fn insert(&mut self, aabb: AABB, id: usize) {
         self.aabbs[id] = aabb;
+        self.inserted.push(id);
 
         // select grid level based on smaller extent of the aabb
         let aabb_size = aabb.width().min(aabb.height());
@@ -203,12 +227,12 @@ impl HGrid {
                     })
                 })
             })
-            .filter_map(move |id| {
+            .filter(move |&id| {
                 if timestamps[id] != curr_timestamp {
                     timestamps[id] = curr_timestamp;
-                    Some(id)
+                    true
                 } else {
-                    None
+                    false
                 }
             })
     }
@@ -223,9 +247,12 @@ trait IterableBitset {
     fn get_word(&self, idx: usize) -> u64;
 }
 
+// only used in tests for now
+#[allow(dead_code)]
 #[derive(Clone, Copy)]
 struct Bitset<'a>(&'a [u64]);
 
+#[allow(dead_code)]
 impl<'a> Bitset<'a> {
     pub fn iter(&self) -> BitsetIter<Self> {
         BitsetIter {
@@ -1,6 +1,9 @@
 //! Intersection queries for points, rays, etc. vs. colliders.
 
-use super::{Collider, ColliderShape};
+use super::{
+    shape_shape::intersection_check, Collider, ColliderShape, ContactResult, EdgeSegment, Polygon,
+    MAX_POLYGON_VERTICES,
+};
 use crate::math as m;
 
 /// Check whether or not a point intersects with a collider.
@@ -14,5 +17,513 @@ pub fn point_collider_bool(point: m::Vec2, pose: &m::Pose, coll: &Collider) -> b
             let y_dist = p_wrt_c.y.abs();
             x_dist * x_dist + y_dist * y_dist < r * r
         }
+        ColliderShape::Polygon(poly) => poly
+            .vertices()
+            .iter()
+            .zip(poly.normals())
+            .all(|(v, n)| n.dot(p_wrt_c - *v) < 0.0),
+        // edges have no area
+        ColliderShape::Edge(_) => false,
+    }
+}
+
+/// A half-line starting from a point and going infinitely in one direction.
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub start: m::Vec2,
+    pub dir: m::Unit<m::Vec2>,
+}
+
+impl Ray {
+    /// Create a ray, normalizing the direction.
+    pub fn new(start: m::Vec2, dir: m::Vec2) -> Self {
+        Ray {
+            start,
+            dir: m::Unit::new_normalize(dir),
+        }
+    }
+
+    /// Get the point at the given distance along the ray.
+    pub fn point_at(&self, t: f64) -> m::Vec2 {
+        self.start + t * *self.dir
+    }
+}
+
+/// The point where a ray first hits a collider.
+#[derive(Clone, Copy, Debug)]
+pub struct RayIntersection {
+    /// Distance from the start of the ray.
+    pub t: f64,
+    /// Surface normal at the point of intersection, in world space.
+    pub normal: m::Unit<m::Vec2>,
+}
+
+/// Find the first point where a ray hits a collider, if it does so before `max_t`.
+///
+/// Rays starting inside a collider don't hit it.
+pub fn ray_collider(
+    ray: Ray,
+    max_t: f64,
+    pose: &m::Pose,
+    coll: &Collider,
+) -> Option<RayIntersection> {
+    // work in collider-local space
+    let start = pose.inversed() * ray.start;
+    let dir = pose.rotation.reversed() * *ray.dir;
+
+    let (t, normal) = match coll.shape {
+        ColliderShape::Circle { r } => ray_circle(start, dir, max_t, m::Vec2::zero(), r),
+        ColliderShape::Rect { hw, hh } => {
+            ray_polygon(start, dir, max_t, &Polygon::from_rect(hw, hh))
+        }
+        ColliderShape::Capsule { hl, r } => {
+            // the parts overlap, so a ray starting inside one of them
+            // could still hit another one without this check
+            if point_collider_bool(ray.start, pose, coll) {
+                return None;
+            }
+            let parts = [
+                ray_circle(start, dir, max_t, m::Vec2::new(-hl, 0.0), r),
+                ray_circle(start, dir, max_t, m::Vec2::new(hl, 0.0), r),
+                if hl > 0.0 {
+                    ray_polygon(start, dir, max_t, &Polygon::from_rect(hl, r))
+                } else {
+                    None
+                },
+            ];
+            parts
+                .iter()
+                .flatten()
+                .min_by(|(t1, _), (t2, _)| t1.partial_cmp(t2).unwrap())
+                .copied()
+        }
+        ColliderShape::Polygon(poly) => ray_polygon(start, dir, max_t, &poly),
+        ColliderShape::Edge(edge) => ray_edge(start, dir, max_t, &edge),
+    }?;
+
+    Some(RayIntersection {
+        t,
+        normal: pose.rotation * m::Unit::new_normalize(normal),
+    })
+}
+
+fn ray_circle(
+    start: m::Vec2,
+    dir: m::Vec2,
+    max_t: f64,
+    center: m::Vec2,
+    r: f64,
+) -> Option<(f64, m::Vec2)> {
+    let to_start = start - center;
+    let b = to_start.dot(dir);
+    let c = to_start.mag_sq() - r * r;
+    // starting inside or pointing away
+    if c < 0.0 || b > 0.0 {
+        return None;
+    }
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = -b - discriminant.sqrt();
+    if t > max_t {
+        return None;
+    }
+    Some((t, start + t * dir - center))
+}
+
+/// Cyrus-Beck clipping against the faces of the polygon.
+fn ray_polygon(start: m::Vec2, dir: m::Vec2, max_t: f64, poly: &Polygon) -> Option<(f64, m::Vec2)> {
+    let mut t_enter = 0.0;
+    let mut t_exit = max_t;
+    let mut enter_normal = None;
+    for (v, n) in poly.vertices().iter().zip(poly.normals()) {
+        let dist = n.dot(*v - start);
+        let speed = n.dot(dir);
+        if speed == 0.0 {
+            if dist < 0.0 {
+                // parallel to and outside of this face
+                return None;
+            }
+        } else {
+            let t = dist / speed;
+            if speed < 0.0 {
+                if t > t_enter {
+                    t_enter = t;
+                    enter_normal = Some(*n);
+                }
+            } else if t < t_exit {
+                t_exit = t;
+            }
+            if t_enter > t_exit {
+                return None;
+            }
+        }
+    }
+    // no entering face means the ray started inside
+    enter_normal.map(|n| (t_enter, n))
+}
+
+fn ray_edge(
+    start: m::Vec2,
+    dir: m::Vec2,
+    max_t: f64,
+    edge: &EdgeSegment,
+) -> Option<(f64, m::Vec2)> {
+    let normal = edge.normal();
+    let dist = normal.dot(start - edge.start);
+    let speed = normal.dot(dir);
+    // edges can only be hit from the front
+    if dist < 0.0 || speed >= 0.0 {
+        return None;
+    }
+    let t = -dist / speed;
+    if t > max_t {
+        return None;
+    }
+    let along = edge.end - edge.start;
+    let hit_param = (start + t * dir - edge.start).dot(along) / along.mag_sq();
+    if (0.0..=1.0).contains(&hit_param) {
+        Some((t, normal))
+    } else {
+        None
+    }
+}
+
+/// The point where a moving collider first touches another.
+#[derive(Clone, Copy, Debug)]
+pub struct ShapeCastIntersection {
+    /// Distance travelled along the motion vector before touching.
+    pub t: f64,
+    /// Point of contact on the surface of the hit collider, in world space.
+    pub point: m::Vec2,
+    /// Surface normal of the hit collider at the point of contact, in world space.
+    pub normal: m::Unit<m::Vec2>,
+}
+
+/// Move a collider from `start_pose` along `motion` without rotating it
+/// and find the first point where it touches another collider.
+///
+/// Colliders that already intersect the moving one at its starting pose aren't hit.
+/// Like in collision detection, one-sided edges are only hit
+/// by things whose center is in front of them.
+///
+/// Every collider is a point, segment or convex polygon rounded by some radius,
+/// so the first time of impact is found exactly by casting a ray
+/// against the rounded Minkowski difference of the two shapes.
+/// Nothing is missed no matter how thin the colliders are or how far the motion goes.
+pub fn shape_cast_collider(
+    coll: &Collider,
+    start_pose: &m::Pose,
+    motion: m::Vec2,
+    other_pose: &m::Pose,
+    other_coll: &Collider,
+) -> Option<ShapeCastIntersection> {
+    let motion_len = motion.mag();
+    let both_edges = matches!(
+        (coll.shape, other_coll.shape),
+        (ColliderShape::Edge(_), ColliderShape::Edge(_))
+    );
+    if motion_len == 0.0
+        || both_edges
+        || !matches!(
+            intersection_check(start_pose, coll, other_pose, other_coll),
+            ContactResult::Zero
+        )
+    {
+        return None;
+    }
+    let dir = motion / motion_len;
+
+    // edges ignore things whose center is behind them, before and at the moment of impact
+    let center_in_front =
+        |edge_coll: &Collider, edge_pose: &m::Pose, center: m::Vec2| match edge_coll.shape {
+            ColliderShape::Edge(edge) => {
+                edge.normal()
+                    .dot(edge_pose.inversed() * center - edge.start)
+                    >= 0.0
+            }
+            _ => true,
+        };
+    let other_center_wrt_moving = |t: f64| other_pose.translation - t * motion;
+    let in_front_at = |t: f64| {
+        let mut moving_pose = *start_pose;
+        moving_pose.append_translation(t * motion);
+        center_in_front(other_coll, other_pose, moving_pose.translation)
+            && center_in_front(coll, start_pose, other_center_wrt_moving(t))
+    };
+    if !in_front_at(0.0) {
+        return None;
+    }
+
+    // the moving collider touches the other one at distance t along the motion
+    // when t * dir is in the Minkowski difference (other - moving).
+    // the boundary of that is covered by edges of one shape translated by vertices of the other,
+    // so the first hit on any of those (rounded by both radii) is the first hit on the whole thing
+    let moving = RoundedCore::new(coll, start_pose);
+    let other = RoundedCore::new(other_coll, other_pose);
+    let radius = moving.radius + other.radius;
+    let mut first_hit: Option<(f64, m::Vec2, [m::Vec2; 2], [m::Vec2; 2])> = None;
+    let mut test_segment = |segment: [m::Vec2; 2], on_other: [m::Vec2; 2]| {
+        let max_t = first_hit.map(|(t, ..)| t).unwrap_or(motion_len);
+        if let Some((t, normal)) = ray_rounded_segment(m::Vec2::zero(), dir, max_t, segment, radius)
+        {
+            first_hit = Some((t, normal, segment, on_other));
+        }
+    };
+    for &vert in moving.points() {
+        for [start, end] in other.edges() {
+            test_segment([start - vert, end - vert], [start, end]);
+        }
+    }
+    for &vert in other.points() {
+        for [start, end] in moving.edges() {
+            test_segment([vert - start, vert - end], [vert, vert]);
+        }
+    }
+
+    let (t, normal, segment, on_other) = first_hit?;
+    if !in_front_at(t / motion_len) {
+        return None;
+    }
+    let normal = m::Unit::new_normalize(normal);
+    // the point on the other shape's core moves along with the closest point on the segment
+    let along = segment[1] - segment[0];
+    let param = if along == m::Vec2::zero() {
+        0.0
+    } else {
+        ((t * dir - segment[0]).dot(along) / along.mag_sq()).clamp(0.0, 1.0)
+    };
+    let point = on_other[0] + param * (on_other[1] - on_other[0]) + other.radius * *normal;
+
+    Some(ShapeCastIntersection { t, point, normal })
+}
+
+/// A collider as a point, segment or convex polygon in world space rounded by a radius.
+struct RoundedCore {
+    points: [m::Vec2; MAX_POLYGON_VERTICES],
+    count: usize,
+    radius: f64,
+}
+
+impl RoundedCore {
+    fn new(coll: &Collider, pose: &m::Pose) -> Self {
+        let mut points = [m::Vec2::zero(); MAX_POLYGON_VERTICES];
+        let mut set_points = |local: &[m::Vec2]| {
+            for (point, local) in points.iter_mut().zip(local) {
+                *point = *pose * *local;
+            }
+            local.len()
+        };
+        let (count, radius) = match coll.shape {
+            ColliderShape::Circle { r } => (set_points(&[m::Vec2::zero()]), r),
+            ColliderShape::Rect { hw, hh } => (
+                set_points(&[
+                    m::Vec2::new(-hw, -hh),
+                    m::Vec2::new(hw, -hh),
+                    m::Vec2::new(hw, hh),
+                    m::Vec2::new(-hw, hh),
+                ]),
+                0.0,
+            ),
+            ColliderShape::Capsule { hl, r } => (
+                set_points(&[m::Vec2::new(-hl, 0.0), m::Vec2::new(hl, 0.0)]),
+                r,
+            ),
+            ColliderShape::Polygon(poly) => (set_points(poly.vertices()), 0.0),
+            ColliderShape::Edge(edge) => (set_points(&[edge.start, edge.end]), 0.0),
+        };
+        RoundedCore {
+            points,
+            count,
+            radius,
+        }
+    }
+
+    fn points(&self) -> &[m::Vec2] {
+        &self.points[..self.count]
+    }
+
+    /// Edges of the core. A point is one zero-length edge and a segment is one edge,
+    /// polygons are closed loops.
+    fn edges(&self) -> impl Iterator<Item = [m::Vec2; 2]> + '_ {
+        let points = self.points();
+        let edge_count = if points.len() < 3 { 1 } else { points.len() };
+        (0..edge_count).map(move |i| [points[i], points[(i + 1) % points.len()]])
+    }
+}
+
+/// First hit of a ray starting outside of a line segment rounded by a radius
+/// (a capsule, or a circle if the segment has zero length).
+fn ray_rounded_segment(
+    start: m::Vec2,
+    dir: m::Vec2,
+    max_t: f64,
+    segment: [m::Vec2; 2],
+    r: f64,
+) -> Option<(f64, m::Vec2)> {
+    let along = segment[1] - segment[0];
+    let side_offset = if along == m::Vec2::zero() {
+        m::Vec2::zero()
+    } else {
+        r * m::left_normal(along).normalized()
+    };
+    let mut parts = [
+        ray_segment(start, dir, max_t, segment[0], segment[1]),
+        None,
+        None,
+        None,
+    ];
+    if r > 0.0 {
+        parts[0] = ray_segment(
+            start,
+            dir,
+            max_t,
+            segment[0] + side_offset,
+            segment[1] + side_offset,
+        );
+        parts[1] = ray_segment(
+            start,
+            dir,
+            max_t,
+            segment[0] - side_offset,
+            segment[1] - side_offset,
+        );
+        parts[2] = ray_circle(start, dir, max_t, segment[0], r);
+        parts[3] = ray_circle(start, dir, max_t, segment[1], r);
+    }
+    parts
+        .iter()
+        .flatten()
+        .min_by(|(t1, _), (t2, _)| t1.partial_cmp(t2).unwrap())
+        .copied()
+}
+
+/// Ray against a two-sided line segment, with the normal facing the start of the ray.
+fn ray_segment(
+    start: m::Vec2,
+    dir: m::Vec2,
+    max_t: f64,
+    seg_start: m::Vec2,
+    seg_end: m::Vec2,
+) -> Option<(f64, m::Vec2)> {
+    let along = seg_end - seg_start;
+    let normal = m::left_normal(along);
+    let speed = normal.dot(dir);
+    if speed == 0.0 {
+        return None;
+    }
+    let t = normal.dot(seg_start - start) / speed;
+    if !(0.0..=max_t).contains(&t) {
+        return None;
+    }
+    let hit_param = (start + t * dir - seg_start).dot(along) / along.mag_sq();
+    if !(0.0..=1.0).contains(&hit_param) {
+        return None;
+    }
+    Some((t, if speed < 0.0 { normal } else { -normal }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pose_at(x: f64, y: f64) -> m::Pose {
+        m::Pose::new(m::Vec2::new(x, y), m::Rotor2::identity())
+    }
+
+    /// Motion far longer than either shape is thick doesn't skip over the other shape.
+    #[test]
+    fn cast_hits_thin_wall_from_far_away() {
+        let bullet = Collider::new_circle(0.01);
+        let wall = Collider::new_rect(0.002, 2.0);
+        let hit = shape_cast_collider(
+            &bullet,
+            &pose_at(-100.0, 0.3),
+            m::Vec2::new(200.0, 0.0),
+            &pose_at(0.0, 0.0),
+            &wall,
+        )
+        .expect("Bullet went through the wall");
+        assert!((hit.t - (100.0 - 0.011)).abs() < 1e-9);
+        assert!((hit.point - m::Vec2::new(-0.001, 0.3)).mag() < 1e-9);
+        assert!((*hit.normal - m::Vec2::new(-1.0, 0.0)).mag() < 1e-9);
+    }
+
+    /// Polygons whose origin is outside of them still get hit.
+    #[test]
+    fn cast_polygon_with_outside_origin() {
+        let poly = Collider::new_polygon(&[
+            m::Vec2::new(1.0, 1.0),
+            m::Vec2::new(2.0, 1.0),
+            m::Vec2::new(1.5, 2.0),
+        ]);
+        let ground = Collider::new_rect(10.0, 0.001);
+        let hit = shape_cast_collider(
+            &poly,
+            &pose_at(0.0, 50.0),
+            m::Vec2::new(0.0, -100.0),
+            &m::Pose::identity(),
+            &ground,
+        )
+        .expect("Polygon went through the ground");
+        assert!((hit.t - 50.9995).abs() < 1e-9);
+        assert!((*hit.normal - m::Vec2::new(0.0, 1.0)).mag() < 1e-9);
+        assert!((hit.point.y - 0.0005).abs() < 1e-9 && hit.point.x >= 1.0 && hit.point.x <= 2.0);
+    }
+
+    #[test]
+    fn cast_against_edge() {
+        let ground = Collider::new_edge(m::Vec2::new(-5.0, 0.0), m::Vec2::new(5.0, 0.0));
+        let hit = shape_cast_collider(
+            &Collider::new_square(0.5),
+            &pose_at(1.0, 30.0),
+            m::Vec2::new(0.0, -60.0),
+            &m::Pose::identity(),
+            &ground,
+        )
+        .expect("Box went through the edge");
+        assert!((hit.t - 29.75).abs() < 1e-9);
+        assert!((*hit.normal - m::Vec2::new(0.0, 1.0)).mag() < 1e-9);
+    }
+
+    /// Edges can only be hit by things coming from the front.
+    #[test]
+    fn cast_passes_through_back_of_edge() {
+        let ground = Collider::new_edge(m::Vec2::new(-5.0, 0.0), m::Vec2::new(5.0, 0.0));
+        let hit = shape_cast_collider(
+            &Collider::new_square(0.5),
+            &pose_at(0.0, -3.0),
+            m::Vec2::new(0.0, 6.0),
+            &m::Pose::identity(),
+            &ground,
+        );
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn cast_rounded_shapes_touch_at_sum_of_radii() {
+        let hit = shape_cast_collider(
+            &Collider::new_capsule(1.0, 0.25),
+            &pose_at(0.0, 0.0),
+            m::Vec2::new(10.0, 10.0),
+            &pose_at(5.0, 5.0),
+            &Collider::new_circle(0.5),
+        )
+        .unwrap();
+        // the capsule's right cap hits the circle on the diagonal
+        let cap_center = m::Vec2::new(0.5, 0.0);
+        let along = m::Vec2::new(1.0, 1.0).normalized();
+        let moved = cap_center + hit.t * along;
+        assert!(((moved - m::Vec2::new(5.0, 5.0)).mag() - 0.75).abs() < 1e-9);
+        // starting out touching is not a hit
+        assert!(shape_cast_collider(
+            &Collider::new_circle(1.0),
+            &pose_at(0.0, 0.0),
+            m::Vec2::new(1.0, 0.0),
+            &pose_at(1.5, 0.0),
+            &Collider::new_circle(1.0),
+        )
+        .is_none());
     }
 }
@@ -1,4 +1,4 @@
-use super::collider::ColliderShape;
+use super::collider::{self, ColliderShape};
 use crate::math::{self as m, Pose, Unit};
 use crate::physics::Collider;
 
@@ -65,6 +65,10 @@ pub fn intersection_check(
 ) -> ContactResult {
     use ColliderShape::*;
     match (coll1.shape, coll2.shape) {
+        // edges are only meant for static terrain, so they never need to collide with each other
+        (Edge(_), Edge(_)) => ContactResult::Zero,
+        (Edge(edge), _) => edge_shape(pose1, &edge, pose2, coll2),
+        (_, Edge(edge)) => flip_contacts(edge_shape(pose2, &edge, pose1, coll1)),
         (Circle { r: r1 }, Circle { r: r2 }) => circle_circle(pose1, r1, pose2, r2),
         (Circle { r }, Rect { hw, hh }) => flip_contacts(rect_circle(pose2, hw, hh, pose1, r)),
         (Rect { hw, hh }, Circle { r }) => rect_circle(pose1, hw, hh, pose2, r),
@@ -84,6 +88,29 @@ pub fn intersection_check(
         (Capsule { hl: hl1, r: r1 }, Capsule { hl: hl2, r: r2 }) => {
             capsule_capsule(pose1, hl1, r1, pose2, hl2, r2)
         }
+        (Polygon(poly), Circle { r }) => polygon_circle(pose1, &poly, pose2, r),
+        (Circle { r }, Polygon(poly)) => flip_contacts(polygon_circle(pose2, &poly, pose1, r)),
+        (Polygon(poly), Rect { hw, hh }) => polygon_polygon(
+            pose1,
+            &poly,
+            0.0,
+            pose2,
+            &collider::Polygon::from_rect(hw, hh),
+            0.0,
+        ),
+        (Rect { hw, hh }, Polygon(poly)) => polygon_polygon(
+            pose1,
+            &collider::Polygon::from_rect(hw, hh),
+            0.0,
+            pose2,
+            &poly,
+            0.0,
+        ),
+        (Polygon(poly), Capsule { hl, r }) => polygon_capsule(pose1, &poly, pose2, hl, r),
+        (Capsule { hl, r }, Polygon(poly)) => {
+            flip_contacts(polygon_capsule(pose2, &poly, pose1, hl, r))
+        }
+        (Polygon(poly1), Polygon(poly2)) => polygon_polygon(pose1, &poly1, 0.0, pose2, &poly2, 0.0),
     }
 }
 
@@ -498,7 +525,7 @@ fn rect_capsule(
             (
                 m::Vec2::zero(),
                 Unit::new_unchecked(m::Vec2::unit_x()),
-                std::f64::MAX,
+                f64::MAX,
             )
         } else {
             let closest_rect_corner = m::Vec2::new(
@@ -876,6 +903,508 @@ fn capsule_capsule(
     }
 }
 
+//
+// POLYGON <-> CIRCLE
+//
+
+fn polygon_circle(
+    pose_poly: &m::Pose,
+    poly: &collider::Polygon,
+    pose_circle: &m::Pose,
+    r: f64,
+) -> ContactResult {
+    let pose_c_wrt_poly = pose_poly.inversed() * *pose_circle;
+    let center = pose_c_wrt_poly.translation;
+    let verts = poly.vertices();
+    let normals = poly.normals();
+
+    // find the edge the circle's center is furthest outside of
+    let (edge_i, separation) = normals
+        .iter()
+        .zip(verts)
+        .map(|(n, v)| n.dot(center - *v))
+        .enumerate()
+        .max_by(|(_, s1), (_, s2)| s1.partial_cmp(s2).expect("There was a NaN somewhere"))
+        .unwrap();
+    if separation > r {
+        return ContactResult::Zero;
+    }
+
+    let edge_normal = Unit::new_unchecked(normals[edge_i]);
+    let (point, normal) = if separation <= 0.0 {
+        // circle center is inside the polygon, push out through the closest edge
+        (center - separation * *edge_normal, edge_normal)
+    } else {
+        // outside the closest edge, the closest point is either on the edge or one of its ends
+        let v1 = verts[edge_i];
+        let v2 = verts[(edge_i + 1) % verts.len()];
+        let closest_vert = if (center - v1).dot(v2 - v1) <= 0.0 {
+            Some(v1)
+        } else if (center - v2).dot(v1 - v2) <= 0.0 {
+            Some(v2)
+        } else {
+            None
+        };
+        match closest_vert {
+            Some(vert) => {
+                if (center - vert).mag_sq() >= r * r {
+                    return ContactResult::Zero;
+                }
+                (vert, Unit::new_normalize(center - vert))
+            }
+            None => (center - separation * *edge_normal, edge_normal),
+        }
+    };
+
+    ContactResult::One(Contact {
+        normal: pose_poly.rotation * normal,
+        offsets: [point, pose_c_wrt_poly.rotation.reversed() * (-r * *normal)],
+    })
+}
+
+//
+// POLYGON <-> CAPSULE
+//
+
+fn polygon_capsule(
+    pose_poly: &m::Pose,
+    poly: &collider::Polygon,
+    pose_cap: &m::Pose,
+    hl: f64,
+    r: f64,
+) -> ContactResult {
+    if hl <= 0.0 {
+        // zero-length capsule is a circle and has no direction to build a segment from
+        return polygon_circle(pose_poly, poly, pose_cap, r);
+    }
+    // a capsule is a line segment with a radius
+    polygon_polygon(
+        pose_poly,
+        poly,
+        0.0,
+        pose_cap,
+        &collider::Polygon::segment(hl),
+        r,
+    )
+}
+
+//
+// POLYGON <-> POLYGON
+//
+
+/// How much further apart the closest points of two separated polygons can be
+/// than their best separating edge before they're considered to touch at corners only.
+const POLYGON_FACE_TOLERANCE: f64 = 0.0001;
+
+/// Separating axis test between two convex polygons, each optionally rounded by a radius.
+/// Rounding allows capsules to use this as segments with a radius.
+///
+/// The edge with the least penetration is chosen as the reference edge,
+/// and the most opposing edge on the other polygon is clipped against it to get contact points.
+fn polygon_polygon(
+    pose1: &m::Pose,
+    poly1: &collider::Polygon,
+    r1: f64,
+    pose2: &m::Pose,
+    poly2: &collider::Polygon,
+    r2: f64,
+) -> ContactResult {
+    let pose2_wrt_pose1 = pose1.inversed() * *pose2;
+    let pose1_wrt_pose2 = pose2_wrt_pose1.inversed();
+    let r_sum = r1 + r2;
+
+    let (edge1, sep1) = max_separation(poly1, poly2, &pose2_wrt_pose1);
+    if sep1 > r_sum {
+        return ContactResult::Zero;
+    }
+    let (edge2, sep2) = max_separation(poly2, poly1, &pose1_wrt_pose2);
+    if sep2 > r_sum {
+        return ContactResult::Zero;
+    }
+
+    let max_sep = sep1.max(sep2);
+    if max_sep > 0.0 {
+        // the polygons themselves don't overlap, only their rounded margins can.
+        // separating axes only give a lower bound for distance in this case,
+        // so find the actual closest points
+        let [p1, p2] = closest_points(poly1, poly2, &pose2_wrt_pose1);
+        let dist = (p2 - p1).mag();
+        if dist >= r_sum {
+            return ContactResult::Zero;
+        }
+        if dist - max_sep > POLYGON_FACE_TOLERANCE {
+            // closest points are corners, only one contact point
+            let normal = Unit::new_unchecked((p2 - p1) / dist);
+            return ContactResult::One(Contact {
+                normal: pose1.rotation * normal,
+                offsets: [p1 + r1 * *normal, pose1_wrt_pose2 * (p2 - r2 * *normal)],
+            });
+        }
+    }
+
+    // prefer poly1 as the reference unless poly2 is clearly better
+    // to avoid flip-flopping between the two when they're close
+    if sep2 > 0.98 * sep1 + 0.001 {
+        flip_contacts(polygon_clip(pose2, poly2, r2, edge2, pose1, poly1, r1))
+    } else {
+        polygon_clip(pose1, poly1, r1, edge1, pose2, poly2, r2)
+    }
+}
+
+/// Find the edge of `poly` that `other` is furthest outside of, and the distance it's outside by.
+fn max_separation(
+    poly: &collider::Polygon,
+    other: &collider::Polygon,
+    other_wrt_poly: &m::Pose,
+) -> (usize, f64) {
+    let other_verts = transformed_vertices(other, other_wrt_poly);
+    let other_verts = &other_verts[..other.vertices().len()];
+    poly.normals()
+        .iter()
+        .zip(poly.vertices())
+        .map(|(n, v)| {
+            other_verts
+                .iter()
+                .map(|ov| n.dot(*ov - *v))
+                .fold(f64::MAX, f64::min)
+        })
+        .enumerate()
+        .max_by(|(_, s1), (_, s2)| s1.partial_cmp(s2).expect("There was a NaN somewhere"))
+        .unwrap()
+}
+
+/// Closest points between the boundaries of two polygons that don't intersect,
+/// in poly1's local space.
+fn closest_points(
+    poly1: &collider::Polygon,
+    poly2: &collider::Polygon,
+    pose2_wrt_pose1: &m::Pose,
+) -> [m::Vec2; 2] {
+    fn closest_on_segment(p: m::Vec2, start: m::Vec2, end: m::Vec2) -> m::Vec2 {
+        let edge = end - start;
+        let t = ((p - start).dot(edge) / edge.mag_sq()).clamp(0.0, 1.0);
+        start + t * edge
+    }
+
+    let verts1 = poly1.vertices();
+    let verts2 = transformed_vertices(poly2, pose2_wrt_pose1);
+    let verts2 = &verts2[..poly2.vertices().len()];
+
+    // because the polygons don't intersect, the closest points always involve
+    // at least one vertex, so checking every vertex against every edge is enough
+    let mut closest = [verts1[0], verts2[0]];
+    let mut closest_dist_sq = f64::MAX;
+    for (i, &v1) in verts1.iter().enumerate() {
+        for (j, &v2) in verts2.iter().enumerate() {
+            let on_edge2 = closest_on_segment(v1, v2, verts2[(j + 1) % verts2.len()]);
+            let dist_sq = (on_edge2 - v1).mag_sq();
+            if dist_sq < closest_dist_sq {
+                closest_dist_sq = dist_sq;
+                closest = [v1, on_edge2];
+            }
+            let on_edge1 = closest_on_segment(v2, v1, verts1[(i + 1) % verts1.len()]);
+            let dist_sq = (v2 - on_edge1).mag_sq();
+            if dist_sq < closest_dist_sq {
+                closest_dist_sq = dist_sq;
+                closest = [on_edge1, v2];
+            }
+        }
+    }
+    closest
+}
+
+/// Vertices of a polygon moved by a pose, in a fixed-size buffer to avoid allocating.
+/// Only the first `poly.vertices().len()` of them are meaningful.
+fn transformed_vertices(
+    poly: &collider::Polygon,
+    pose: &m::Pose,
+) -> [m::Vec2; collider::MAX_POLYGON_VERTICES] {
+    let mut verts = [m::Vec2::zero(); collider::MAX_POLYGON_VERTICES];
+    for (target, v) in verts.iter_mut().zip(poly.vertices()) {
+        *target = *pose * *v;
+    }
+    verts
+}
+
+/// Clip the incident polygon's most opposing edge against a reference edge on the other.
+/// Contacts are returned with the reference polygon as object 1.
+fn polygon_clip(
+    pose_ref: &m::Pose,
+    poly_ref: &collider::Polygon,
+    r_ref: f64,
+    ref_edge_i: usize,
+    pose_inc: &m::Pose,
+    poly_inc: &collider::Polygon,
+    r_inc: f64,
+) -> ContactResult {
+    // everything in the reference polygon's space
+    let inc_wrt_ref = pose_ref.inversed() * *pose_inc;
+    let ref_verts = poly_ref.vertices();
+    let axis = Unit::new_unchecked(poly_ref.normals()[ref_edge_i]);
+    let ref_start = ref_verts[ref_edge_i];
+    let ref_end = ref_verts[(ref_edge_i + 1) % ref_verts.len()];
+
+    // incident edge is the one facing most directly against the reference edge
+    let inc_verts = poly_inc.vertices();
+    let (inc_edge_i, _) = poly_inc
+        .normals()
+        .iter()
+        .map(|n| (inc_wrt_ref.rotation * *n).dot(*axis))
+        .enumerate()
+        .min_by(|(_, d1), (_, d2)| d1.partial_cmp(d2).expect("There was a NaN somewhere"))
+        .unwrap();
+    let inc_points = [
+        inc_wrt_ref * inc_verts[inc_edge_i],
+        inc_wrt_ref * inc_verts[(inc_edge_i + 1) % inc_verts.len()],
+    ];
+    // start the incident edge from its deeper end like in rect_rect
+    let (inc_start, inc_end) = if axis.dot(inc_points[0]) <= axis.dot(inc_points[1]) {
+        (inc_points[0], inc_points[1])
+    } else {
+        (inc_points[1], inc_points[0])
+    };
+
+    let owning_edge = Edge {
+        start: ref_start,
+        dir: Unit::new_normalize(ref_end - ref_start),
+        length: (ref_end - ref_start).mag(),
+    };
+    let incident_edge = Edge {
+        start: inc_start,
+        dir: Unit::new_normalize(inc_end - inc_start),
+        length: (inc_end - inc_start).mag(),
+    };
+
+    let r_sum = r_ref + r_inc;
+    let depth_at = |point: m::Vec2| r_sum - axis.dot(point - ref_start);
+    let contact_at = |point: m::Vec2, depth: f64| Contact {
+        normal: pose_ref.rotation * axis,
+        offsets: [
+            point + (depth - r_inc) * *axis,
+            inc_wrt_ref.inversed() * (point - r_inc * *axis),
+        ],
+    };
+
+    match clip_edge(owning_edge, incident_edge) {
+        EdgeClipResult::Intersects => {
+            let depth = depth_at(incident_edge.start);
+            if depth <= 0.0 {
+                return ContactResult::Zero;
+            }
+            ContactResult::One(contact_at(incident_edge.start, depth))
+        }
+        EdgeClipResult::Passes { enters, exits } => {
+            let enter_point = incident_edge.start + (enters * *incident_edge.dir);
+            let exit_point = incident_edge.start + (exits * *incident_edge.dir);
+            let enter_depth = depth_at(enter_point);
+            let exit_depth = depth_at(exit_point);
+            match (enter_depth > 0.0, exit_depth > 0.0) {
+                // clipped down to a single point, don't duplicate it
+                (true, true) if exits - enters < POLYGON_FACE_TOLERANCE => {
+                    ContactResult::One(contact_at(enter_point, enter_depth))
+                }
+                (true, true) => ContactResult::Two(
+                    contact_at(enter_point, enter_depth),
+                    contact_at(exit_point, exit_depth),
+                ),
+                (true, false) => ContactResult::One(contact_at(enter_point, enter_depth)),
+                (false, true) => ContactResult::One(contact_at(exit_point, exit_depth)),
+                (false, false) => ContactResult::Zero,
+            }
+        }
+        EdgeClipResult::Misses => ContactResult::Zero,
+    }
+}
+
+//
+// EDGE <-> ANYTHING
+//
+
+fn edge_shape(
+    pose_edge: &m::Pose,
+    edge: &collider::EdgeSegment,
+    pose_other: &m::Pose,
+    coll_other: &Collider,
+) -> ContactResult {
+    let face_normal = edge.normal();
+    // edges are one-sided, ignore anything that's mostly behind.
+    // the middle of the shape's extent along the normal is used rather than its origin,
+    // which can be anywhere for polygons
+    let other_wrt_edge = pose_edge.inversed() * *pose_other;
+    let (min, max) = extent_along(&coll_other.shape, &other_wrt_edge, face_normal);
+    if (min + max) / 2.0 < face_normal.dot(edge.start) {
+        return ContactResult::Zero;
+    }
+
+    // collide as a two-sided segment first, then fix up normals
+    let segment = collider::Polygon::segment_between(edge.start, edge.end);
+    let contacts = match coll_other.shape {
+        ColliderShape::Circle { r } => polygon_circle(pose_edge, &segment, pose_other, r),
+        ColliderShape::Rect { hw, hh } => polygon_polygon(
+            pose_edge,
+            &segment,
+            0.0,
+            pose_other,
+            &collider::Polygon::from_rect(hw, hh),
+            0.0,
+        ),
+        ColliderShape::Capsule { hl, r } => polygon_capsule(pose_edge, &segment, pose_other, hl, r),
+        ColliderShape::Polygon(poly) => {
+            polygon_polygon(pose_edge, &segment, 0.0, pose_other, &poly, 0.0)
+        }
+        ColliderShape::Edge(_) => ContactResult::Zero,
+    };
+
+    // every contact in a result has the same normal, so one of them is enough to check
+    let first = match contacts.iter().next() {
+        Some(c) => *c,
+        None => return ContactResult::Zero,
+    };
+    let normal = pose_edge.rotation.reversed() * *first.normal;
+    if edge_contact_normal(edge, face_normal, normal, first.offsets[0]) == normal {
+        contacts
+    } else {
+        // the contact points were found along the replaced normal,
+        // so find them again against the face
+        edge_face_contacts(pose_edge, &segment, pose_other, coll_other)
+    }
+}
+
+/// Smallest and largest distance along a direction of the points of a shape at a pose.
+fn extent_along(shape: &ColliderShape, pose: &m::Pose, dir: m::Vec2) -> (f64, f64) {
+    let center = dir.dot(pose.translation);
+    let local_dir = pose.rotation.reversed() * dir;
+    let half_extent = match *shape {
+        ColliderShape::Circle { r } => r,
+        ColliderShape::Rect { hw, hh } => hw * local_dir.x.abs() + hh * local_dir.y.abs(),
+        ColliderShape::Capsule { hl, r } => hl * local_dir.x.abs() + r,
+        ColliderShape::Polygon(poly) => {
+            let dists = poly.vertices().iter().map(|v| local_dir.dot(*v));
+            let (min, max) = dists.fold((f64::MAX, f64::MIN), |(min, max), d| {
+                (min.min(d), max.max(d))
+            });
+            return (center + min, center + max);
+        }
+        // edges never collide with each other, see `intersection_check`
+        ColliderShape::Edge(_) => 0.0,
+    };
+    (center - half_extent, center + half_extent)
+}
+
+/// Contacts between the colliding face of an edge and another shape,
+/// pushing the shape out along the face normal.
+///
+/// The face is clipped to the segment's ends like the regular polygon collision is,
+/// leaving anything past them to the neighboring segments.
+fn edge_face_contacts(
+    pose_edge: &m::Pose,
+    segment: &collider::Polygon,
+    pose_other: &m::Pose,
+    coll_other: &Collider,
+) -> ContactResult {
+    // a segment polygon goes from start to end and back,
+    // the way back being the colliding face
+    const FACE: usize = 1;
+    let circle_contact = |r: f64| {
+        let verts = segment.vertices();
+        let normal = Unit::new_unchecked(segment.normals()[FACE]);
+        let other_wrt_edge = pose_edge.inversed() * *pose_other;
+        let center = other_wrt_edge.translation;
+        let dir = verts[0] - verts[1];
+        let along = (center - verts[1]).dot(dir);
+        let dist = normal.dot(center - verts[1]);
+        if dist >= r || along < 0.0 || along > dir.mag_sq() {
+            return ContactResult::Zero;
+        }
+        ContactResult::One(Contact {
+            normal: pose_edge.rotation * normal,
+            offsets: [
+                center - dist * *normal,
+                other_wrt_edge.rotation.reversed() * (-r * *normal),
+            ],
+        })
+    };
+    match coll_other.shape {
+        ColliderShape::Circle { r } => circle_contact(r),
+        ColliderShape::Capsule { hl, r } if hl <= 0.0 => circle_contact(r),
+        ColliderShape::Capsule { hl, r } => polygon_clip(
+            pose_edge,
+            segment,
+            0.0,
+            FACE,
+            pose_other,
+            &collider::Polygon::segment(hl),
+            r,
+        ),
+        ColliderShape::Rect { hw, hh } => polygon_clip(
+            pose_edge,
+            segment,
+            0.0,
+            FACE,
+            pose_other,
+            &collider::Polygon::from_rect(hw, hh),
+            0.0,
+        ),
+        ColliderShape::Polygon(poly) => {
+            polygon_clip(pose_edge, segment, 0.0, FACE, pose_other, &poly, 0.0)
+        }
+        ColliderShape::Edge(_) => ContactResult::Zero,
+    }
+}
+
+/// Correct a contact normal (in the edge's local space) so that things don't catch on the joints
+/// between segments of a chain or get pushed through the back of the edge.
+///
+/// Normals that only make sense for a corner sticking out are replaced with the face normal
+/// unless the joint with the neighboring segment actually is a corner sticking out.
+fn edge_contact_normal(
+    edge: &collider::EdgeSegment,
+    face_normal: m::Vec2,
+    normal: m::Vec2,
+    point_on_edge: m::Vec2,
+) -> m::Vec2 {
+    let normal_dot_face = normal.dot(face_normal);
+    if normal_dot_face < 0.0 {
+        return face_normal;
+    }
+    if 1.0 - normal_dot_face < POLYGON_FACE_TOLERANCE {
+        // already the face normal
+        return normal;
+    }
+
+    // not the face normal, so it's a contact with one of the endpoints.
+    // figure out which one and look at the neighboring segment there
+    let dir = edge.end - edge.start;
+    let at_start = (point_on_edge - edge.start).dot(dir) < 0.5 * dir.mag_sq();
+    let (neighbor_dir, is_convex) = match (at_start, edge.ghost_prev, edge.ghost_next) {
+        (true, Some(prev), _) => {
+            let neighbor_dir = edge.start - prev;
+            (neighbor_dir, neighbor_dir.wedge(dir).xy < 0.0)
+        }
+        (false, _, Some(next)) => {
+            let neighbor_dir = next - edge.end;
+            (neighbor_dir, dir.wedge(neighbor_dir).xy < 0.0)
+        }
+        // free end of a chain, corner collisions are real
+        _ => return normal,
+    };
+    if !is_convex {
+        // flat or concave joint, the neighbor's face is in the way of any corner contact
+        return face_normal;
+    }
+
+    // convex joint, allow normals between the two faces' normals
+    let neighbor_normal = m::left_normal(neighbor_dir).normalized();
+    let orientation = neighbor_normal.wedge(face_normal).xy.signum();
+    if neighbor_normal.wedge(normal).xy * orientation >= 0.0
+        && normal.wedge(face_normal).xy * orientation >= 0.0
+    {
+        normal
+    } else {
+        face_normal
+    }
+}
+
 //
 // EDGE CLIP
 //
@@ -994,4 +1523,170 @@ mod tests {
             _ => panic!("Intersected but shouldn't have"),
         }
     }
+
+    #[test]
+    fn polygon_resting_on_polygon() {
+        let ground = Collider::new_polygon(&[
+            m::Vec2::new(-2.0, -0.5),
+            m::Vec2::new(2.0, -0.5),
+            m::Vec2::new(2.0, 0.5),
+            m::Vec2::new(-2.0, 0.5),
+        ]);
+        let triangle = Collider::new_polygon(&[
+            m::Vec2::new(0.0, 1.0),
+            m::Vec2::new(-0.5, -0.5),
+            m::Vec2::new(0.5, -0.5),
+        ]);
+        let ground_pose = Pose::identity();
+        let tri_pose = Pose::new(m::Vec2::new(0.3, 0.99), m::Rotor2::identity());
+
+        match intersection_check(&ground_pose, &ground, &tri_pose, &triangle) {
+            ContactResult::Two(c1, c2) => {
+                for c in &[c1, c2] {
+                    assert!((c.normal.y - 1.0).abs() < 0.001);
+                    let depth =
+                        (ground_pose * c.offsets[0] - tri_pose * c.offsets[1]).dot(*c.normal);
+                    assert!((depth - 0.01).abs() < 0.001);
+                }
+            }
+            other => panic!("Expected two contacts, got {:?}", other),
+        }
+
+        let tri_pose_apart = Pose::new(m::Vec2::new(0.3, 1.01), m::Rotor2::identity());
+        assert!(matches!(
+            intersection_check(&ground_pose, &ground, &tri_pose_apart, &triangle),
+            ContactResult::Zero
+        ));
+    }
+
+    #[test]
+    fn circle_on_polygon_corner() {
+        let triangle = Collider::new_polygon(&[
+            m::Vec2::new(0.0, 1.0),
+            m::Vec2::new(-1.0, 0.0),
+            m::Vec2::new(1.0, 0.0),
+        ]);
+        let circle = Collider::new_circle(0.5);
+        let circle_pose = Pose::new(m::Vec2::new(0.0, 1.4), m::Rotor2::identity());
+
+        match intersection_check(&circle_pose, &circle, &Pose::identity(), &triangle) {
+            ContactResult::One(c) => {
+                // normal faces away from the circle, straight down into the tip
+                assert!((c.normal.y + 1.0).abs() < 0.001);
+                assert!((c.offsets[1] - m::Vec2::new(0.0, 1.0)).mag() < 0.001);
+            }
+            other => panic!("Expected one contact, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn box_sliding_over_flat_edge_joint() {
+        let points = [
+            m::Vec2::new(-2.0, 0.0),
+            m::Vec2::new(0.0, 0.0),
+            m::Vec2::new(2.0, 0.0),
+        ];
+        let mut left = Collider::new_edge(points[0], points[1]);
+        let mut right = Collider::new_edge(points[1], points[2]);
+        if let ColliderShape::Edge(e) = &mut left.shape {
+            e.ghost_next = Some(points[2]);
+        }
+        if let ColliderShape::Edge(e) = &mut right.shape {
+            e.ghost_prev = Some(points[0]);
+        }
+        let block = Collider::new_rect(1.0, 1.0);
+        // leading bottom corner has just passed the joint and is slightly penetrating
+        let block_pose = Pose::new(m::Vec2::new(-0.499, 0.49), m::Rotor2::identity());
+
+        for edge in &[left, right] {
+            for c in intersection_check(&Pose::identity(), edge, &block_pose, &block).iter() {
+                assert!(
+                    (c.normal.y - 1.0).abs() < 0.001,
+                    "Caught on a joint with normal {:?}",
+                    c.normal
+                );
+            }
+        }
+
+        // one-sided: nothing happens from below
+        let below_pose = Pose::new(m::Vec2::new(1.0, -0.49), m::Rotor2::identity());
+        assert!(matches!(
+            intersection_check(&Pose::identity(), &right, &below_pose, &block),
+            ContactResult::Zero
+        ));
+    }
+
+    /// When a contact normal is replaced with the face normal,
+    /// the contact points are found again along it instead of keeping the old ones.
+    #[test]
+    fn edge_contacts_follow_replaced_normal() {
+        // a crest where the chain turns downwards
+        let points = [
+            m::Vec2::new(-2.0, 0.0),
+            m::Vec2::new(0.0, 0.0),
+            m::Vec2::new(2.0, -0.6),
+        ];
+        let mut right = Collider::new_edge(points[1], points[2]);
+        if let ColliderShape::Edge(e) = &mut right.shape {
+            e.ghost_prev = Some(points[0]);
+        }
+        let face_normal = m::left_normal(points[2] - points[1]).normalized();
+        let block = Collider::new_rect(1.0, 1.0);
+        // sunk a bit into the flat side with the bottom corner just past the crest
+        let block_pose = Pose::new(m::Vec2::new(-0.48, 0.48), m::Rotor2::identity());
+
+        let contacts = intersection_check(&Pose::identity(), &right, &block_pose, &block);
+        assert!(contacts.iter().count() > 0);
+        for c in contacts.iter() {
+            assert!((*c.normal - face_normal).mag() < 0.001);
+            let on_block = block_pose * c.offsets[1];
+            let depth = (c.offsets[0] - on_block).dot(*c.normal);
+            assert!((on_block.y + 0.02).abs() < 0.001);
+            assert!(depth > 0.0 && depth < 0.025, "depth was {}", depth);
+        }
+    }
+
+    /// Whether something is behind an edge depends on where its shape is, not its origin.
+    #[test]
+    fn edge_one_sidedness_uses_whole_shape() {
+        let edge = Collider::new_edge(m::Vec2::new(-2.0, 0.0), m::Vec2::new(2.0, 0.0));
+        // origin a metre below the shape
+        let poly = Collider::new_polygon(&[
+            m::Vec2::new(-0.5, 1.0),
+            m::Vec2::new(0.5, 1.0),
+            m::Vec2::new(0.5, 2.0),
+            m::Vec2::new(-0.5, 2.0),
+        ]);
+        let mostly_in_front = Pose::new(m::Vec2::new(0.0, -1.01), m::Rotor2::identity());
+        assert!(matches!(
+            intersection_check(&Pose::identity(), &edge, &mostly_in_front, &poly),
+            ContactResult::Two(_, _)
+        ));
+        let mostly_behind = Pose::new(m::Vec2::new(0.0, -1.9), m::Rotor2::identity());
+        assert!(matches!(
+            intersection_check(&Pose::identity(), &edge, &mostly_behind, &poly),
+            ContactResult::Zero
+        ));
+    }
+
+    #[test]
+    fn capsule_lying_on_polygon() {
+        let ground = Collider::new_polygon(&[
+            m::Vec2::new(-2.0, -0.5),
+            m::Vec2::new(2.0, -0.5),
+            m::Vec2::new(2.0, 0.5),
+            m::Vec2::new(-2.0, 0.5),
+        ]);
+        let capsule = Collider::new_capsule(1.0, 0.25);
+        let cap_pose = Pose::new(m::Vec2::new(0.0, 0.74), m::Rotor2::identity());
+
+        match intersection_check(&cap_pose, &capsule, &Pose::identity(), &ground) {
+            ContactResult::Two(c1, c2) => {
+                for c in &[c1, c2] {
+                    assert!((c.normal.y + 1.0).abs() < 0.001);
+                }
+            }
+            other => panic!("Expected two contacts, got {:?}", other),
+        }
+    }
 }
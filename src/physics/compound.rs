@@ -0,0 +1,167 @@
+//! Bodies made out of several colliders.
+
+use crate::{
+    graph::{self, UnsafeNode},
+    math as m,
+    physics::{Body, Collider},
+};
+
+/// Attach several colliders to one body, giving it a compound shape.
+///
+/// Each collider is placed relative to the body by its [`offset`][Collider::offset].
+/// Use [`Body::new_dynamic_compound`] to compute mass and moment of inertia for the body.
+/// Every collider is its own node, so the spatial index, contact solving and events
+/// all tell exactly which one of them was involved.
+///
+/// A node can only have one edge to each layer, so only the first collider is connected to the
+/// body and its pose in both directions, and the rest point to them one-way.
+/// The colliders are also linked into a loop so that deleting the body deletes all of them.
+/// The returned nodes are in the same order as the colliders.
+/// Use [`body_colliders`] to find all of them from the body, and connect a
+/// [`Shape::from_collider`][crate::graphics::Shape::from_collider] to each of them to draw them.
+///
+/// # Panics
+/// Panics if `colliders` is empty or the body or pose node is dead.
+pub fn attach_compound(
+    body: graph::Node<Body>,
+    pose: graph::Node<m::Pose>,
+    colliders: &[Collider],
+    l_collider: &mut graph::Layer<Collider>,
+    graph: &mut graph::Graph,
+) -> Vec<graph::Node<Collider>> {
+    assert!(
+        !colliders.is_empty(),
+        "A compound needs at least one collider"
+    );
+    assert!(
+        body.check(graph).is_some() && pose.check(graph).is_some(),
+        "Tried to attach colliders to a dead body"
+    );
+
+    let nodes: Vec<graph::NodePosition> = colliders
+        .iter()
+        .map(|coll| l_collider.insert(*coll, graph).pos())
+        .collect();
+
+    graph.connect_unchecked(&body, &nodes[0]);
+    graph.connect_unchecked(&pose, &nodes[0]);
+    for child in &nodes[1..] {
+        graph.connect_oneway_unchecked(child, &body);
+        graph.connect_oneway_unchecked(child, &pose);
+    }
+
+    // link the colliders into a loop so they keep each other alive and get deleted together
+    if nodes.len() > 1 {
+        for (curr, next) in nodes.iter().zip(nodes.iter().cycle().skip(1)) {
+            graph.connect_oneway_unchecked(curr, next);
+        }
+    }
+
+    nodes
+        .into_iter()
+        .map(|pos| graph::NodeRef::as_node(&l_collider.get_unchecked(pos), graph))
+        .collect()
+}
+
+/// Iterate over every collider attached to a body,
+/// including all the colliders of a compound made with [`attach_compound`].
+///
+/// Bodies with a single collider just yield that one.
+pub fn body_colliders<'g>(
+    body: &impl graph::SafeNode<MarkerType = Body>,
+    graph: &'g graph::Graph,
+    l_collider: &'g graph::Layer<Collider>,
+) -> impl Iterator<Item = graph::NodeRef<'g, Collider>> + 'g {
+    let first = graph.get_neighbor(body, l_collider);
+    let first_pos = first.as_ref().map(|c| c.pos());
+    // compound colliders are linked into a loop, stop when we get back to the start
+    std::iter::successors(first, move |curr| {
+        graph
+            .get_neighbor(curr, l_collider)
+            .filter(|next| Some(next.pos()) != first_pos)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::tests::World;
+
+    /// A dumbbell of two squares with the body origin between them.
+    fn spawn_dumbbell(
+        world: &mut World,
+        position: m::Vec2,
+    ) -> (graph::Node<Body>, Vec<graph::Node<Collider>>) {
+        let colliders = [-1.0, 0.0, 1.0].map(|x| {
+            Collider::new_square(0.5)
+                .with_offset(m::Pose::new(m::Vec2::new(x, 0.0), Default::default()))
+        });
+        let body = world.l_body.insert(
+            Body::new_dynamic_compound(&colliders, 1.0),
+            &mut world.graph,
+        );
+        let body = graph::NodeRef::as_node(&body, &world.graph);
+        let pose = world
+            .l_pose
+            .insert(m::Pose::new(position, Default::default()), &mut world.graph);
+        let pose = graph::NodeRef::as_node(&pose, &world.graph);
+        world.graph.connect(
+            &body.check(&world.graph).unwrap(),
+            &pose.check(&world.graph).unwrap(),
+        );
+        let nodes = attach_compound(
+            body,
+            pose,
+            &colliders,
+            &mut world.l_collider,
+            &mut world.graph,
+        );
+        (body, nodes)
+    }
+
+    #[test]
+    fn body_colliders_walks_every_collider_once() {
+        let mut world = World::new();
+        let (dumbbell, parts) = spawn_dumbbell(&mut world, m::Vec2::zero());
+        let single = world.spawn_box(m::Vec2::new(3.0, 0.0), 0.0);
+        let colliders_of = |b: graph::Node<Body>| -> Vec<graph::Node<Collider>> {
+            let body = b.check(&world.graph).unwrap();
+            body_colliders(&body, &world.graph, &world.l_collider)
+                .map(|c| graph::NodeRef::as_node(&c, &world.graph))
+                .collect()
+        };
+        assert_eq!(colliders_of(dumbbell), parts);
+        assert_eq!(colliders_of(single), vec![world.collider(single)]);
+    }
+
+    #[test]
+    fn point_query_hits_every_part_of_a_compound() {
+        let mut world = World::new();
+        let (dumbbell, parts) = spawn_dumbbell(&mut world, m::Vec2::new(1.0, 1.0));
+        for (x, part) in [0.0, 1.0, 2.0].iter().zip(&parts) {
+            let (_, coll, body) = world
+                .physics
+                .query_point_body(
+                    &world.graph,
+                    &world.l_pose,
+                    &world.l_collider,
+                    &world.l_body,
+                    m::Vec2::new(*x, 1.1),
+                )
+                .expect("point should be inside the compound");
+            assert_eq!(graph::NodeRef::as_node(&body, &world.graph), dumbbell);
+            assert_eq!(graph::NodeRef::as_node(&coll, &world.graph), *part);
+        }
+        // between the squares
+        assert!(world
+            .physics
+            .query_point_body(
+                &world.graph,
+                &world.l_pose,
+                &world.l_collider,
+                &world.l_body,
+                m::Vec2::new(0.5, 1.0),
+            )
+            .is_none());
+    }
+}
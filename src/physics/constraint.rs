@@ -2,6 +2,7 @@
 
 use super::Body;
 use crate::{graph, math as m};
+use std::f64::consts::PI;
 
 /// A constraint restricts the relative motion of two bodies,
 /// or the motion of a single body in the world.
@@ -9,7 +10,7 @@ use crate::{graph, math as m};
 /// [`ConstraintBuilder`][self::ConstraintBuilder] is the preferred
 /// way to create these, but the fields are public to allow in-place editing
 /// for advanced users.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Constraint {
     /// The body that owns this constraint.
     pub owner: graph::Node<Body>,
@@ -22,27 +23,101 @@ pub struct Constraint {
     pub linear_damping: f64,
     /// Damping coefficient for angular velocity.
     pub angular_damping: f64,
-    /// Offsets from each body's center of mass (or world origin).
+    /// Offsets from each body's pose (or world origin).
     pub offsets: [m::Vec2; 2],
     /// Which directions to enforce the constraint in.
     pub limit: ConstraintLimit,
     /// Type of the constraint.
     pub ty: ConstraintType,
+    /// Force holding the origin points in place above which the constraint breaks
+    /// and is removed. `None` makes it unbreakable by force.
+    ///
+    /// Only what holds the joint together counts towards breaking it.
+    /// Joint motors and limits act in the direction the joint is free to move in,
+    /// so they aren't counted and a joint can't be broken by its own motor.
+    pub break_force: Option<f64>,
+    /// Torque holding the relative angle above which the constraint breaks
+    /// and is removed. `None` makes it unbreakable by torque.
+    ///
+    /// Motors and limits don't count here either (see [`break_force`][Self::break_force]),
+    /// so only prismatic and weld joints have torque.
+    pub break_torque: Option<f64>,
+    /// Magnitude of the force holding the origin points in place,
+    /// averaged over the last frame, in newtons. Updated by the physics system.
+    /// Motors and limits aren't counted.
+    pub force: f64,
+    /// Magnitude of the torque holding the relative angle,
+    /// averaged over the last frame, in N⋅m. Updated by the physics system.
+    /// Motors and limits aren't counted.
+    pub torque: f64,
 }
 
 /// Type-specific variables for constraints.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub enum ConstraintType {
     /// A distance constraint enforces a specific distance between two points.
     Distance {
         /// The desired distance.
         distance: f64,
     },
+    /// A revolute joint, or hinge, pins the origin points together
+    /// while letting the bodies rotate relative to each other.
+    Revolute {
+        /// The relative angle considered neutral for the purposes of limits.
+        /// `None` is replaced with the relative angle of the bodies
+        /// the first time the joint is simulated.
+        rest_angle: Option<f64>,
+        /// Limits for the relative angle, measured from `rest_angle`.
+        angle_limits: Option<JointLimits>,
+        /// A motor driving the relative angular velocity.
+        motor: Option<Motor>,
+    },
+    /// A prismatic joint, or slider, only allows the owner's origin point to move along
+    /// an axis going through the target's origin point, and locks relative rotation.
+    Prismatic {
+        /// Direction of the axis in the target's local space,
+        /// or in world space if there's no target.
+        axis: m::Unit<m::Vec2>,
+        /// The relative angle that the bodies are locked to.
+        rest_angle: f64,
+        /// Limits for the position of the owner's origin along the axis,
+        /// measured from the target's origin.
+        translation_limits: Option<JointLimits>,
+        /// A motor driving the relative velocity along the axis.
+        motor: Option<Motor>,
+    },
+    /// A weld joint pins the origin points together and locks relative rotation,
+    /// gluing the bodies together. Add compliance to make it springy.
+    Weld {
+        /// The relative angle that the bodies are locked to.
+        rest_angle: f64,
+    },
+}
+
+/// Minimum and maximum values for some degree of freedom of a joint.
+///
+/// Angles are in radians and must be within `-PI..PI`.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct JointLimits {
+    pub min: f64,
+    pub max: f64,
+}
+
+/// A motor drives a joint towards a target velocity with a limited force.
+///
+/// For rotating joints, velocity is in radians per second and force is torque in N⋅m.
+/// For sliding joints, velocity is in metres per second and force is in newtons.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Motor {
+    /// The velocity the motor tries to reach.
+    pub target_velocity: f64,
+    /// The maximum force the motor can apply.
+    pub max_force: f64,
 }
 
 /// Some constraints can be set to only work in one direction,
 /// to e.g. set a maximum distance while allowing shorter distances.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub enum ConstraintLimit {
     /// Always apply a correction to the constraint.
     Eq,
@@ -62,6 +137,10 @@ pub struct ConstraintBuilder {
     compliance: f64,
     linear_damping: f64,
     angular_damping: f64,
+    rest_angle: Option<f64>,
+    motor: Option<Motor>,
+    break_force: Option<f64>,
+    break_torque: Option<f64>,
 }
 
 impl ConstraintBuilder {
@@ -79,6 +158,10 @@ impl ConstraintBuilder {
             compliance: 0.0,
             linear_damping: 0.1,
             angular_damping: 0.0,
+            rest_angle: None,
+            motor: None,
+            break_force: None,
+            break_torque: None,
         }
     }
 
@@ -89,7 +172,7 @@ impl ConstraintBuilder {
     }
 
     /// Set the origin point of the constraint on the owning body
-    /// relative to the center of mass.
+    /// relative to the body's pose.
     ///
     /// This has no effect on angular-only constraints.
     pub fn with_origin(mut self, point: m::Vec2) -> Self {
@@ -98,7 +181,7 @@ impl ConstraintBuilder {
     }
 
     /// Set the origin point of the constraint on the target body
-    /// relative to the center of mass,
+    /// relative to the body's pose,
     /// or in the world if the target is None.
     ///
     /// This has no effect on angular-only constraints.
@@ -130,6 +213,45 @@ impl ConstraintBuilder {
         self
     }
 
+    /// Set the relative angle between the bodies (owner's rotation minus the target's)
+    /// that is considered neutral.
+    ///
+    /// This only has an effect on constraints with an angular part.
+    /// If it isn't set, revolute joints use the relative angle the bodies have
+    /// when the joint is first simulated, and other joints use zero.
+    pub fn with_rest_angle(mut self, angle: f64) -> Self {
+        self.rest_angle = Some(angle);
+        self
+    }
+
+    /// Add a motor to the constraint. See [`Motor`][self::Motor] for units.
+    ///
+    /// This only has an effect on joints that can have a motor.
+    pub fn with_motor(mut self, target_velocity: f64, max_force: f64) -> Self {
+        self.motor = Some(Motor {
+            target_velocity,
+            max_force,
+        });
+        self
+    }
+
+    /// Make the constraint break when its force exceeds the given value in newtons.
+    ///
+    /// Broken constraints are removed from the physics system and produce an
+    /// [`Event::ConstraintBroken`][crate::event::Event::ConstraintBroken].
+    pub fn with_break_force(mut self, force: f64) -> Self {
+        self.break_force = Some(force);
+        self
+    }
+
+    /// Make the constraint break when its torque exceeds the given value in N⋅m.
+    ///
+    /// Only constraints with an angular part have torque.
+    pub fn with_break_torque(mut self, torque: f64) -> Self {
+        self.break_torque = Some(torque);
+        self
+    }
+
     /// Set the limit for when to enforce the constraint.
     pub fn with_limit(mut self, limit: ConstraintLimit) -> Self {
         self.limit = limit;
@@ -147,6 +269,51 @@ impl ConstraintBuilder {
         self.build(ConstraintType::Distance { distance: 0.0 })
     }
 
+    /// Build a revolute joint that pins the origin points together
+    /// and optionally limits the relative angle of the bodies.
+    ///
+    /// # Panics
+    /// Panics if the angle limits aren't within `-PI..PI` or the minimum is greater than the maximum.
+    pub fn build_revolute(self, angle_limits: Option<JointLimits>) -> Constraint {
+        if let Some(limits) = angle_limits {
+            assert!(
+                -PI <= limits.min && limits.min <= limits.max && limits.max <= PI,
+                "Revolute joint limits must be within -PI..PI with min <= max, got {:?}",
+                limits
+            );
+        }
+        self.build(ConstraintType::Revolute {
+            rest_angle: self.rest_angle,
+            angle_limits,
+            motor: self.motor,
+        })
+    }
+
+    /// Build a prismatic joint that keeps the owner's origin point on an axis
+    /// through the target's origin point, optionally limiting how far along the axis it can go.
+    ///
+    /// The axis is given in the target's local space, or world space if there's no target.
+    pub fn build_prismatic(
+        self,
+        axis: m::Vec2,
+        translation_limits: Option<JointLimits>,
+    ) -> Constraint {
+        self.build(ConstraintType::Prismatic {
+            axis: m::Unit::new_normalize(axis),
+            rest_angle: self.rest_angle.unwrap_or(0.0),
+            translation_limits,
+            motor: self.motor,
+        })
+    }
+
+    /// Build a weld joint that holds the origin points together
+    /// and keeps the relative angle at the rest angle.
+    pub fn build_weld(self) -> Constraint {
+        self.build(ConstraintType::Weld {
+            rest_angle: self.rest_angle.unwrap_or(0.0),
+        })
+    }
+
     fn build(self, ty: ConstraintType) -> Constraint {
         Constraint {
             owner: self.owner,
@@ -157,6 +324,304 @@ impl ConstraintBuilder {
             offsets: self.offsets,
             limit: self.limit,
             ty,
+            break_force: self.break_force,
+            break_torque: self.break_torque,
+            force: 0.0,
+            torque: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{
+        tests::{angle, World},
+        ConstraintHandle, Velocity,
+    };
+
+    //
+    // joints
+    //
+
+    /// A box in a weightless world pinned to ground at its center.
+    fn pinned_box(
+        world: &mut World,
+        position: m::Vec2,
+        angle: f64,
+        builder: impl FnOnce(ConstraintBuilder) -> Constraint,
+    ) -> (graph::Node<Body>, ConstraintHandle) {
+        world.gravity = m::Vec2::zero();
+        let b = world.spawn_box(position, angle);
+        let handle = world.physics.add_constraint(builder(
+            ConstraintBuilder::new(b).with_target_origin(position),
+        ));
+        (b, handle)
+    }
+
+    #[test]
+    fn revolute_rest_angle_defaults_to_initial_angle() {
+        let mut world = World::new();
+        let limits = JointLimits {
+            min: -0.1,
+            max: 0.1,
+        };
+        let (b, handle) = pinned_box(&mut world, m::Vec2::zero(), 1.0, |cb| {
+            cb.build_revolute(Some(limits))
+        });
+        world.tick();
+        match world.physics.get_constraint(handle).unwrap().ty {
+            ConstraintType::Revolute { rest_angle, .. } => {
+                assert!((rest_angle.unwrap() - 1.0).abs() < 1e-9)
+            }
+            _ => unreachable!(),
+        }
+        assert!((angle(world.pose(b)) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn revolute_limits_stop_rotation() {
+        let mut world = World::new();
+        let limits = JointLimits {
+            min: -0.5,
+            max: 0.5,
+        };
+        let (b, _) = pinned_box(&mut world, m::Vec2::zero(), 0.0, |cb| {
+            cb.build_revolute(Some(limits))
+        });
+        world.body_mut(b).velocity.angular = 5.0;
+        let mut max_angle: f64 = 0.0;
+        for _ in 0..60 {
+            world.tick();
+            let a = angle(world.pose(b));
+            assert!((-0.51..0.51).contains(&a), "angle {} went past limits", a);
+            max_angle = max_angle.max(a);
+        }
+        assert!(max_angle > 0.45);
+        assert!(world.pose(b).translation.mag() < 1e-3);
+    }
+
+    #[test]
+    fn revolute_motor_drives_to_target_velocity() {
+        let mut world = World::new();
+        let (strong, _) = pinned_box(&mut world, m::Vec2::new(-2.0, 0.0), 0.0, |cb| {
+            cb.with_motor(2.0, 100.0).build_revolute(None)
+        });
+        let (weak, _) = pinned_box(&mut world, m::Vec2::new(2.0, 0.0), 0.0, |cb| {
+            cb.with_motor(2.0, 0.001).build_revolute(None)
+        });
+        for _ in 0..60 {
+            world.tick();
+        }
+        assert!((world.body(strong).velocity.angular - 2.0).abs() < 0.01);
+        assert!(world.body(weak).velocity.angular < 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "within -PI..PI")]
+    fn revolute_limits_outside_half_turn_are_rejected() {
+        let mut world = World::new();
+        pinned_box(&mut world, m::Vec2::zero(), 0.0, |cb| {
+            cb.build_revolute(Some(JointLimits {
+                min: -4.0,
+                max: 0.0,
+            }))
+        });
+    }
+
+    #[test]
+    fn prismatic_keeps_body_on_axis() {
+        let mut world = World::new();
+        let limits = JointLimits {
+            min: -1.0,
+            max: 1.0,
+        };
+        let (b, _) = pinned_box(&mut world, m::Vec2::zero(), 0.0, |cb| {
+            cb.build_prismatic(m::Vec2::new(1.0, 1.0), Some(limits))
+        });
+        world.body_mut(b).velocity = Velocity {
+            linear: m::Vec2::new(3.0, 0.0),
+            angular: 2.0,
+        };
+        for _ in 0..60 {
+            world.tick();
+            let pose = world.pose(b);
+            let along = pose.translation.dot(m::Vec2::new(1.0, 1.0).normalized());
+            let across = pose
+                .translation
+                .wedge(m::Vec2::new(1.0, 1.0).normalized())
+                .xy;
+            assert!(across.abs() < 1e-3, "drifted {} off the axis", across);
+            assert!(
+                along < 1.01,
+                "went {} along the axis, past the limit",
+                along
+            );
+            assert!(angle(pose).abs() < 1e-3);
+        }
+        // it slid up to the limit
+        assert!(world.pose(b).translation.mag() > 0.95);
+    }
+
+    #[test]
+    fn weld_holds_relative_pose() {
+        let mut world = World::new();
+        world.spawn_ground();
+        let base = world.spawn_box(m::Vec2::new(0.0, 1.0), 0.0);
+        let arm = world.spawn_box(m::Vec2::new(0.5, 1.0), 0.0);
+        world.physics.add_constraint(
+            ConstraintBuilder::new(arm)
+                .with_target(base)
+                .with_origin(m::Vec2::new(-0.25, 0.0))
+                .with_target_origin(m::Vec2::new(0.25, 0.0))
+                .build_weld(),
+        );
+        world.body_mut(arm).velocity.angular = 5.0;
+        for _ in 0..90 {
+            world.tick();
+            let relative = world.pose(base).inversed() * world.pose(arm);
+            assert!(
+                (relative.translation - m::Vec2::new(0.5, 0.0)).mag() < 1e-2,
+                "arm moved to {:?} relative to base",
+                relative.translation
+            );
+            assert!(angle(relative).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn constraint_offsets_are_relative_to_pose() {
+        let mut world = World::new();
+        let (b, _) = pinned_box(&mut world, m::Vec2::zero(), 0.0, |cb| cb.build_attachment());
+        world.gravity = m::Vec2::new(0.0, -9.81);
+        world.body_mut(b).center_of_mass = m::Vec2::new(0.2, 0.0);
+        for _ in 0..60 {
+            world.tick();
+        }
+        let pose = world.pose(b);
+        // the pose origin stays pinned while the offset center of mass swings down
+        assert!(pose.translation.mag() < 1e-3);
+        assert!(angle(pose) < -0.5);
+    }
+
+    //
+    // breaking constraints
+    //
+
+    /// A box welded to ground by its left edge, so the weld holds up both
+    /// its weight and the torque of its weight around the edge.
+    fn cantilever(
+        world: &mut World,
+        position: m::Vec2,
+        customize: impl FnOnce(ConstraintBuilder) -> ConstraintBuilder,
+    ) -> ConstraintHandle {
+        let b = world.spawn_box(position, 0.0);
+        let builder = ConstraintBuilder::new(b)
+            .with_origin(m::Vec2::new(-0.25, 0.0))
+            .with_target_origin(position + m::Vec2::new(-0.25, 0.0));
+        world
+            .physics
+            .add_constraint(customize(builder).build_weld())
+    }
+
+    #[test]
+    fn constraint_force_and_torque_are_measured_separately() {
+        let mut world = World::new();
+        let handle = cantilever(&mut world, m::Vec2::zero(), |cb| cb);
+        for _ in 0..30 {
+            world.tick();
+        }
+        // box is 0.5 x 0.5 with density 1
+        let weight = 0.25 * 9.81;
+        let constraint = world.physics.get_constraint(handle).unwrap();
+        assert!(
+            (constraint.force - weight).abs() < 0.05 * weight,
+            "force {} should match weight {}",
+            constraint.force,
+            weight
+        );
+        assert!(
+            (constraint.torque - 0.25 * weight).abs() < 0.05 * 0.25 * weight,
+            "torque {} should match torque of weight {}",
+            constraint.torque,
+            0.25 * weight
+        );
+    }
+
+    #[test]
+    fn constraints_break_on_force_or_torque() {
+        let mut world = World::new();
+        let holds = cantilever(&mut world, m::Vec2::new(-2.0, 0.0), |cb| {
+            cb.with_break_force(3.0).with_break_torque(1.0)
+        });
+        let breaks_from_force =
+            cantilever(&mut world, m::Vec2::zero(), |cb| cb.with_break_force(2.0));
+        let breaks_from_torque = cantilever(&mut world, m::Vec2::new(2.0, 0.0), |cb| {
+            cb.with_break_torque(0.5)
+        });
+        for _ in 0..30 {
+            world.tick();
+        }
+        assert!(world.physics.get_constraint(holds).is_some());
+        assert!(world.physics.get_constraint(breaks_from_force).is_none());
+        assert!(world.physics.get_constraint(breaks_from_torque).is_none());
+    }
+
+    /// Pushing a revolute joint against its limit with a motor doesn't strain the joint.
+    #[test]
+    fn revolute_motor_and_limits_dont_count_as_torque() {
+        let mut world = World::new();
+        let limits = JointLimits {
+            min: -0.5,
+            max: 0.5,
+        };
+        let (_, handle) = pinned_box(&mut world, m::Vec2::zero(), 0.0, |cb| {
+            cb.with_motor(5.0, 100.0)
+                .with_break_force(1.0)
+                .with_break_torque(0.1)
+                .build_revolute(Some(limits))
+        });
+        for _ in 0..30 {
+            world.tick();
+        }
+        let constraint = world.physics.get_constraint(handle).unwrap();
+        assert_eq!(constraint.torque, 0.0);
+    }
+
+    /// Pushing a prismatic joint against its limit with a motor doesn't strain the joint.
+    #[test]
+    fn prismatic_motor_and_limits_dont_count_as_force() {
+        let mut world = World::new();
+        let limits = JointLimits {
+            min: -0.5,
+            max: 0.5,
+        };
+        let (_, handle) = pinned_box(&mut world, m::Vec2::zero(), 0.0, |cb| {
+            cb.with_motor(5.0, 100.0)
+                .with_break_force(1.0)
+                .build_prismatic(m::Vec2::unit_x(), Some(limits))
+        });
+        for _ in 0..30 {
+            world.tick();
+        }
+        assert!(world.physics.get_constraint(handle).is_some());
+    }
+
+    #[test]
+    fn removed_constraints_stop_acting() {
+        let mut world = World::new();
+        let removed = cantilever(&mut world, m::Vec2::new(-2.0, 0.0), |cb| cb);
+        let kept = cantilever(&mut world, m::Vec2::zero(), |cb| cb);
+        let removed_box = world.physics.get_constraint(removed).unwrap().owner;
+        assert!(world.physics.remove_constraint(removed).is_some());
+        assert!(world.physics.remove_constraint(removed).is_none());
+        let added = cantilever(&mut world, m::Vec2::new(2.0, 0.0), |cb| cb);
+        for _ in 0..30 {
+            world.tick();
         }
+        assert!(world.pose(removed_box).translation.y < -1.0);
+        assert!(world.physics.get_constraint(kept).is_some());
+        assert!(world.physics.get_constraint(added).is_some());
+        assert_eq!(world.physics.constraint_order, vec![kept, added]);
     }
 }
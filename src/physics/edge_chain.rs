@@ -0,0 +1,124 @@
+//! Chains of one-sided edges for building static terrain.
+
+use crate::{
+    graph::{self, UnsafeNode},
+    math as m,
+    physics::{
+        collision::{ColliderShape, EdgeSegment},
+        Collider, Material,
+    },
+};
+
+/// Spawn a chain of static one-sided edge colliders through the given points.
+///
+/// Every segment is its own collider, so each one is inserted into the spatial index separately,
+/// and neighboring points are stored in each segment as ghost vertices so that
+/// things sliding along the chain move smoothly across the joints.
+/// If `closed` is true, the last point is also connected to the first one.
+///
+/// The colliding side of each segment is on its left, so a chain drawn from left to right is
+/// solid from above, and a closed chain wound clockwise is solid from the outside.
+/// Points are in world space.
+///
+/// The segments are linked together in the graph such that deleting any of them deletes the
+/// whole chain. The returned nodes are in the same order as the points.
+///
+/// # Panics
+/// Panics if there are fewer than two points or two consecutive points are the same.
+pub fn spawn_edge_chain(
+    points: &[m::Vec2],
+    closed: bool,
+    material: Material,
+    l_collider: &mut graph::Layer<Collider>,
+    graph: &mut graph::Graph,
+) -> Vec<graph::Node<Collider>> {
+    assert!(points.len() >= 2, "An edge chain needs at least two points");
+
+    let point_count = points.len();
+    let segment_count = if closed { point_count } else { point_count - 1 };
+    let point_at = |idx: usize| points[idx % point_count];
+
+    let mut nodes: Vec<graph::NodePosition> = Vec::with_capacity(segment_count);
+    for seg_idx in 0..segment_count {
+        let mut coll =
+            Collider::new_edge(point_at(seg_idx), point_at(seg_idx + 1)).with_material(material);
+        if let ColliderShape::Edge(EdgeSegment {
+            ghost_prev,
+            ghost_next,
+            ..
+        }) = &mut coll.shape
+        {
+            *ghost_prev = if closed || seg_idx > 0 {
+                Some(point_at(seg_idx + point_count - 1))
+            } else {
+                None
+            };
+            *ghost_next = if closed || seg_idx + 2 < point_count {
+                Some(point_at(seg_idx + 2))
+            } else {
+                None
+            };
+        }
+        nodes.push(l_collider.insert(coll, graph).pos());
+    }
+
+    // link the segments into a loop so they keep each other alive and get deleted together
+    for (curr, next) in nodes.iter().zip(nodes.iter().cycle().skip(1)) {
+        graph.connect_oneway_unchecked(curr, next);
+    }
+
+    nodes
+        .into_iter()
+        .map(|pos| graph::NodeRef::as_node(&l_collider.get_unchecked(pos), graph))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{tests::World, Body, Velocity};
+
+    /// A frictionless box sliding along a flat chain crosses the joints
+    /// without catching on them or being bounced up.
+    #[test]
+    fn box_slides_across_joints_smoothly() {
+        let mut world = World::new();
+        let points = [
+            m::Vec2::new(-4.0, 0.0),
+            m::Vec2::new(-1.0, 0.0),
+            m::Vec2::new(0.0, 0.0),
+            m::Vec2::new(1.0, 0.0),
+            m::Vec2::new(4.0, 0.0),
+        ];
+        let ice = Material {
+            static_friction_coef: None,
+            dynamic_friction_coef: None,
+            ..Default::default()
+        };
+        spawn_edge_chain(&points, false, ice, &mut world.l_collider, &mut world.graph);
+        let coll = Collider::new_square(0.5).with_material(ice);
+        let b = world.spawn_body(
+            Body::new_dynamic(&coll, 1.0).with_velocity(Velocity {
+                linear: m::Vec2::new(3.0, 0.0),
+                angular: 0.0,
+            }),
+            coll,
+            m::Pose::new(m::Vec2::new(-2.5, 0.25), Default::default()),
+        );
+
+        for _ in 0..90 {
+            world.tick();
+            let vel = world.body(b).velocity;
+            let y = world.pose(b).translation.y;
+            assert!(
+                (vel.linear.x - 3.0).abs() < 0.01
+                    && vel.linear.y.abs() < 0.01
+                    && vel.angular.abs() < 0.01,
+                "box hit a joint with velocity {:?}",
+                vel
+            );
+            assert!((y - 0.25).abs() < 0.001, "box is at height {}", y);
+        }
+        assert!(world.pose(b).translation.x > 1.5);
+    }
+}
@@ -0,0 +1,322 @@
+//! Geometry for computing buoyancy in fluid volumes.
+
+use super::collision::ColliderShape;
+use super::Collider;
+use crate::math as m;
+
+/// Number of vertices used to approximate each rounded end of a capsule.
+const CAPSULE_CAP_POINTS: usize = 8;
+/// Number of vertices used to approximate a circle when it can't be computed exactly.
+const CIRCLE_POINTS: usize = 16;
+/// Most vertices any shape is approximated with.
+const MAX_OUTLINE_POINTS: usize = 2 * (CAPSULE_CAP_POINTS + 1);
+/// Clipping adds at most one vertex per clipping plane,
+/// and a shape is clipped by at most the edges of another one and the surface.
+const MAX_CLIPPED_POINTS: usize = 2 * MAX_OUTLINE_POINTS + 1;
+
+/// Height of the surface of a fluid, i.e. the highest point of its collider along `up`.
+fn surface_level(coll: &Collider, pose: &m::Pose, up: m::Vec2) -> f64 {
+    let highest = |points: &[m::Vec2]| {
+        points
+            .iter()
+            .map(|p| (*pose * *p).dot(up))
+            .fold(f64::MIN, f64::max)
+    };
+    match coll.shape {
+        ColliderShape::Circle { r } => pose.translation.dot(up) + r,
+        ColliderShape::Rect { hw, hh } => highest(&[
+            m::Vec2::new(-hw, -hh),
+            m::Vec2::new(hw, -hh),
+            m::Vec2::new(hw, hh),
+            m::Vec2::new(-hw, hh),
+        ]),
+        ColliderShape::Capsule { hl, r } => {
+            highest(&[m::Vec2::new(-hl, 0.0), m::Vec2::new(hl, 0.0)]) + r
+        }
+        ColliderShape::Polygon(poly) => highest(poly.vertices()),
+        ColliderShape::Edge(edge) => highest(&[edge.start, edge.end]),
+    }
+}
+
+/// Area and world-space centroid of the part of a collider inside a fluid collider
+/// and below its surface along `up`, or `None` if none of it is.
+///
+/// Circles cut only by the surface are computed exactly,
+/// other round shapes are approximated with polygons.
+pub(super) fn submerged_part(
+    coll: &Collider,
+    pose: &m::Pose,
+    fluid_coll: &Collider,
+    fluid_pose: &m::Pose,
+    up: m::Vec2,
+) -> Option<(f64, m::Vec2)> {
+    let fluid = ClipPolygon::outline(fluid_coll, fluid_pose)?;
+    let level = surface_level(fluid_coll, fluid_pose, up);
+
+    if let ColliderShape::Circle { r } = coll.shape {
+        // edges of the fluid facing up are part of the surface
+        let only_cut_by_surface = fluid.planes().all(|(normal, dist)| {
+            normal.dot(up) > 1.0 - 1e-9 || pose.translation.dot(normal) + r <= dist
+        });
+        if only_cut_by_surface {
+            return submerged_circle(r, pose, up, level);
+        }
+    }
+
+    let mut part = ClipPolygon::outline(coll, pose)?;
+    for (normal, dist) in fluid.planes() {
+        part = part.clipped(normal, dist);
+    }
+    part.clipped(up, level).area_and_centroid()
+}
+
+/// The circular segment of a circle cut off by the surface.
+fn submerged_circle(r: f64, pose: &m::Pose, up: m::Vec2, level: f64) -> Option<(f64, m::Vec2)> {
+    // d is the signed distance from the surface down to the center
+    let d = (pose.translation.dot(up) - level).clamp(-r, r);
+    let half_chord_sq = r * r - d * d;
+    let area = r * r * (d / r).acos() - d * half_chord_sq.sqrt();
+    if area <= 0.0 {
+        return None;
+    }
+    // distance of the segment's centroid from the center of the circle
+    let dist = 2.0 * half_chord_sq.powf(1.5) / (3.0 * area);
+    Some((area, pose.translation - up * dist))
+}
+
+/// A counterclockwise convex polygon in world space,
+/// stored in a fixed-size buffer to avoid allocating in the middle of a tick.
+#[derive(Clone, Copy)]
+struct ClipPolygon {
+    verts: [m::Vec2; MAX_CLIPPED_POINTS],
+    len: usize,
+}
+
+impl ClipPolygon {
+    fn new() -> Self {
+        Self {
+            verts: [m::Vec2::zero(); MAX_CLIPPED_POINTS],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, v: m::Vec2) {
+        self.verts[self.len] = v;
+        self.len += 1;
+    }
+
+    fn vertices(&self) -> &[m::Vec2] {
+        &self.verts[..self.len]
+    }
+
+    /// The shape of a collider as a polygon, or `None` for edges which have no area.
+    fn outline(coll: &Collider, pose: &m::Pose) -> Option<Self> {
+        let mut poly = Self::new();
+        match coll.shape {
+            ColliderShape::Circle { r } => {
+                let angle_incr = std::f64::consts::TAU / CIRCLE_POINTS as f64;
+                for i in 0..CIRCLE_POINTS {
+                    let angle = angle_incr * i as f64;
+                    poly.push(*pose * (r * m::Vec2::new(angle.cos(), angle.sin())));
+                }
+            }
+            ColliderShape::Capsule { hl, r } => {
+                let angle_incr = std::f64::consts::PI / CAPSULE_CAP_POINTS as f64;
+                let cap_point = |center: f64, angle: f64| {
+                    m::Vec2::new(center + r * angle.cos(), r * angle.sin())
+                };
+                for i in 0..=CAPSULE_CAP_POINTS {
+                    let angle = -std::f64::consts::FRAC_PI_2 + angle_incr * i as f64;
+                    poly.push(*pose * cap_point(hl, angle));
+                }
+                for i in 0..=CAPSULE_CAP_POINTS {
+                    let angle = std::f64::consts::FRAC_PI_2 + angle_incr * i as f64;
+                    poly.push(*pose * cap_point(-hl, angle));
+                }
+            }
+            ColliderShape::Rect { hw, hh } => {
+                for v in &[
+                    m::Vec2::new(-hw, -hh),
+                    m::Vec2::new(hw, -hh),
+                    m::Vec2::new(hw, hh),
+                    m::Vec2::new(-hw, hh),
+                ] {
+                    poly.push(*pose * *v);
+                }
+            }
+            ColliderShape::Polygon(p) => {
+                for v in p.vertices() {
+                    poly.push(*pose * *v);
+                }
+            }
+            ColliderShape::Edge(_) => return None,
+        }
+        Some(poly)
+    }
+
+    /// Edges of the polygon as half-planes of points `p` with `p.dot(normal) <= dist`.
+    fn planes(&self) -> impl Iterator<Item = (m::Vec2, f64)> + '_ {
+        let verts = self.vertices();
+        verts.iter().enumerate().map(move |(i, &v)| {
+            let edge = verts[(i + 1) % verts.len()] - v;
+            let normal = m::Vec2::new(edge.y, -edge.x).normalized();
+            (normal, v.dot(normal))
+        })
+    }
+
+    /// Sutherland-Hodgman against a single half-plane of points `p` with `p.dot(normal) <= dist`.
+    fn clipped(&self, normal: m::Vec2, dist: f64) -> Self {
+        let mut clipped = Self::new();
+        let verts = self.vertices();
+        let depth = |p: m::Vec2| dist - p.dot(normal);
+        for (i, &curr) in verts.iter().enumerate() {
+            let next = verts[(i + 1) % verts.len()];
+            let (curr_depth, next_depth) = (depth(curr), depth(next));
+            if curr_depth >= 0.0 {
+                clipped.push(curr);
+            }
+            if (curr_depth >= 0.0) != (next_depth >= 0.0) {
+                let t = curr_depth / (curr_depth - next_depth);
+                clipped.push(curr + (next - curr) * t);
+            }
+        }
+        clipped
+    }
+
+    fn area_and_centroid(&self) -> Option<(f64, m::Vec2)> {
+        let verts = self.vertices();
+        if verts.len() < 3 {
+            return None;
+        }
+        // triangle fan from the first vertex
+        let origin = verts[0];
+        let (doubled_area, weighted_centroids) = verts[1..].windows(2).fold(
+            (0.0, m::Vec2::zero()),
+            |(doubled_area, weighted_centroids), edge| {
+                let cross = (edge[0] - origin).wedge(edge[1] - origin).xy;
+                (
+                    doubled_area + cross,
+                    weighted_centroids + (origin + edge[0] + edge[1]) * cross,
+                )
+            },
+        );
+        if doubled_area <= 0.0 {
+            return None;
+        }
+        Some((
+            doubled_area / 2.0,
+            weighted_centroids / (3.0 * doubled_area),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph,
+        physics::{tests::World, Body, Fluid},
+    };
+
+    fn at(x: f64, y: f64) -> m::Pose {
+        m::Pose::new(m::Vec2::new(x, y), Default::default())
+    }
+
+    /// A 4 by 2 pool with its surface at y = 0.
+    fn pool() -> (Collider, m::Pose) {
+        (Collider::new_rect(4.0, 2.0), at(0.0, -1.0))
+    }
+
+    fn submerged(coll: &Collider, pose: &m::Pose) -> Option<(f64, m::Vec2)> {
+        let (fluid, fluid_pose) = pool();
+        submerged_part(coll, pose, &fluid, &fluid_pose, m::Vec2::unit_y())
+    }
+
+    #[test]
+    fn only_the_part_below_the_surface_is_submerged() {
+        let (area, centroid) = submerged(&Collider::new_square(1.0), &at(0.0, 0.0)).unwrap();
+        assert!((area - 0.5).abs() < 1e-9);
+        assert!((centroid - m::Vec2::new(0.0, -0.25)).mag() < 1e-9);
+        assert!(submerged(&Collider::new_square(1.0), &at(0.0, 0.6)).is_none());
+    }
+
+    #[test]
+    fn only_the_part_inside_the_fluid_is_submerged() {
+        // straddling the side of the pool
+        let (area, centroid) = submerged(&Collider::new_square(1.0), &at(2.0, -1.0)).unwrap();
+        assert!((area - 0.5).abs() < 1e-9);
+        assert!((centroid - m::Vec2::new(1.75, -1.0)).mag() < 1e-9);
+        // below the surface but outside the pool
+        assert!(submerged(&Collider::new_square(1.0), &at(3.0, -1.0)).is_none());
+        // poking out of the bottom, which isn't part of the surface
+        let (area, _) = submerged(&Collider::new_square(1.0), &at(0.0, -2.0)).unwrap();
+        assert!((area - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn circles_are_exact_unless_cut_by_the_sides() {
+        let circle = Collider::new_circle(0.5);
+        let (area, centroid) = submerged(&circle, &at(0.0, 0.0)).unwrap();
+        assert!((area - std::f64::consts::PI * 0.125).abs() < 1e-9);
+        assert!((centroid.y - -2.0 / (3.0 * std::f64::consts::PI)).abs() < 1e-9);
+        // half of it sticking out of the side is approximated with a polygon
+        let (area, centroid) = submerged(&circle, &at(2.0, -1.0)).unwrap();
+        assert!((area - std::f64::consts::PI * 0.125).abs() < 0.02);
+        assert!(centroid.x < 2.0);
+    }
+
+    #[test]
+    fn floating_depth_depends_on_density_and_not_gravity_scale() {
+        let mut world = World::new();
+        // a pool with its surface at y = 0
+        world.spawn_static(
+            Collider::new_rect(8.0, 4.0).with_fluid(Fluid::default()),
+            m::Pose::new(m::Vec2::new(0.0, -2.0), Default::default()),
+        );
+        let coll = Collider::new_square(0.5);
+        let boxes: Vec<(f64, graph::Node<Body>)> = [(1.0, -2.0), (0.5, 2.0)]
+            .iter()
+            .map(|&(gravity_scale, x)| {
+                let body = world.spawn_body(
+                    Body::new_dynamic(&coll, 0.5).with_gravity_scale(gravity_scale),
+                    coll,
+                    m::Pose::new(m::Vec2::new(x, 0.0), Default::default()),
+                );
+                (gravity_scale, body)
+            })
+            .collect();
+        for _ in 0..600 {
+            world.tick();
+        }
+        for (gravity_scale, body) in boxes {
+            // the displaced fluid weighs as much as the body with its gravity scale
+            let submerged_height = 0.5 * 0.5 * gravity_scale;
+            let expected_y = 0.25 - submerged_height;
+            assert!((world.pose(body).translation.y - expected_y).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn bodies_beside_a_fluid_dont_float() {
+        let mut world = World::new();
+        world.spawn_ground();
+        world.spawn_static(
+            Collider::new_rect(2.0, 2.0).with_fluid(Fluid {
+                density: 10.0,
+                ..Default::default()
+            }),
+            m::Pose::new(m::Vec2::new(-2.0, 0.5), Default::default()),
+        );
+        // the bounding boxes overlap, but only a sliver of the circle is in the fluid
+        let coll = Collider::new_circle(0.25);
+        let b = world.spawn_body(
+            Body::new_dynamic(&coll, 1.0),
+            coll,
+            m::Pose::new(m::Vec2::new(-0.8, 1.0), Default::default()),
+        );
+        for _ in 0..60 {
+            world.tick();
+        }
+        assert!(world.pose(b).translation.y < -0.2);
+    }
+}
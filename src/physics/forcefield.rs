@@ -2,8 +2,20 @@ use crate::math as m;
 
 /// A (possibly) position-dependent force that is typically
 /// fed to a physics solver and applied to all rigid bodies each frame.
+///
+/// A field has two parts: an acceleration that is the same for every body,
+/// like gravity, and a force that can depend on the mass and velocity of the body,
+/// like drag. Implement whichever makes sense, the force is zero by default.
 pub trait ForceField {
+    /// Acceleration at the given position, in m/s².
+    ///
+    /// This is scaled by each body's [`gravity_scale`][super::Body::gravity_scale].
     fn value_at(&self, position: m::Vec2) -> m::Vec2;
+
+    /// Force in newtons on a body at the given position moving at the given velocity.
+    fn force_at(&self, _position: m::Vec2, _velocity: m::Vec2, _mass: f64) -> m::Vec2 {
+        m::Vec2::zero()
+    }
 }
 
 pub struct NoneField;
@@ -19,6 +31,10 @@ impl<F1: ForceField, F2: ForceField> ForceField for Sum<F1, F2> {
     fn value_at(&self, pos: m::Vec2) -> m::Vec2 {
         self.0.value_at(pos) + self.1.value_at(pos)
     }
+
+    fn force_at(&self, pos: m::Vec2, vel: m::Vec2, mass: f64) -> m::Vec2 {
+        self.0.force_at(pos, vel, mass) + self.1.force_at(pos, vel, mass)
+    }
 }
 
 /// Constant gravity field over all of space.
@@ -48,3 +64,36 @@ impl ForceField for PointGravity {
         strength * dist.normalized()
     }
 }
+
+/// Air resistance slowing bodies down with a force proportional to the square of their speed.
+///
+/// Unlike damping, this has less effect on heavier bodies.
+pub struct Drag(pub f64);
+impl ForceField for Drag {
+    fn value_at(&self, _pos: m::Vec2) -> m::Vec2 {
+        m::Vec2::zero()
+    }
+
+    fn force_at(&self, _pos: m::Vec2, vel: m::Vec2, _mass: f64) -> m::Vec2 {
+        -self.0 * vel.mag() * vel
+    }
+}
+
+/// Wind blowing at a constant velocity,
+/// pushing bodies with drag relative to the moving air.
+pub struct Wind {
+    /// Velocity of the air.
+    pub velocity: m::Vec2,
+    /// Drag coefficient, see [`Drag`][self::Drag].
+    pub drag: f64,
+}
+impl ForceField for Wind {
+    fn value_at(&self, _pos: m::Vec2) -> m::Vec2 {
+        m::Vec2::zero()
+    }
+
+    fn force_at(&self, _pos: m::Vec2, vel: m::Vec2, _mass: f64) -> m::Vec2 {
+        let relative = self.velocity - vel;
+        self.drag * relative.mag() * relative
+    }
+}
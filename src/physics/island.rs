@@ -0,0 +1,193 @@
+//! Grouping bodies into islands of things that touch or are constrained together,
+//! used to put resting groups of bodies to sleep as a unit.
+
+/// A union-find structure over body indices.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Islands {
+    parents: Vec<usize>,
+}
+
+impl Islands {
+    /// Put every body in its own island.
+    pub fn reset(&mut self, body_count: usize) {
+        self.parents.clear();
+        self.parents.extend(0..body_count);
+    }
+
+    /// Get the index of the body that represents the island the given body is in.
+    pub fn root(&mut self, mut body: usize) -> usize {
+        while self.parents[body] != body {
+            // path halving to keep the trees flat
+            self.parents[body] = self.parents[self.parents[body]];
+            body = self.parents[body];
+        }
+        body
+    }
+
+    /// Merge the islands of two bodies.
+    pub fn join(&mut self, body1: usize, body2: usize) {
+        let root1 = self.root(body1);
+        let root2 = self.root(body2);
+        if root1 != root2 {
+            self.parents[root2] = root1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        graph, math as m,
+        physics::{tests::World, Body, Collider, ConstraintBuilder, SleepParams},
+    };
+
+    fn sleepy_world() -> World {
+        let mut world = World::new();
+        world.physics.sleep_params = Some(SleepParams::default());
+        world.spawn_ground();
+        world
+    }
+
+    fn is_sleeping(world: &World, body: graph::Node<Body>) -> bool {
+        world.body(body).is_sleeping()
+    }
+
+    #[test]
+    fn bodies_dont_sleep_by_default() {
+        let mut world = World::new();
+        world.spawn_ground();
+        let b = world.spawn_box(m::Vec2::new(0.0, -0.25), 0.0);
+        for _ in 0..120 {
+            world.tick();
+        }
+        assert!(!is_sleeping(&world, b));
+    }
+
+    #[test]
+    fn resting_stack_falls_asleep_together() {
+        let mut world = sleepy_world();
+        let bottom = world.spawn_box(m::Vec2::new(0.0, -0.25), 0.0);
+        let top = world.spawn_box(m::Vec2::new(0.0, 0.25), 0.0);
+        world.tick();
+        assert!(!is_sleeping(&world, bottom) && !is_sleeping(&world, top));
+        for _ in 0..120 {
+            world.tick();
+        }
+        assert!(is_sleeping(&world, bottom) && is_sleeping(&world, top));
+        let resting_pose = world.pose(top);
+        world.tick();
+        assert_eq!(world.pose(top), resting_pose);
+    }
+
+    /// Waking one body wakes everything it touches, but not separate islands.
+    #[test]
+    fn waking_spreads_through_island_only() {
+        let mut world = sleepy_world();
+        let stack = [
+            world.spawn_box(m::Vec2::new(-2.0, -0.25), 0.0),
+            world.spawn_box(m::Vec2::new(-2.0, 0.25), 0.0),
+        ];
+        let loner = world.spawn_box(m::Vec2::new(2.0, -0.25), 0.0);
+        for _ in 0..120 {
+            world.tick();
+        }
+        assert!(stack
+            .iter()
+            .chain(&[loner])
+            .all(|&b| is_sleeping(&world, b)));
+
+        world.body_mut(stack[1]).velocity.linear = m::Vec2::new(0.0, 1.0);
+        world.tick();
+        assert!(stack.iter().all(|&b| !is_sleeping(&world, b)));
+        assert!(is_sleeping(&world, loner));
+    }
+
+    #[test]
+    fn awake_body_landing_on_sleeping_one_wakes_it() {
+        let mut world = sleepy_world();
+        let sleeper = world.spawn_box(m::Vec2::new(0.0, -0.25), 0.0);
+        for _ in 0..120 {
+            world.tick();
+        }
+        assert!(is_sleeping(&world, sleeper));
+
+        world.spawn_box(m::Vec2::new(0.0, 2.0), 0.0);
+        let mut woken = false;
+        for _ in 0..60 {
+            world.tick();
+            woken |= !is_sleeping(&world, sleeper);
+        }
+        assert!(woken);
+    }
+
+    #[test]
+    fn only_modifying_constraint_wakes_bodies() {
+        let mut world = sleepy_world();
+        let b = world.spawn_box(m::Vec2::new(0.0, -0.25), 0.0);
+        let handle = world.physics.add_constraint(
+            ConstraintBuilder::new(b)
+                .with_target_origin(m::Vec2::new(0.0, -0.25))
+                .with_compliance(0.01)
+                .build_attachment(),
+        );
+        for _ in 0..120 {
+            world.tick();
+        }
+        assert!(is_sleeping(&world, b));
+
+        let offset = world.physics.get_constraint_mut(handle).unwrap().offsets[1];
+        world.tick();
+        assert!(is_sleeping(&world, b));
+
+        world.physics.get_constraint_mut(handle).unwrap().offsets[1] = offset + m::Vec2::unit_y();
+        world.tick();
+        assert!(!is_sleeping(&world, b));
+    }
+
+    /// A box hanging from a kinematic body falls asleep while the kinematic body is still
+    /// and wakes up to follow it when it starts moving,
+    /// whichever of the two owns the hinge between them.
+    #[test]
+    fn moving_kinematic_body_wakes_what_hangs_from_it() {
+        for &kinematic_owns in &[true, false] {
+            let mut world = sleepy_world();
+            let coll = Collider::new_square(0.2);
+            let anchor = world.spawn_body(
+                Body::new_kinematic(),
+                coll,
+                m::Pose::new(m::Vec2::new(0.0, 3.0), Default::default()),
+            );
+            let hanging = world.spawn_box(m::Vec2::new(0.0, 2.0), 0.0);
+            // the hinge is at the anchor's center and a metre above the box's
+            let hinge = [m::Vec2::zero(), m::Vec2::new(0.0, 1.0)];
+            let (owner, target, origins) = if kinematic_owns {
+                (anchor, hanging, hinge)
+            } else {
+                (hanging, anchor, [hinge[1], hinge[0]])
+            };
+            world.physics.add_constraint(
+                ConstraintBuilder::new(owner)
+                    .with_target(target)
+                    .with_origin(origins[0])
+                    .with_target_origin(origins[1])
+                    .build_revolute(None),
+            );
+            for _ in 0..120 {
+                world.tick();
+            }
+            assert!(is_sleeping(&world, hanging));
+
+            world.body_mut(anchor).velocity.linear = m::Vec2::new(1.0, 0.0);
+            for _ in 0..60 {
+                world.tick();
+            }
+            assert!(!is_sleeping(&world, hanging));
+            let offset = world.pose(anchor).translation - world.pose(hanging).translation;
+            assert!(
+                (offset.mag() - 1.0).abs() < 0.01,
+                "box is {:?} from the anchor",
+                offset
+            );
+        }
+    }
+}
@@ -4,19 +4,37 @@ use crate::{
     graph::{self, UnsafeNode},
     graphics::Shape,
     math as m,
-    physics::{collision::ROPE_LAYER, Body, Collider, Mass, Material},
+    physics::{
+        collision::ROPE_LAYER, Body, Collider, ConstraintBuilder, ConstraintHandle, Material,
+        Physics,
+    },
 };
 
 /// A rope built out of connected particles.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Rope {
+    /// Rest length of the segments between particles.
     pub spacing: f64,
+    /// Total rest length of the rope.
+    ///
+    /// The first segment takes up whatever is left over from the others being `spacing` long,
+    /// so changing this reels the rope in or out at the start.
+    /// Use [`set_rope_length`][self::set_rope_length] for changes larger than
+    /// a fraction of `spacing`, which also adds and removes particles.
+    pub length: f64,
     pub thickness: f64,
     pub compliance: f64,
+    /// How far in radians the rope bends away from its rest bend at each particle
+    /// before the bending constraint starts resisting.
     pub bending_max_angle: f64,
     pub bending_compliance: f64,
     pub damping: f64,
     pub material: Material,
+    /// Signed angle in radians the rope bends by at each particle when at rest,
+    /// in order from the start, positive counterclockwise.
+    /// Particles past the end of this are straight, so leave it empty for a straight rope.
+    /// [`spawn_rope_path`][self::spawn_rope_path] sets it to follow the shape of the path.
+    pub rest_bends: Vec<f64>,
 }
 
 /// Information returned from the creation of a rope, useful for e.g. constraining the rope's ends.
@@ -30,8 +48,9 @@ pub struct RopeProperties {
 
 /// Spawn a rope in the shape of the line, adjusting spacing so that a particle lands on both
 /// the start and end points.
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_rope_line(
-    mut rope: Rope,
+    rope: Rope,
     start: m::Vec2,
     end: m::Vec2,
     particle_mass: f64,
@@ -42,19 +61,104 @@ pub fn spawn_rope_line(
     l_shape: &mut graph::Layer<Shape>,
     graph: &mut graph::Graph,
 ) -> RopeProperties {
-    let dist = end - start;
-    let dist_mag = dist.mag();
-    let segment_count = (dist_mag / rope.spacing).round() as usize;
-    let particle_count = segment_count + 1;
-    rope.spacing = dist_mag / segment_count as f64;
-    let dir = dist / dist_mag;
-    let step = rope.spacing * dir;
-
-    let body_proto = Body {
-        velocity: Default::default(),
-        mass: Mass::from(particle_mass),
-        moment_of_inertia: Mass::Infinite,
-    };
+    spawn_rope_path(
+        rope,
+        RopePath::Polyline(&[start, end]),
+        particle_mass,
+        l_body,
+        l_pose,
+        l_collider,
+        l_rope,
+        l_shape,
+        graph,
+    )
+}
+
+/// A path to lay a rope along with [`spawn_rope_path`][self::spawn_rope_path].
+#[derive(Clone, Copy, Debug)]
+pub enum RopePath<'a> {
+    /// Straight lines between consecutive points.
+    Polyline(&'a [m::Vec2]),
+    /// A chain of cubic Bézier curves. The first point is the start of the path,
+    /// after which every curve takes two control points and an end point,
+    /// which is also where the next curve starts.
+    /// Leftover points that don't make up a whole curve are ignored.
+    CubicBezier(&'a [m::Vec2]),
+}
+
+/// Number of straight pieces each Bézier curve is split into when laying a rope along it.
+const BEZIER_SUBDIVISIONS: usize = 32;
+
+impl<'a> RopePath<'a> {
+    /// Approximate the path with a polyline.
+    fn to_polyline(self) -> Vec<m::Vec2> {
+        match self {
+            RopePath::Polyline(points) => points.to_vec(),
+            RopePath::CubicBezier(points) => {
+                let mut polyline = Vec::with_capacity(
+                    1 + BEZIER_SUBDIVISIONS * (points.len().saturating_sub(1) / 3),
+                );
+                polyline.extend(points.first());
+                for curve in points.windows(4).step_by(3) {
+                    polyline.extend((1..=BEZIER_SUBDIVISIONS).map(|i| {
+                        let t = i as f64 / BEZIER_SUBDIVISIONS as f64;
+                        let inv_t = 1.0 - t;
+                        inv_t * inv_t * inv_t * curve[0]
+                            + 3.0 * inv_t * inv_t * t * curve[1]
+                            + 3.0 * inv_t * t * t * curve[2]
+                            + t * t * t * curve[3]
+                    }));
+                }
+                polyline
+            }
+        }
+    }
+}
+
+/// Spawn a rope along a polyline or a chain of Bézier curves.
+///
+/// Particles are placed along the path so that they're all `spacing` apart along it,
+/// adjusting spacing to make a particle land on both ends of the path.
+/// The rope is as long as the path, but particles on either side of a corner
+/// are a bit closer to each other than `spacing`, so corners get rounded off a little.
+///
+/// The rest bend of each particle is set to the angle of the path there
+/// so that the rope keeps its shape instead of springing straight when the simulation starts.
+///
+/// # Panics
+/// Panics if the path has fewer than 2 points.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_rope_path(
+    mut rope: Rope,
+    path: RopePath,
+    particle_mass: f64,
+    l_body: &mut graph::Layer<Body>,
+    l_pose: &mut graph::Layer<m::Pose>,
+    l_collider: &mut graph::Layer<Collider>,
+    l_rope: &mut graph::Layer<Rope>,
+    l_shape: &mut graph::Layer<Shape>,
+    graph: &mut graph::Graph,
+) -> RopeProperties {
+    let path = path.to_polyline();
+    assert!(path.len() >= 2, "A rope path needs at least 2 points");
+
+    let path_length: f64 = path.windows(2).map(|seg| (seg[1] - seg[0]).mag()).sum();
+    let segment_count = ((path_length / rope.spacing).round() as usize).max(1);
+    let (points, spacing) = place_along_path(&path, segment_count);
+    let particle_count = points.len();
+    rope.spacing = spacing;
+    rope.length = spacing * segment_count as f64;
+    // the first particle has nothing to bend around, and the last one is left out
+    // because it's straight by default
+    rope.rest_bends = std::iter::once(0.0)
+        .chain(
+            points
+                .windows(3)
+                .map(|p| bend_angle(p[1] - p[0], p[2] - p[1])),
+        )
+        .collect();
+
+    let body_proto = Body::new_particle(particle_mass);
     let collider_proto = Collider::new_circle(rope.thickness / 2.0)
         .with_layer(ROPE_LAYER)
         .with_material(rope.material);
@@ -66,43 +170,293 @@ pub fn spawn_rope_line(
     };
 
     let rope_node = l_rope.insert(rope, graph);
-    let first_body = l_body.insert(body_proto, graph);
-    let first_pose = l_pose.insert(m::Pose::new(start, Default::default()), graph);
-    let first_coll = l_collider.insert(collider_proto, graph);
-    let first_shape = l_shape.insert(shape_proto, graph);
-    graph.connect(&first_body, &first_pose);
-    graph.connect(&first_body, &first_coll);
-    graph.connect(&first_pose, &first_coll);
-    graph.connect(&first_pose, &first_shape);
-    graph.connect_oneway(&rope_node, &first_body);
-    let first_body = first_body.pos();
-
-    let mut next_pos: m::Vec2 = start + step;
-    let mut prev_body: graph::NodePosition = first_body.pos();
-    for _ in 1..particle_count {
+    let mut first_body: Option<graph::NodePosition> = None;
+    let mut prev_body: Option<graph::NodePosition> = None;
+    for point in points {
         let body = l_body.insert(body_proto, graph);
-        let pose = l_pose.insert(m::Pose::new(next_pos, Default::default()), graph);
+        let pose = l_pose.insert(m::Pose::new(point, Default::default()), graph);
         let coll = l_collider.insert(collider_proto, graph);
         let shape = l_shape.insert(shape_proto, graph);
         graph.connect(&body, &pose);
         graph.connect(&pose, &coll);
         graph.connect(&body, &coll);
         graph.connect(&pose, &shape);
-        graph.connect_oneway_unchecked(&prev_body, &body);
-
-        next_pos += step;
-        prev_body = body.pos();
+        match prev_body {
+            Some(prev) => graph.connect_oneway_unchecked(&prev, &body),
+            None => {
+                graph.connect_oneway(&rope_node, &body);
+                first_body = Some(body.pos());
+            }
+        }
+        prev_body = Some(body.pos());
     }
-    graph.connect_oneway_unchecked(&prev_body, &rope_node);
+    let (first_body, last_body) = (first_body.unwrap(), prev_body.unwrap());
+    graph.connect_oneway_unchecked(&last_body, &rope_node);
 
     RopeProperties {
         particle_count,
         rope_node: graph::NodeRef::as_node(&rope_node, graph),
         first_particle: graph::NodeRef::as_node(&l_body.get_unchecked(first_body), graph),
-        last_particle: graph::NodeRef::as_node(&l_body.get_unchecked(prev_body), graph),
+        last_particle: graph::NodeRef::as_node(&l_body.get_unchecked(last_body), graph),
     }
 }
 
+/// Signed angle in radians from the direction `from` to the direction `to`,
+/// positive counterclockwise.
+pub(crate) fn bend_angle(from: m::Vec2, to: m::Vec2) -> f64 {
+    from.wedge(to).xy.atan2(from.dot(to))
+}
+
+/// Place `step_count + 1` points on a polyline so that the first and last are at its ends
+/// and the distance travelled along the path between consecutive points is the same everywhere.
+/// Returns the points and the distance between them.
+pub(crate) fn place_along_path(path: &[m::Vec2], step_count: usize) -> (Vec<m::Vec2>, f64) {
+    let path_length: f64 = path.windows(2).map(|seg| (seg[1] - seg[0]).mag()).sum();
+    let step = path_length / step_count as f64;
+
+    let mut points = Vec::with_capacity(step_count + 1);
+    points.push(path[0]);
+    // distance along the path to the start of the current segment
+    let mut seg_start_dist = 0.0;
+    let mut segments = path.windows(2);
+    let mut seg = segments.next();
+    for i in 1..step_count {
+        let dist = step * i as f64;
+        while let Some(s) = seg {
+            let seg_len = (s[1] - s[0]).mag();
+            // rounding errors may take the last points slightly past the end
+            let is_last = segments.len() == 0;
+            if is_last || seg_start_dist + seg_len >= dist {
+                let t = if seg_len > 0.0 {
+                    ((dist - seg_start_dist) / seg_len).min(1.0)
+                } else {
+                    0.0
+                };
+                points.push(s[0] + t * (s[1] - s[0]));
+                break;
+            }
+            seg_start_dist += seg_len;
+            seg = segments.next();
+        }
+    }
+    points.push(*path.last().unwrap());
+    (points, step)
+}
+
+/// One of the two ends of a rope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RopeEnd {
+    /// The first particle.
+    Start,
+    /// The last particle.
+    End,
+}
+
+/// Get the particle at one end of a rope.
+pub fn rope_end_particle<'a>(
+    rope_node: graph::NodeRef<'a, Rope>,
+    end: RopeEnd,
+    l_body: &'a graph::Layer<Body>,
+    graph: &'a graph::Graph,
+) -> Option<graph::NodeRef<'a, Body>> {
+    match end {
+        RopeEnd::Start => graph.get_neighbor(&rope_node, l_body),
+        RopeEnd::End => RopeIter::new(rope_node, l_body, graph).last(),
+    }
+}
+
+/// Attach one end of a rope to a point on a body, or a fixed point in the world
+/// if `body` is `None`. The anchor point is relative to the body's center of mass,
+/// like the origins in [`ConstraintBuilder`][super::ConstraintBuilder].
+///
+/// Returns a handle to the created constraint, which can be removed to detach the rope,
+/// or `None` if the rope has been deleted.
+#[allow(clippy::too_many_arguments)]
+pub fn attach_rope(
+    physics: &mut Physics,
+    rope_node: graph::Node<Rope>,
+    end: RopeEnd,
+    body: Option<graph::Node<Body>>,
+    anchor: m::Vec2,
+    l_body: &graph::Layer<Body>,
+    l_rope: &graph::Layer<Rope>,
+    graph: &graph::Graph,
+) -> Option<ConstraintHandle> {
+    let rope_node = l_rope.get(rope_node.check(graph)?);
+    let particle = rope_end_particle(rope_node, end, l_body, graph)?;
+    let builder = ConstraintBuilder::new(graph::NodeRef::as_node(&particle, graph))
+        .with_target_origin(anchor);
+    let builder = match body {
+        Some(body) => builder.with_target(body),
+        None => builder,
+    };
+    Some(physics.add_constraint(builder.build_attachment()))
+}
+
+/// Change the rest length of a rope, reeling it in or out at the start like a winch.
+///
+/// Particles are added or removed right after the first one as needed
+/// to keep the first segment between half and one and a half times `spacing` long.
+/// The first particle is never removed, so anything attached to it stays attached.
+/// New particles are copies of the second one, and the rope is straight at them.
+///
+/// A rope can't be made shorter than one segment with a length of zero.
+#[allow(clippy::too_many_arguments)]
+pub fn set_rope_length(
+    rope_node: graph::Node<Rope>,
+    length: f64,
+    l_body: &mut graph::Layer<Body>,
+    l_pose: &mut graph::Layer<m::Pose>,
+    l_collider: &mut graph::Layer<Collider>,
+    l_rope: &mut graph::Layer<Rope>,
+    l_shape: &mut graph::Layer<Shape>,
+    graph: &mut graph::Graph,
+) {
+    let rope_pos = match rope_node.check(graph) {
+        Some(node) => node.pos(),
+        None => return,
+    };
+    let (length, spacing) = {
+        let mut rope = l_rope.get_mut_unchecked(rope_pos);
+        rope.length = length.max(0.0);
+        (rope.length, rope.spacing)
+    };
+    let mut particle_count = RopeIter::new(l_rope.get_unchecked(rope_pos), l_body, graph).count();
+    if particle_count < 2 {
+        return;
+    }
+    let first = graph
+        .get_neighbor_unchecked(&rope_pos, l_body)
+        .unwrap()
+        .pos();
+
+    loop {
+        let first_segment = length - spacing * (particle_count - 2) as f64;
+        let second = graph.get_neighbor_unchecked(&first, l_body).unwrap().pos();
+        if first_segment > 1.5 * spacing {
+            // reel out by splitting the first segment
+            let first_pos = graph
+                .get_neighbor_unchecked(&first, l_pose)
+                .unwrap()
+                .translation;
+            let second_pose = *graph.get_neighbor_unchecked(&second, l_pose).unwrap();
+            let t = (first_segment - spacing) / first_segment;
+            let new_pos = first_pos + t * (second_pose.translation - first_pos);
+
+            let body_proto = *l_body.get_unchecked(second);
+            let collider_proto = *graph.get_neighbor_unchecked(&second, l_collider).unwrap();
+            let shape_proto = graph
+                .get_neighbor_unchecked(&second, l_pose)
+                .and_then(|pose| graph.get_neighbor_unchecked(&pose, l_shape))
+                .map(|shape| *shape);
+
+            let body = l_body.insert(body_proto, graph).pos();
+            let pose = l_pose
+                .insert(m::Pose::new(new_pos, second_pose.rotation), graph)
+                .pos();
+            let coll = l_collider.insert(collider_proto, graph).pos();
+            graph.connect_unchecked(&body, &pose);
+            graph.connect_unchecked(&pose, &coll);
+            graph.connect_unchecked(&body, &coll);
+            if let Some(shape_proto) = shape_proto {
+                let shape = l_shape.insert(shape_proto, graph).pos();
+                graph.connect_unchecked(&pose, &shape);
+            }
+            graph.disconnect_oneway_unchecked(&first, &second);
+            graph.connect_oneway_unchecked(&first, &body);
+            graph.connect_oneway_unchecked(&body, &second);
+            let rest_bends = &mut l_rope.get_mut_unchecked(rope_pos).rest_bends;
+            if rest_bends.len() > 1 {
+                rest_bends.insert(1, 0.0);
+            }
+            particle_count += 1;
+        } else if first_segment < 0.5 * spacing && particle_count > 2 {
+            // reel in by removing the second particle
+            let third = graph.get_neighbor_unchecked(&second, l_body).unwrap().pos();
+            graph.disconnect_oneway_unchecked(&second, &third);
+            graph.disconnect_oneway_unchecked(&first, &second);
+            graph.connect_oneway_unchecked(&first, &third);
+            // the particle still has edges to and from its own pose and collider
+            graph.delete(l_body.get_unchecked(second));
+            let rest_bends = &mut l_rope.get_mut_unchecked(rope_pos).rest_bends;
+            if rest_bends.len() > 1 {
+                rest_bends.remove(1);
+            }
+            particle_count -= 1;
+        } else {
+            break;
+        }
+    }
+}
+
+/// Cut a rope between the given particle and the one after it,
+/// splitting it into two ropes.
+///
+/// The original rope node keeps the particles up to and including `particle`
+/// and a new rope node with the same properties is created for the rest.
+/// Constraints attached to the particles are unaffected.
+///
+/// Returns the new rope node, or `None` if the particle isn't part of the rope
+/// or is its last particle.
+pub fn cut_rope(
+    rope_node: graph::Node<Rope>,
+    particle: graph::Node<Body>,
+    l_body: &graph::Layer<Body>,
+    l_rope: &mut graph::Layer<Rope>,
+    graph: &mut graph::Graph,
+) -> Option<graph::Node<Rope>> {
+    let rope_pos = rope_node.check(graph)?.pos();
+    let particle_pos = particle.check(graph)?.pos();
+    let rope = Rope::clone(&l_rope.get_unchecked(rope_pos));
+
+    let particles: Vec<graph::NodePosition> =
+        RopeIter::new(l_rope.get_unchecked(rope_pos), l_body, graph)
+            .map(|p| p.pos())
+            .collect();
+    let cut_idx = particles.iter().position(|p| *p == particle_pos)?;
+    let next = *particles.get(cut_idx + 1)?;
+    let last = *particles.last().unwrap();
+
+    // the first segment of the original rope may have a different length from the others
+    let first_segment = rope.length - rope.spacing * (particles.len() - 2) as f64;
+    {
+        let mut cut = l_rope.get_mut_unchecked(rope_pos);
+        cut.length = match cut_idx {
+            0 => 0.0,
+            _ => first_segment + rope.spacing * (cut_idx - 1) as f64,
+        };
+        cut.rest_bends.truncate(cut_idx + 1);
+    }
+    let new_rope = l_rope
+        .insert(
+            Rope {
+                length: rope.spacing * (particles.len() - cut_idx - 2) as f64,
+                rest_bends: match rope.rest_bends.get(cut_idx + 1..) {
+                    // the new first particle has nothing to bend around
+                    Some([_, rest @ ..]) => {
+                        std::iter::once(0.0).chain(rest.iter().copied()).collect()
+                    }
+                    _ => Vec::new(),
+                },
+                ..rope
+            },
+            graph,
+        )
+        .pos();
+
+    graph.disconnect_oneway_unchecked(&particle_pos, &next);
+    // connect before disconnecting so the rope node's refcount never hits zero
+    graph.connect_oneway_unchecked(&particle_pos, &rope_pos);
+    graph.disconnect_oneway_unchecked(&last, &rope_pos);
+    graph.connect_oneway_unchecked(&new_rope, &next);
+    graph.connect_oneway_unchecked(&last, &new_rope);
+
+    Some(graph::NodeRef::as_node(
+        &l_rope.get_unchecked(new_rope),
+        graph,
+    ))
+}
+
 /// An iterator over the particles in a particular rope, in order from start to end.
 pub struct RopeIter<'a> {
     rope_node: graph::NodeRef<'a, Rope>,
@@ -141,3 +495,270 @@ impl<'a> Iterator for RopeIter<'a> {
         self.curr_body
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{
+        tests::{rope_proto, World},
+        Velocity,
+    };
+
+    impl World {
+        /// A free rope laid along a path.
+        fn spawn_rope_path(&mut self, path: RopePath) -> RopeProperties {
+            spawn_rope_path(
+                rope_proto(),
+                path,
+                0.02,
+                &mut self.l_body,
+                &mut self.l_pose,
+                &mut self.l_collider,
+                &mut self.l_rope,
+                &mut self.l_shape,
+                &mut self.graph,
+            )
+        }
+
+        fn rope_points(&self, rope: graph::Node<Rope>) -> Vec<m::Vec2> {
+            self.rope_particles(rope)
+                .into_iter()
+                .map(|particle| self.pose(particle).translation)
+                .collect()
+        }
+
+        fn set_rope_length(&mut self, rope: graph::Node<Rope>, length: f64) {
+            set_rope_length(
+                rope,
+                length,
+                &mut self.l_body,
+                &mut self.l_pose,
+                &mut self.l_collider,
+                &mut self.l_rope,
+                &mut self.l_shape,
+                &mut self.graph,
+            );
+        }
+
+        fn rope_particles(&self, rope: graph::Node<Rope>) -> Vec<graph::Node<Body>> {
+            let rope = self.l_rope.get(rope.check(&self.graph).unwrap());
+            RopeIter::new(rope, &self.l_body, &self.graph)
+                .map(|particle| graph::NodeRef::as_node(&particle, &self.graph))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn set_rope_length_reels_in_and_out() {
+        let mut world = World::new();
+        let rope = world.spawn_rope(m::Vec2::new(0.0, 2.0), m::Vec2::new(1.0, 2.0));
+        world.set_rope_length(rope.rope_node, 2.0);
+        let particles = world.rope_particles(rope.rope_node);
+        // the first segment stays between half and one and a half times the spacing
+        assert_eq!(particles.len(), 21);
+        assert_eq!(particles[0], rope.first_particle);
+        assert_eq!(*particles.last().unwrap(), rope.last_particle);
+        for _ in 0..300 {
+            world.tick();
+        }
+        // still swinging, but hanging at the full length
+        let hanging = world.pose(rope.last_particle).translation - m::Vec2::new(0.0, 2.0);
+        assert!((hanging.mag() - 2.0).abs() < 0.05);
+        assert!(hanging.y < -1.5);
+
+        // reeling in all the way leaves the first segment with zero length
+        world.set_rope_length(rope.rope_node, 0.0);
+        assert_eq!(world.rope_particles(rope.rope_node).len(), 2);
+        // put the particles exactly on top of each other
+        let first_pose = world.pose(rope.first_particle);
+        for &particle in &[rope.first_particle, rope.last_particle] {
+            world.body_mut(particle).velocity = Velocity::default();
+            let body = world.l_body.get(particle.check(&world.graph).unwrap());
+            let pose = world
+                .graph
+                .get_neighbor(&body, &world.l_pose)
+                .unwrap()
+                .pos();
+            *world.l_pose.get_mut_unchecked(pose) = first_pose;
+        }
+        for _ in 0..120 {
+            world.tick();
+        }
+        let end = world.pose(rope.last_particle).translation;
+        assert!(end.x.is_finite() && end.y.is_finite());
+        assert!((end - m::Vec2::new(0.0, 2.0)).mag() < 0.01);
+    }
+
+    #[test]
+    fn cut_rope_splits_it_in_two() {
+        let mut world = World::new();
+        let rope = world.spawn_rope(m::Vec2::new(0.0, 2.0), m::Vec2::new(0.0, 1.0));
+        let particles = world.rope_particles(rope.rope_node);
+        assert_eq!(particles.len(), 11);
+        let new_rope = cut_rope(
+            rope.rope_node,
+            particles[4],
+            &world.l_body,
+            &mut world.l_rope,
+            &mut world.graph,
+        )
+        .unwrap();
+        assert_eq!(world.rope_particles(rope.rope_node), particles[..5]);
+        assert_eq!(world.rope_particles(new_rope), particles[5..]);
+        let length = |world: &World, rope: graph::Node<Rope>| {
+            world.l_rope.get(rope.check(&world.graph).unwrap()).length
+        };
+        assert!((length(&world, rope.rope_node) - 0.4).abs() < 1e-9);
+        assert!((length(&world, new_rope) - 0.5).abs() < 1e-9);
+        // the end of a rope can't be cut
+        assert!(cut_rope(
+            new_rope,
+            rope.last_particle,
+            &world.l_body,
+            &mut world.l_rope,
+            &mut world.graph,
+        )
+        .is_none());
+
+        // the attached part keeps hanging while the rest falls
+        for _ in 0..30 {
+            world.tick();
+        }
+        assert!((world.pose(particles[4]).translation.y - 1.6).abs() < 0.01);
+        assert!(world.pose(rope.last_particle).translation.y < 0.0);
+    }
+
+    #[test]
+    fn rope_path_particles_are_spaced_along_the_path() {
+        let mut world = World::new();
+        let rope = world.spawn_rope_path(RopePath::Polyline(&[
+            m::Vec2::new(0.0, 0.0),
+            m::Vec2::new(1.0, 0.0),
+            m::Vec2::new(1.0, 0.5),
+            m::Vec2::new(1.0, 1.0),
+        ]));
+        let points = world.rope_points(rope.rope_node);
+        assert_eq!(points.len(), 21);
+        let rope_length = world
+            .l_rope
+            .get(rope.rope_node.check(&world.graph).unwrap())
+            .length;
+        assert!((rope_length - 2.0).abs() < 1e-9);
+        // distance travelled along the L to reach a point on it
+        for (i, p) in points.iter().enumerate() {
+            let along_path = if p.y.abs() < 1e-9 { p.x } else { 1.0 + p.y };
+            assert!((along_path - 0.1 * i as f64).abs() < 1e-9);
+        }
+        assert_eq!(points[0], m::Vec2::new(0.0, 0.0));
+        assert_eq!(points[20], m::Vec2::new(1.0, 1.0));
+
+        // bezier curves aren't traversed at an even speed,
+        // but particles are still evenly spaced
+        let rope = world.spawn_rope_path(RopePath::CubicBezier(&[
+            m::Vec2::new(0.0, 3.0),
+            m::Vec2::new(0.0, 3.0),
+            m::Vec2::new(0.0, 3.0),
+            m::Vec2::new(2.0, 3.0),
+        ]));
+        let points = world.rope_points(rope.rope_node);
+        assert_eq!(points.len(), 21);
+        for (i, p) in points.iter().enumerate() {
+            assert!((*p - m::Vec2::new(0.1 * i as f64, 3.0)).mag() < 1e-9);
+        }
+        let rope = world.spawn_rope_path(RopePath::CubicBezier(&[
+            m::Vec2::new(0.0, 5.0),
+            m::Vec2::new(0.0, 6.0),
+            m::Vec2::new(3.0, 6.0),
+            m::Vec2::new(3.0, 5.0),
+        ]));
+        let points = world.rope_points(rope.rope_node);
+        assert_eq!(points[0], m::Vec2::new(0.0, 5.0));
+        assert_eq!(*points.last().unwrap(), m::Vec2::new(3.0, 5.0));
+        let spacing = world
+            .l_rope
+            .get(rope.rope_node.check(&world.graph).unwrap())
+            .spacing;
+        for seg in points.windows(2) {
+            let dist = (seg[1] - seg[0]).mag();
+            assert!(dist < spacing + 1e-9 && dist > 0.99 * spacing);
+        }
+    }
+
+    #[test]
+    fn rope_path_keeps_its_shape() {
+        let mut world = World::new();
+        world.gravity = m::Vec2::zero();
+        let rope = world.spawn_rope_path(RopePath::Polyline(&[
+            m::Vec2::new(0.0, 0.0),
+            m::Vec2::new(1.0, 0.0),
+            m::Vec2::new(1.0, 1.0),
+        ]));
+        for _ in 0..60 {
+            world.tick();
+        }
+        let points = world.rope_points(rope.rope_node);
+        let corner_bend = bend_angle(points[10] - points[9], points[11] - points[10]);
+        assert!((corner_bend - std::f64::consts::FRAC_PI_2).abs() < 0.35);
+        assert!((points[20] - points[0] - m::Vec2::new(1.0, 1.0)).mag() < 0.1);
+        // the shape is kept in the rope, particles themselves stay unrotated
+        for particle in world.rope_particles(rope.rope_node) {
+            assert_eq!(world.pose(particle).rotation, m::Rotor2::identity());
+        }
+    }
+
+    #[test]
+    fn rest_bends_stay_with_their_particles() {
+        let mut world = World::new();
+        let rope = world.spawn_rope_path(RopePath::Polyline(&[
+            m::Vec2::new(0.0, 0.0),
+            m::Vec2::new(0.5, 0.0),
+            m::Vec2::new(0.5, 0.5),
+        ]));
+        let rest_bends = |world: &World, rope: graph::Node<Rope>| {
+            let rope = world.l_rope.get(rope.check(&world.graph).unwrap());
+            rope.rest_bends.clone()
+        };
+        let corner = |bends: &[f64]| {
+            bends
+                .iter()
+                .position(|b| (b - std::f64::consts::FRAC_PI_2).abs() < 1e-9)
+        };
+        assert_eq!(corner(&rest_bends(&world, rope.rope_node)), Some(5));
+        let corner_particle = world.rope_particles(rope.rope_node)[5];
+
+        // reeling out adds straight particles at the start
+        world.set_rope_length(rope.rope_node, 1.3);
+        let particles = world.rope_particles(rope.rope_node);
+        let bends = rest_bends(&world, rope.rope_node);
+        assert_eq!(particles.len(), 14);
+        assert_eq!(particles[8], corner_particle);
+        assert_eq!(corner(&bends), Some(8));
+        assert!(bends[..8].iter().all(|&b| b == 0.0));
+
+        // and cutting hands the rest of them to the new rope
+        let new_rope = cut_rope(
+            rope.rope_node,
+            particles[6],
+            &world.l_body,
+            &mut world.l_rope,
+            &mut world.graph,
+        )
+        .unwrap();
+        assert_eq!(rest_bends(&world, rope.rope_node).len(), 7);
+        assert_eq!(corner(&rest_bends(&world, new_rope)), Some(1));
+    }
+
+    /// Ropes spawned as a line don't bend at rest and keep their particles unrotated.
+    #[test]
+    fn rope_lines_are_straight() {
+        let mut world = World::new();
+        let rope = world.spawn_rope(m::Vec2::new(0.0, 2.0), m::Vec2::new(1.0, 2.0));
+        let node = world
+            .l_rope
+            .get(rope.rope_node.check(&world.graph).unwrap());
+        assert!(node.rest_bends.iter().all(|&b| b == 0.0));
+        for particle in world.rope_particles(rope.rope_node) {
+            assert_eq!(world.pose(particle).rotation, m::Rotor2::identity());
+        }
+    }
+}
@@ -0,0 +1,507 @@
+//! Saving and restoring the state of the physics world, e.g. for rollback netcode and replays.
+
+use super::{
+    collision::AABB, Body, Collider, Constraint, ConstraintHandle, ContactEvent, Physics, Rope,
+    SoftBody,
+};
+use crate::{graph, math as m, math::serde_pose::PoseData};
+use slotmap as sm;
+use std::{
+    any::Any,
+    collections::{BTreeMap, BTreeSet},
+};
+
+/// An error from restoring a physics snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The snapshot couldn't be decoded. It may be corrupted
+    /// or made with a different version of the engine.
+    Decode(bincode::Error),
+    /// The layers given don't match the ones the snapshot was taken from,
+    /// or they don't include every layer in the graph.
+    LayerMismatch,
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Decode(err) => write!(f, "Failed to decode snapshot: {}", err),
+            SnapshotError::LayerMismatch => {
+                write!(f, "Snapshot was taken from a different set of layers")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Decode(err) => Some(err),
+            SnapshotError::LayerMismatch => None,
+        }
+    }
+}
+
+/// A graph layer outside of physics whose contents can be saved in a physics snapshot.
+///
+/// Implemented for every layer whose components can be serialized and deserialized.
+/// See [`Physics::snapshot`] for why these are needed.
+pub trait SnapshotLayer {
+    /// Index of the layer in its graph.
+    fn layer_index(&self) -> usize;
+    /// Encode the components in the layer.
+    fn encode_content(&self) -> bincode::Result<Vec<u8>>;
+    /// Decode components encoded with `encode_content` without changing the layer yet.
+    fn decode_content(&self, bytes: &[u8]) -> bincode::Result<Box<dyn Any>>;
+    /// Replace the components in the layer with ones returned by `decode_content`.
+    fn replace_content(&mut self, content: Box<dyn Any>);
+}
+
+impl<T> SnapshotLayer for graph::Layer<T>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned + 'static,
+{
+    fn layer_index(&self) -> usize {
+        self.index
+    }
+
+    fn encode_content(&self) -> bincode::Result<Vec<u8>> {
+        bincode::serialize(&self.content)
+    }
+
+    fn decode_content(&self, bytes: &[u8]) -> bincode::Result<Box<dyn Any>> {
+        let content: Vec<T> = bincode::deserialize(bytes)?;
+        Ok(Box::new(content))
+    }
+
+    fn replace_content(&mut self, content: Box<dyn Any>) {
+        self.content = *content
+            .downcast()
+            .expect("Content decoded for a different layer");
+    }
+}
+
+type ColliderPair = [graph::Node<Collider>; 2];
+
+// written from borrowed data to avoid copying everything before encoding it
+#[derive(serde::Serialize)]
+struct SnapshotRef<'a> {
+    graph: &'a graph::Graph,
+    layer_indices: [usize; 5],
+    other_layers: Vec<(usize, Vec<u8>)>,
+    poses: Vec<PoseData>,
+    bodies: &'a [Body],
+    colliders: &'a [Collider],
+    ropes: &'a [Rope],
+    soft_bodies: &'a [SoftBody],
+    user_constraints: &'a sm::DenseSlotMap<ConstraintHandle, Constraint>,
+    constraint_order: &'a [ConstraintHandle],
+    active_contacts: &'a BTreeMap<ColliderPair, ContactEvent>,
+    trigger_pairs: &'a BTreeSet<ColliderPair>,
+    one_way_pairs: &'a BTreeMap<ColliderPair, bool>,
+    wake_queue: &'a [graph::Node<Body>],
+    spatial_index: Vec<(usize, AABB)>,
+}
+
+// must have the same fields in the same order as `SnapshotRef`
+#[derive(serde::Deserialize)]
+struct Snapshot {
+    graph: graph::Graph,
+    layer_indices: [usize; 5],
+    other_layers: Vec<(usize, Vec<u8>)>,
+    poses: Vec<PoseData>,
+    bodies: Vec<Body>,
+    colliders: Vec<Collider>,
+    ropes: Vec<Rope>,
+    soft_bodies: Vec<SoftBody>,
+    user_constraints: sm::DenseSlotMap<ConstraintHandle, Constraint>,
+    constraint_order: Vec<ConstraintHandle>,
+    active_contacts: BTreeMap<ColliderPair, ContactEvent>,
+    trigger_pairs: BTreeSet<ColliderPair>,
+    one_way_pairs: BTreeMap<ColliderPair, bool>,
+    wake_queue: Vec<graph::Node<Body>>,
+    spatial_index: Vec<(usize, AABB)>,
+}
+
+impl Physics {
+    /// Save the state of the physics world as a compact binary blob
+    /// that can be given to [`restore_snapshot`][Self::restore_snapshot] to return to this moment.
+    ///
+    /// The snapshot contains the graph along with the contents of the physics layers,
+    /// user constraints, and everything `Physics` remembers between ticks
+    /// such as active contacts and trigger overlaps.
+    /// Settings like `substeps`, the mask matrix, material pairs, sleep parameters
+    /// and the contact modifier aren't included.
+    ///
+    /// Because the whole graph is saved, objects created or deleted after taking a snapshot
+    /// are also undone by restoring it. For the graph to stay consistent with its layers,
+    /// every other layer in the graph must be saved too by giving it in `other_layers`,
+    /// otherwise restoring the snapshot fails.
+    /// For an [`EventGraph`][crate::event::EventGraph] give its `sinks` layer.
+    /// Its consumers can't be saved, so a sink that gets deleted after taking the snapshot
+    /// comes back with the consumer of whatever sink took its place in the meantime.
+    #[allow(clippy::too_many_arguments)]
+    pub fn snapshot(
+        &self,
+        graph: &graph::Graph,
+        l_pose: &graph::Layer<m::Pose>,
+        l_body: &graph::Layer<Body>,
+        l_collider: &graph::Layer<Collider>,
+        l_rope: &graph::Layer<Rope>,
+        l_soft_body: &graph::Layer<SoftBody>,
+        other_layers: &[&dyn SnapshotLayer],
+    ) -> Vec<u8> {
+        // encoding into memory only fails if a Serialize impl does, which ours don't
+        let other_layers = other_layers
+            .iter()
+            .map(|layer| {
+                let content = layer
+                    .encode_content()
+                    .expect("Failed to encode layer for physics snapshot");
+                (layer.layer_index(), content)
+            })
+            .collect();
+        let snapshot = SnapshotRef {
+            graph,
+            layer_indices: [
+                l_pose.index,
+                l_body.index,
+                l_collider.index,
+                l_rope.index,
+                l_soft_body.index,
+            ],
+            other_layers,
+            poses: l_pose.content.iter().map(PoseData::from).collect(),
+            bodies: &l_body.content,
+            colliders: &l_collider.content,
+            ropes: &l_rope.content,
+            soft_bodies: &l_soft_body.content,
+            user_constraints: &self.user_constraints,
+            constraint_order: &self.constraint_order,
+            active_contacts: &self.active_contacts,
+            trigger_pairs: &self.trigger_pairs,
+            one_way_pairs: &self.one_way_pairs,
+            wake_queue: &self.wake_queue,
+            spatial_index: self.spatial_index.contents().collect(),
+        };
+        bincode::serialize(&snapshot).expect("Failed to encode physics snapshot")
+    }
+
+    /// Return the physics world to the state saved in a snapshot
+    /// made with [`snapshot`][Self::snapshot].
+    ///
+    /// Ticking after restoring gives bit for bit the same results as ticking after
+    /// the snapshot was taken, as long as the same build of the game runs on the same platform.
+    ///
+    /// `other_layers` must be the same layers in the same order as when taking the snapshot,
+    /// and together with the physics layers they must include every layer in the graph.
+    /// Nothing is changed if an error is returned.
+    /// Constraint handles that existed when the snapshot was taken stay valid,
+    /// but handles given out for constraints added after restoring
+    /// may be different from ones given out the first time around.
+    #[allow(clippy::too_many_arguments)]
+    pub fn restore_snapshot(
+        &mut self,
+        snapshot: &[u8],
+        graph: &mut graph::Graph,
+        l_pose: &mut graph::Layer<m::Pose>,
+        l_body: &mut graph::Layer<Body>,
+        l_collider: &mut graph::Layer<Collider>,
+        l_rope: &mut graph::Layer<Rope>,
+        l_soft_body: &mut graph::Layer<SoftBody>,
+        other_layers: &mut [&mut dyn SnapshotLayer],
+    ) -> Result<(), SnapshotError> {
+        let snapshot: Snapshot = bincode::deserialize(snapshot).map_err(SnapshotError::Decode)?;
+        let layer_indices = [
+            l_pose.index,
+            l_body.index,
+            l_collider.index,
+            l_rope.index,
+            l_soft_body.index,
+        ];
+        let other_indices_match = other_layers.len() == snapshot.other_layers.len()
+            && (other_layers.iter())
+                .zip(&snapshot.other_layers)
+                .all(|(layer, (idx, _))| layer.layer_index() == *idx);
+        // every layer in the graph needs to be restored along with it, exactly once
+        let mut covered_layers = vec![false; graph.layer_count()];
+        let covers_graph = (layer_indices.iter())
+            .chain(snapshot.other_layers.iter().map(|(idx, _)| idx))
+            .all(|&idx| {
+                idx < covered_layers.len() && !std::mem::replace(&mut covered_layers[idx], true)
+            })
+            && covered_layers.iter().all(|&covered| covered);
+        if snapshot.graph.layer_count() != graph.layer_count()
+            || snapshot.layer_indices != layer_indices
+            || !other_indices_match
+            || !covers_graph
+        {
+            return Err(SnapshotError::LayerMismatch);
+        }
+        let other_contents = (other_layers.iter())
+            .zip(&snapshot.other_layers)
+            .map(|(layer, (_, bytes))| layer.decode_content(bytes))
+            .collect::<bincode::Result<Vec<_>>>()
+            .map_err(SnapshotError::Decode)?;
+
+        *graph = snapshot.graph;
+        l_pose.content = snapshot.poses.into_iter().map(m::Pose::from).collect();
+        l_body.content = snapshot.bodies;
+        l_collider.content = snapshot.colliders;
+        l_rope.content = snapshot.ropes;
+        l_soft_body.content = snapshot.soft_bodies;
+        for (layer, content) in other_layers.iter_mut().zip(other_contents) {
+            layer.replace_content(content);
+        }
+
+        self.user_constraints = snapshot.user_constraints;
+        self.constraint_order = snapshot.constraint_order;
+        self.active_contacts = snapshot.active_contacts;
+        self.trigger_pairs = snapshot.trigger_pairs;
+        self.one_way_pairs = snapshot.one_way_pairs;
+        self.wake_queue = snapshot.wake_queue;
+        // queries between ticks go through the spatial index, so it needs to match too
+        self.spatial_index
+            .restore(l_collider.content.len(), snapshot.spatial_index);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graphics::Shape,
+        physics::{tests::World, ConstraintBuilder},
+    };
+
+    impl World {
+        fn snapshot(&self) -> Vec<u8> {
+            self.physics.snapshot(
+                &self.graph,
+                &self.l_pose,
+                &self.l_body,
+                &self.l_collider,
+                &self.l_rope,
+                &self.l_soft_body,
+                &[&self.l_shape, &self.l_evt_sink],
+            )
+        }
+
+        fn restore(&mut self, snapshot: &[u8]) -> Result<(), SnapshotError> {
+            self.physics.restore_snapshot(
+                snapshot,
+                &mut self.graph,
+                &mut self.l_pose,
+                &mut self.l_body,
+                &mut self.l_collider,
+                &mut self.l_rope,
+                &mut self.l_soft_body,
+                &mut [&mut self.l_shape, &mut self.l_evt_sink],
+            )
+        }
+
+        /// Bit patterns of the poses and velocities of every body.
+        fn state(&self) -> Vec<u64> {
+            let mut state = Vec::new();
+            for body in self.l_body.iter(&self.graph) {
+                let pose = self.graph.get_neighbor(&body, &self.l_pose).unwrap();
+                state.extend(
+                    [
+                        pose.translation.x,
+                        pose.translation.y,
+                        pose.rotation.s,
+                        pose.rotation.bv.xy,
+                        body.velocity.linear.x,
+                        body.velocity.linear.y,
+                        body.velocity.angular,
+                    ]
+                    .iter()
+                    .map(|v| v.to_bits()),
+                );
+            }
+            state
+        }
+    }
+
+    fn test_world() -> (World, [graph::Node<Body>; 3]) {
+        let mut world = World::new();
+        world.spawn_ground();
+        let boxes = [
+            world.spawn_box(m::Vec2::new(0.0, 0.5), 0.0),
+            world.spawn_box(m::Vec2::new(0.1, 1.2), 0.3),
+            world.spawn_box(m::Vec2::new(1.5, 2.0), -0.5),
+        ];
+        world.physics.add_constraint(
+            ConstraintBuilder::new(boxes[2])
+                .with_target(boxes[1])
+                .build_distance(1.0),
+        );
+        world.spawn_rope(m::Vec2::new(-2.0, 3.0), m::Vec2::new(-0.5, 3.0));
+        (world, boxes)
+    }
+
+    /// Ticking after restoring a snapshot gives exactly the same results as the first time,
+    /// including when objects and constraints are created after the snapshot.
+    #[test]
+    fn restore_replays_exactly() {
+        let (mut world, boxes) = test_world();
+        for _ in 0..30 {
+            world.tick();
+        }
+        let snapshot = world.snapshot();
+        let body_count = world.l_body.iter(&world.graph).count();
+
+        let run = |world: &mut World| {
+            let mut states = Vec::new();
+            for frame in 0..60 {
+                if frame == 10 {
+                    let new_box = world.spawn_box(m::Vec2::new(-1.0, 2.0), 0.1);
+                    world.physics.add_constraint(
+                        ConstraintBuilder::new(new_box)
+                            .with_target(boxes[0])
+                            .build_distance(1.5),
+                    );
+                }
+                world.tick();
+                states.push(world.state());
+            }
+            states
+        };
+        let first_run = run(&mut world);
+        world.restore(&snapshot).unwrap();
+        // the box spawned after the snapshot is gone
+        assert_eq!(world.l_body.iter(&world.graph).count(), body_count);
+        let second_run = run(&mut world);
+        assert!(first_run == second_run);
+    }
+
+    /// Constraints are solved in the order they were added
+    /// no matter what else has happened in the slotmap they're stored in.
+    #[test]
+    fn constraint_order_is_independent_of_slotmap_history() {
+        let constraints = |boxes: [graph::Node<Body>; 3]| {
+            [
+                ConstraintBuilder::new(boxes[0])
+                    .with_target(boxes[1])
+                    .build_distance(1.0),
+                ConstraintBuilder::new(boxes[0])
+                    .with_target(boxes[2])
+                    .build_distance(1.2),
+            ]
+        };
+
+        let (mut world_a, boxes_a) = test_world();
+        for c in constraints(boxes_a).iter() {
+            world_a.physics.add_constraint(*c);
+        }
+
+        let (mut world_b, boxes_b) = test_world();
+        let temp = world_b
+            .physics
+            .add_constraint(ConstraintBuilder::new(boxes_b[1]).build_distance(2.0));
+        for c in constraints(boxes_b).iter() {
+            world_b.physics.add_constraint(*c);
+        }
+        // removing moves the last constraint in the slotmap into the removed one's place
+        world_b.physics.remove_constraint(temp);
+
+        for _ in 0..60 {
+            world_a.tick();
+            world_b.tick();
+        }
+        assert!(world_a.state() == world_b.state());
+    }
+
+    #[test]
+    fn mismatched_layers_are_rejected() {
+        let (world, _) = test_world();
+        let snapshot = world.snapshot();
+
+        let mut other = World::new();
+        let _extra_layer: graph::Layer<f64> = other.graph.create_layer();
+        assert!(matches!(
+            other.restore(&snapshot),
+            Err(SnapshotError::LayerMismatch)
+        ));
+        assert!(matches!(
+            other.restore(&snapshot[..snapshot.len() / 2]),
+            Err(SnapshotError::Decode(_))
+        ));
+    }
+
+    /// Layers outside of physics have to be saved too, otherwise the graph would be left
+    /// pointing to components that have changed or don't exist.
+    #[test]
+    fn unsaved_layers_are_rejected() {
+        let (mut world, _) = test_world();
+        let snapshot = world.physics.snapshot(
+            &world.graph,
+            &world.l_pose,
+            &world.l_body,
+            &world.l_collider,
+            &world.l_rope,
+            &world.l_soft_body,
+            &[&world.l_shape],
+        );
+        assert!(matches!(
+            world.restore(&snapshot),
+            Err(SnapshotError::LayerMismatch)
+        ));
+
+        let snapshot = world.snapshot();
+        let result = world.physics.restore_snapshot(
+            &snapshot,
+            &mut world.graph,
+            &mut world.l_pose,
+            &mut world.l_body,
+            &mut world.l_collider,
+            &mut world.l_rope,
+            &mut world.l_soft_body,
+            &mut [&mut world.l_evt_sink, &mut world.l_shape],
+        );
+        assert!(matches!(result, Err(SnapshotError::LayerMismatch)));
+    }
+
+    /// Changes to layers outside of physics are undone along with everything else.
+    #[test]
+    fn other_layers_are_restored() {
+        let (mut world, _) = test_world();
+        let shapes = |world: &World| -> Vec<String> {
+            (world.l_pose.iter(&world.graph))
+                .map(|pose| {
+                    let shape = world.graph.get_neighbor(&pose, &world.l_shape);
+                    format!("{:?}", shape.map(|shape| *shape))
+                })
+                .collect()
+        };
+        let snapshot = world.snapshot();
+        let shapes_before = shapes(&world);
+
+        // change one shape and give a body without one a new shape
+        let changed = world.l_shape.iter_mut(&world.graph).next().unwrap();
+        *changed.item = Shape::Circle {
+            r: 1.0,
+            points: 3,
+            color: [1.0; 4],
+        };
+        let new_shape = world.l_shape.insert(
+            Shape::Rect {
+                w: 1.0,
+                h: 1.0,
+                color: [1.0; 4],
+            },
+            &mut world.graph,
+        );
+        let new_shape = graph::NodeRef::as_node(&new_shape, &world.graph);
+        let pose = world.l_pose.iter(&world.graph).next().unwrap();
+        let pose = graph::NodeRef::as_node(&pose, &world.graph);
+        world.graph.connect_unchecked(&pose, &new_shape);
+        assert!(shapes(&world) != shapes_before);
+
+        world.restore(&snapshot).unwrap();
+        assert_eq!(shapes(&world), shapes_before);
+        assert!(new_shape.check(&world.graph).is_none());
+    }
+}
@@ -0,0 +1,307 @@
+//! Tools for creating deformable bodies out of particles.
+
+use crate::{
+    graph::{self, UnsafeNode},
+    graphics::Shape,
+    math as m,
+    physics::{place_along_path, Body, Collider, Material},
+};
+
+/// A closed loop of particles that keeps its edge lengths and the area it encloses,
+/// making a squishy blob. See [`spawn_soft_body`][self::spawn_soft_body].
+///
+/// Particles collide with other things as circles, and the edges between them
+/// also collide with rigid colliders where the particles don't,
+/// so things don't slip in between particles.
+/// Particles of the same soft body don't collide with each other.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SoftBody {
+    /// Rest length of the edges between neighboring particles.
+    pub spacing: f64,
+    /// Thickness of the surface, which is also the diameter of the particles.
+    pub thickness: f64,
+    /// Compliance of the edges, zero makes the surface unstretchable.
+    pub compliance: f64,
+    /// Area the particles try to enclose. Change this to inflate or deflate the body.
+    pub rest_area: f64,
+    /// Compliance of the area constraint, zero makes the body incompressible.
+    pub area_compliance: f64,
+    pub damping: f64,
+    pub material: Material,
+    /// Collision layer of the particles and edges, see [`MaskMatrix`][super::collision::MaskMatrix].
+    pub layer: usize,
+}
+
+/// Information returned from the creation of a soft body.
+#[derive(Clone, Copy, Debug)]
+pub struct SoftBodyProperties {
+    pub particle_count: usize,
+    pub soft_body_node: graph::Node<SoftBody>,
+    pub first_particle: graph::Node<Body>,
+}
+
+/// Spawn a soft body in the shape of a polygon outline.
+///
+/// Particles are placed along the outline so that they're all equally far from each other
+/// measured along it, adjusting spacing to make them fit.
+/// Particles on either side of a corner are a bit closer to each other than `spacing`,
+/// so corners get cut off and rounded a little if the spacing is large.
+/// The outline can be in either winding order and doesn't need to be convex.
+/// The rest area of the soft body is set to the area enclosed by the particles.
+///
+/// # Panics
+/// Panics if the outline has fewer than 3 points.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_soft_body(
+    mut soft_body: SoftBody,
+    outline: &[m::Vec2],
+    particle_mass: f64,
+    l_body: &mut graph::Layer<Body>,
+    l_pose: &mut graph::Layer<m::Pose>,
+    l_collider: &mut graph::Layer<Collider>,
+    l_soft_body: &mut graph::Layer<SoftBody>,
+    l_shape: &mut graph::Layer<Shape>,
+    graph: &mut graph::Graph,
+) -> SoftBodyProperties {
+    assert!(outline.len() >= 3, "A soft body needs at least 3 points");
+
+    // the solver wants particles in counterclockwise order
+    let mut outline = outline.to_vec();
+    if signed_area(&outline) < 0.0 {
+        outline.reverse();
+    }
+    // closing the loop turns the outline into a path from the first point back to itself
+    outline.push(outline[0]);
+    let perimeter: f64 = outline.windows(2).map(|seg| (seg[1] - seg[0]).mag()).sum();
+    let particle_count = ((perimeter / soft_body.spacing).round() as usize).max(3);
+    let (mut points, spacing) = place_along_path(&outline, particle_count);
+    // the last point lands back on the first
+    points.pop();
+    soft_body.spacing = spacing;
+    soft_body.rest_area = signed_area(&points);
+
+    let body_proto = Body::new_particle(particle_mass);
+    let collider_proto = Collider::new_circle(soft_body.thickness / 2.0)
+        .with_layer(soft_body.layer)
+        .with_material(soft_body.material);
+    // temporary visualisation with Shapes until I get something more bespoke for this
+    let shape_proto = Shape::Circle {
+        r: soft_body.thickness / 2.0,
+        points: 8,
+        color: [0.871, 0.392, 0.612, 1.0],
+    };
+
+    let soft_body_node = l_soft_body.insert(soft_body, graph);
+    let mut first_body: Option<graph::NodePosition> = None;
+    let mut prev_body: Option<graph::NodePosition> = None;
+    for point in points {
+        let body = l_body.insert(body_proto, graph);
+        let pose = l_pose.insert(m::Pose::new(point, Default::default()), graph);
+        let coll = l_collider.insert(collider_proto, graph);
+        let shape = l_shape.insert(shape_proto, graph);
+        graph.connect(&body, &pose);
+        graph.connect(&pose, &coll);
+        graph.connect(&body, &coll);
+        graph.connect(&pose, &shape);
+        match prev_body {
+            Some(prev) => graph.connect_oneway_unchecked(&prev, &body),
+            None => {
+                graph.connect_oneway(&soft_body_node, &body);
+                first_body = Some(body.pos());
+            }
+        }
+        prev_body = Some(body.pos());
+    }
+    // the last particle points back to the soft body node like in a rope,
+    // the edge closing the loop is implied
+    graph.connect_oneway_unchecked(&prev_body.unwrap(), &soft_body_node);
+
+    SoftBodyProperties {
+        particle_count,
+        soft_body_node: graph::NodeRef::as_node(&soft_body_node, graph),
+        first_particle: graph::NodeRef::as_node(&l_body.get_unchecked(first_body.unwrap()), graph),
+    }
+}
+
+/// Area of a polygon, negative if the points are in clockwise order.
+pub(crate) fn signed_area(points: &[m::Vec2]) -> f64 {
+    0.5 * (0..points.len())
+        .map(|i| points[i].wedge(points[(i + 1) % points.len()]).xy)
+        .sum::<f64>()
+}
+
+/// An iterator over the particles in a particular soft body, in counterclockwise order.
+pub struct SoftBodyIter<'a> {
+    soft_body_node: graph::NodeRef<'a, SoftBody>,
+    has_started: bool,
+    curr_body: Option<graph::NodeRef<'a, Body>>,
+    l_body: &'a graph::Layer<Body>,
+    graph: &'a graph::Graph,
+}
+
+impl<'a> SoftBodyIter<'a> {
+    pub fn new(
+        soft_body_node: graph::NodeRef<'a, SoftBody>,
+        l_body: &'a graph::Layer<Body>,
+        graph: &'a graph::Graph,
+    ) -> Self {
+        Self {
+            soft_body_node,
+            has_started: false,
+            curr_body: None,
+            l_body,
+            graph,
+        }
+    }
+}
+
+impl<'a> Iterator for SoftBodyIter<'a> {
+    type Item = graph::NodeRef<'a, Body>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(curr) = &self.curr_body {
+            self.curr_body = self.graph.get_neighbor(curr, self.l_body)
+        } else if !self.has_started {
+            self.has_started = true;
+            self.curr_body = self.graph.get_neighbor(&self.soft_body_node, self.l_body);
+        }
+        self.curr_body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::tests::World;
+
+    impl World {
+        /// A soft body in the shape of a square with the given center and side length.
+        fn spawn_soft_square(&mut self, center: m::Vec2, side: f64) -> SoftBodyProperties {
+            self.spawn_soft_square_on_layer(center, side, 0)
+        }
+
+        fn spawn_soft_square_on_layer(
+            &mut self,
+            center: m::Vec2,
+            side: f64,
+            layer: usize,
+        ) -> SoftBodyProperties {
+            let half = side / 2.0;
+            spawn_soft_body(
+                SoftBody {
+                    spacing: 0.1,
+                    thickness: 0.1,
+                    compliance: 0.000001,
+                    rest_area: 0.0,
+                    area_compliance: 0.0,
+                    damping: 5.0,
+                    material: Material::default(),
+                    layer,
+                },
+                &[
+                    center + m::Vec2::new(-half, -half),
+                    center + m::Vec2::new(half, -half),
+                    center + m::Vec2::new(half, half),
+                    center + m::Vec2::new(-half, half),
+                ],
+                0.05,
+                &mut self.l_body,
+                &mut self.l_pose,
+                &mut self.l_collider,
+                &mut self.l_soft_body,
+                &mut self.l_shape,
+                &mut self.graph,
+            )
+        }
+    }
+
+    /// Positions of the particles of a soft body.
+    fn soft_body_points(world: &World, soft_body: &SoftBodyProperties) -> Vec<m::Vec2> {
+        let node = soft_body.soft_body_node.check(&world.graph).unwrap();
+        let node = world.l_soft_body.get(node);
+        SoftBodyIter::new(node, &world.l_body, &world.graph)
+            .map(|particle| {
+                world
+                    .graph
+                    .get_neighbor(&particle, &world.l_pose)
+                    .unwrap()
+                    .translation
+            })
+            .collect()
+    }
+
+    #[test]
+    fn soft_body_rests_on_ground_and_keeps_its_area() {
+        let mut world = World::new();
+        world.spawn_ground();
+        let soft_body = world.spawn_soft_square(m::Vec2::new(0.0, 0.5), 1.0);
+        let rest_area = signed_area(&soft_body_points(&world, &soft_body));
+        for _ in 0..180 {
+            world.tick();
+        }
+        let points = soft_body_points(&world, &soft_body);
+        assert!((signed_area(&points) - rest_area).abs() < 0.02 * rest_area);
+        // particles touching the ground are pushed out of it just once, by the edges
+        let lowest = points.iter().map(|p| p.y).fold(f64::MAX, f64::min);
+        assert!((lowest - (-0.5 + 0.05)).abs() < 0.01);
+        assert!(points.iter().all(|p| p.y < 1.0));
+    }
+
+    #[test]
+    fn soft_body_collides_on_its_own_layer() {
+        let mut world = World::new();
+        world.spawn_ground();
+        world.physics.mask_matrix.ignore(0, 1);
+        let soft_body = world.spawn_soft_square_on_layer(m::Vec2::new(0.0, 0.5), 1.0, 1);
+        for _ in 0..60 {
+            world.tick();
+        }
+        // neither the particles nor the edges touch the ground
+        let points = soft_body_points(&world, &soft_body);
+        assert!(points.iter().all(|p| p.y < -0.6));
+    }
+
+    #[test]
+    fn box_rests_on_soft_body() {
+        let mut world = World::new();
+        world.spawn_ground();
+        let soft_body = world.spawn_soft_square(m::Vec2::new(0.0, 0.0), 0.9);
+        let b = world.spawn_box(m::Vec2::new(0.0, 0.8), 0.0);
+        for _ in 0..180 {
+            world.tick();
+        }
+        let points = soft_body_points(&world, &soft_body);
+        let highest = points.iter().map(|p| p.y).fold(f64::MIN, f64::max);
+        // the bottom of the box lies on the top edge of the soft body
+        let box_bottom = world.pose(b).translation.y - 0.25;
+        assert!((box_bottom - (highest + 0.05)).abs() < 0.02);
+        assert!(box_bottom > 0.2);
+    }
+
+    #[test]
+    fn sliding_soft_body_slows_down_from_friction() {
+        let mut world = World::new();
+        world.spawn_ground();
+        let soft_body = world.spawn_soft_square(m::Vec2::new(-3.0, 0.0), 0.9);
+        for _ in 0..60 {
+            world.tick();
+        }
+        let node = soft_body.soft_body_node.check(&world.graph).unwrap();
+        let particles: Vec<graph::Node<Body>> =
+            SoftBodyIter::new(world.l_soft_body.get(node), &world.l_body, &world.graph)
+                .map(|particle| graph::NodeRef::as_node(&particle, &world.graph))
+                .collect();
+        for &particle in &particles {
+            world.body_mut(particle).velocity.linear.x = 4.0;
+        }
+        for _ in 0..120 {
+            world.tick();
+        }
+        let mean_speed = particles
+            .iter()
+            .map(|&particle| world.body(particle).velocity.linear.x)
+            .sum::<f64>()
+            / particles.len() as f64;
+        assert!(mean_speed > 0.0 && mean_speed < 3.0);
+    }
+}
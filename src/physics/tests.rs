@@ -0,0 +1,472 @@
+//! Tests for what happens during a tick that isn't specific to any one of the other modules,
+//! along with a small world shared with the tests of the other physics modules.
+
+use super::*;
+use crate::{event::Event, graphics::Shape};
+use collision::{HGridParams, AABB};
+use forcefield::Gravity;
+
+pub(super) struct World {
+    pub graph: graph::Graph,
+    pub l_pose: graph::Layer<m::Pose>,
+    pub l_body: graph::Layer<Body>,
+    pub l_collider: graph::Layer<Collider>,
+    pub l_rope: graph::Layer<Rope>,
+    pub l_soft_body: graph::Layer<SoftBody>,
+    pub l_shape: graph::Layer<Shape>,
+    pub l_evt_sink: graph::Layer<EventSink>,
+    pub physics: Physics,
+    pub gravity: m::Vec2,
+}
+
+impl World {
+    /// An empty world with earthlike gravity.
+    pub fn new() -> Self {
+        let mut graph = graph::Graph::new();
+        World {
+            l_pose: graph.create_layer(),
+            l_body: graph.create_layer(),
+            l_collider: graph.create_layer(),
+            l_rope: graph.create_layer(),
+            l_soft_body: graph.create_layer(),
+            l_shape: graph.create_layer(),
+            l_evt_sink: graph.create_layer(),
+            graph,
+            physics: Physics::new(HGridParams {
+                approx_bounds: AABB {
+                    min: m::Vec2::new(-10.0, -10.0),
+                    max: m::Vec2::new(10.0, 10.0),
+                },
+                smallest_obj_radius: 0.05,
+                largest_obj_radius: 5.0,
+                expected_obj_count: 64,
+            }),
+            gravity: m::Vec2::new(0.0, -9.81),
+        }
+    }
+
+    /// A wide static box with its top surface at y = -0.5.
+    pub fn spawn_ground(&mut self) -> graph::Node<Collider> {
+        self.spawn_static(
+            Collider::new_rect(10.0, 1.0),
+            m::Pose::new(m::Vec2::new(0.0, -1.0), Default::default()),
+        )
+    }
+
+    pub fn spawn_static(&mut self, coll: Collider, pose: m::Pose) -> graph::Node<Collider> {
+        let coll = self.l_collider.insert(coll, &mut self.graph).pos();
+        let pose = self.l_pose.insert(pose, &mut self.graph);
+        self.graph.connect_unchecked(&coll, &pose);
+        graph::NodeRef::as_node(&self.l_collider.get_unchecked(coll), &self.graph)
+    }
+
+    pub fn spawn_body(&mut self, body: Body, coll: Collider, pose: m::Pose) -> graph::Node<Body> {
+        let body = self.l_body.insert(body, &mut self.graph).pos();
+        let pose = self.l_pose.insert(pose, &mut self.graph).pos();
+        let coll = self.l_collider.insert(coll, &mut self.graph).pos();
+        self.graph.connect_unchecked(&body, &pose);
+        self.graph.connect_unchecked(&body, &coll);
+        self.graph.connect_unchecked(&pose, &coll);
+        graph::NodeRef::as_node(&self.l_body.get_unchecked(body), &self.graph)
+    }
+
+    /// A dynamic half-metre box.
+    pub fn spawn_box(&mut self, position: m::Vec2, angle: f64) -> graph::Node<Body> {
+        let coll = Collider::new_square(0.5);
+        self.spawn_body(
+            Body::new_dynamic(&coll, 1.0),
+            coll,
+            m::Pose::new(position, m::Rotor2::from_angle(angle)),
+        )
+    }
+
+    /// A rope hanging from a fixed point at `start`.
+    pub fn spawn_rope(&mut self, start: m::Vec2, end: m::Vec2) -> RopeProperties {
+        let rope = spawn_rope_line(
+            rope_proto(),
+            start,
+            end,
+            0.02,
+            &mut self.l_body,
+            &mut self.l_pose,
+            &mut self.l_collider,
+            &mut self.l_rope,
+            &mut self.l_shape,
+            &mut self.graph,
+        );
+        self.physics.add_constraint(
+            ConstraintBuilder::new(rope.first_particle)
+                .with_target_origin(start)
+                .build_attachment(),
+        );
+        rope
+    }
+
+    pub fn tick(&mut self) {
+        self.physics.tick(
+            &self.graph,
+            &mut self.l_pose,
+            &mut self.l_body,
+            &self.l_collider,
+            &self.l_rope,
+            &self.l_soft_body,
+            &mut self.l_evt_sink,
+            1.0 / 60.0,
+            &Gravity(self.gravity),
+        );
+    }
+
+    pub fn body(&self, body: graph::Node<Body>) -> Body {
+        *self.l_body.get(body.check(&self.graph).unwrap())
+    }
+
+    pub fn body_mut(&mut self, body: graph::Node<Body>) -> graph::NodeRefMut<'_, Body> {
+        self.l_body.get_mut(body.check(&self.graph).unwrap())
+    }
+
+    /// The first collider attached to a body.
+    pub fn collider(&self, body: graph::Node<Body>) -> graph::Node<Collider> {
+        let body = self.l_body.get(body.check(&self.graph).unwrap());
+        let coll = self.graph.get_neighbor(&body, &self.l_collider).unwrap();
+        graph::NodeRef::as_node(&coll, &self.graph)
+    }
+
+    /// Connect an event sink to a collider to receive its events.
+    pub fn listen(&mut self, coll: graph::Node<Collider>) -> graph::Node<EventSink> {
+        let sink = self
+            .l_evt_sink
+            .insert(EventSink::default(), &mut self.graph)
+            .pos();
+        self.graph.connect_unchecked(&sink, &coll);
+        graph::NodeRef::as_node(&self.l_evt_sink.get_unchecked(sink), &self.graph)
+    }
+
+    /// Take the events received by a sink since the last call.
+    pub fn events(&mut self, sink: graph::Node<EventSink>) -> Vec<Event> {
+        let mut sink = self.l_evt_sink.get_mut(sink.check(&self.graph).unwrap());
+        sink.drain().collect()
+    }
+
+    pub fn pose(&self, body: graph::Node<Body>) -> m::Pose {
+        let body = self.l_body.get(body.check(&self.graph).unwrap());
+        *self.graph.get_neighbor(&body, &self.l_pose).unwrap()
+    }
+}
+
+/// Angle of a pose in radians.
+pub(super) fn angle(pose: m::Pose) -> f64 {
+    Angle::from(pose.rotation).rad()
+}
+
+/// The rope spawned by [`World::spawn_rope`].
+pub(super) fn rope_proto() -> Rope {
+    Rope {
+        spacing: 0.1,
+        length: 0.0,
+        thickness: 0.1,
+        compliance: 0.0000001,
+        bending_max_angle: 0.3,
+        bending_compliance: 0.08,
+        damping: 20.0,
+        material: Material::default(),
+        rest_bends: Vec::new(),
+    }
+}
+
+//
+// continuous collision detection
+//
+
+/// A bullet moving hundreds of times its own size per substep stops at a wall thinner than itself.
+#[test]
+fn ccd_stops_fast_body_at_thin_wall() {
+    let mut world = World::new();
+    world.gravity = m::Vec2::zero();
+    world.spawn_static(
+        Collider::new_rect(0.005, 2.0),
+        m::Pose::new(m::Vec2::new(5.0, 0.0), Default::default()),
+    );
+    let coll = Collider::new_circle(0.01);
+    let bullet = world.spawn_body(
+        Body::new_dynamic(&coll, 1.0)
+            .with_ccd(true)
+            .with_velocity(Velocity {
+                linear: m::Vec2::new(6000.0, 0.0),
+                angular: 0.0,
+            }),
+        coll,
+        m::Pose::new(m::Vec2::new(-5.0, 0.0), Default::default()),
+    );
+    for _ in 0..10 {
+        world.tick();
+        assert!(world.pose(bullet).translation.x < 5.0);
+    }
+}
+
+/// A body that's barely moving at the start of the frame but is pushed hard during it
+/// still hits a wall that wasn't anywhere near its predicted path.
+#[test]
+fn ccd_sees_obstacles_outside_predicted_path() {
+    let mut world = World::new();
+    world.gravity = m::Vec2::zero();
+    world.spawn_static(
+        Collider::new_rect(0.005, 2.0),
+        m::Pose::new(m::Vec2::new(3.0, 0.0), Default::default()),
+    );
+    let coll = Collider::new_circle(0.01);
+    let bullet = world.spawn_body(
+        Body::new_dynamic(&coll, 1.0).with_ccd(true),
+        coll,
+        m::Pose::new(m::Vec2::new(0.0, 0.0), Default::default()),
+    );
+    let mass = world.body(bullet).mass.inv().recip();
+    world
+        .body_mut(bullet)
+        .apply_force(m::Vec2::new(mass * 200_000.0, 0.0));
+    for _ in 0..5 {
+        world.tick();
+        assert!(world.pose(bullet).translation.x < 3.0);
+    }
+}
+
+//
+// events
+//
+
+#[test]
+fn contact_events_begin_persist_and_end() {
+    let mut world = World::new();
+    let ground = world.spawn_ground();
+    let sink = world.listen(ground);
+    let b = world.spawn_box(m::Vec2::new(0.0, 0.0), 0.0);
+    let box_coll = world.collider(b);
+
+    let mut began = false;
+    for _ in 0..60 {
+        world.tick();
+        for evt in world.events(sink) {
+            match evt {
+                Event::ContactBegin(c) => {
+                    assert!(!began, "contact began twice");
+                    began = true;
+                    assert_eq!(c.collider, ground);
+                    assert_eq!(c.other_collider, box_coll);
+                    assert!((c.normal.y - 1.0).abs() < 1e-6);
+                    assert!(c.impulse > 0.0);
+                }
+                Event::ContactPersist(_) => assert!(began, "contact persisted before beginning"),
+                other => panic!("unexpected event {:?}", other),
+            }
+        }
+    }
+    assert!(began);
+
+    // resting on the ground, the impulse over a frame carries the box's weight
+    world.tick();
+    match world.events(sink)[..] {
+        [Event::ContactPersist(c)] => {
+            let weight_impulse = 0.25 * 9.81 / 60.0;
+            assert!(
+                (c.impulse - weight_impulse).abs() < 0.05 * weight_impulse,
+                "impulse {} should match weight {}",
+                c.impulse,
+                weight_impulse
+            );
+            assert_eq!(c.points().len(), 2);
+        }
+        ref other => panic!("expected one persist event, got {:?}", other),
+    }
+
+    world.body_mut(b).velocity.linear = m::Vec2::new(0.0, 10.0);
+    world.tick();
+    match world.events(sink)[..] {
+        [Event::ContactEnd(c)] => {
+            assert_eq!(c.other_collider, box_coll);
+            assert_eq!(c.impulse, 0.0);
+        }
+        ref other => panic!("expected one end event, got {:?}", other),
+    }
+    world.tick();
+    assert!(world.events(sink).is_empty());
+}
+
+#[test]
+fn trigger_events_enter_and_exit() {
+    let mut world = World::new();
+    world.gravity = m::Vec2::zero();
+    let trigger = world.spawn_static(Collider::new_square(1.0).trigger(), m::Pose::identity());
+    let trigger_sink = world.listen(trigger);
+    let coll = Collider::new_circle(0.1);
+    let ball = world.spawn_body(
+        Body::new_dynamic(&coll, 1.0).with_velocity(Velocity {
+            linear: m::Vec2::new(6.0, 0.0),
+            angular: 0.0,
+        }),
+        coll,
+        m::Pose::new(m::Vec2::new(-1.0, 0.0), Default::default()),
+    );
+    let ball_coll = world.collider(ball);
+    let ball_sink = world.listen(ball_coll);
+
+    let mut entered_at = None;
+    let mut exited_at = None;
+    for frame in 0..30 {
+        world.tick();
+        let events = world.events(trigger_sink);
+        let ball_events = world.events(ball_sink);
+        assert_eq!(events.len(), ball_events.len());
+        for (evt, ball_evt) in events.into_iter().zip(ball_events) {
+            match (evt, ball_evt) {
+                (Event::TriggerEnter(t), Event::TriggerEnter(b)) => {
+                    assert!(entered_at.is_none());
+                    entered_at = Some(frame);
+                    assert_eq!((t.collider, t.other_collider), (trigger, ball_coll));
+                    assert_eq!((b.collider, b.other_collider), (ball_coll, trigger));
+                }
+                (Event::TriggerExit(t), Event::TriggerExit(_)) => {
+                    assert!(entered_at.is_some() && exited_at.is_none());
+                    exited_at = Some(frame);
+                    assert_eq!(t.other_collider, ball_coll);
+                }
+                other => panic!("unexpected events {:?}", other),
+            }
+        }
+        let inside = entered_at.is_some() && exited_at.is_none();
+        let overlaps: Vec<_> = world.physics.trigger_overlaps(trigger).collect();
+        assert_eq!(overlaps, if inside { vec![ball_coll] } else { vec![] });
+    }
+    // the ball passes straight through, taking a bit over 1.2 metres / 6 m/s = 12 frames
+    let (entered_at, exited_at) = (entered_at.unwrap(), exited_at.unwrap());
+    assert!((11..=13).contains(&(exited_at - entered_at)));
+    assert!((world.body(ball).velocity.linear - m::Vec2::new(6.0, 0.0)).mag() < 1e-9);
+}
+
+//
+// queries
+//
+
+/// Two overlapping circles on collision layers that ignore each other, ticked once to index them.
+fn query_world() -> (World, [graph::Node<Collider>; 2]) {
+    let mut world = World::new();
+    world.gravity = m::Vec2::zero();
+    world.physics.mask_matrix.ignore(0, 1);
+    let circle = world.spawn_static(Collider::new_circle(0.5), m::Pose::identity());
+    let other_layer = world.spawn_static(
+        Collider::new_circle(0.5).with_layer(1),
+        m::Pose::new(m::Vec2::new(0.2, 0.0), Default::default()),
+    );
+    world.tick();
+    (world, [circle, other_layer])
+}
+
+fn query_aabb(
+    world: &mut World,
+    min: m::Vec2,
+    max: m::Vec2,
+    layer: usize,
+) -> Vec<graph::Node<Collider>> {
+    let hits = world.physics.query_aabb(
+        &world.graph,
+        &world.l_pose,
+        &world.l_collider,
+        AABB { min, max },
+        layer,
+    );
+    hits.iter()
+        .map(|hit| graph::NodeRef::as_node(hit, &world.graph))
+        .collect()
+}
+
+#[test]
+fn query_aabb_finds_colliders_reaching_into_the_area() {
+    let (mut world, [circle, other_layer]) = query_world();
+    let hits = query_aabb(
+        &mut world,
+        m::Vec2::new(0.3, -0.1),
+        m::Vec2::new(0.6, 0.1),
+        0,
+    );
+    assert_eq!(hits, vec![circle]);
+    let hits = query_aabb(
+        &mut world,
+        m::Vec2::new(0.3, -0.1),
+        m::Vec2::new(0.6, 0.1),
+        1,
+    );
+    assert_eq!(hits, vec![other_layer]);
+}
+
+/// The corner of the circle's bounding box is outside of the circle itself.
+#[test]
+fn query_aabb_misses_colliders_only_the_bounding_box_overlaps() {
+    let (mut world, _) = query_world();
+    let hits = query_aabb(
+        &mut world,
+        m::Vec2::new(-0.6, 0.4),
+        m::Vec2::new(-0.4, 0.6),
+        0,
+    );
+    assert!(hits.is_empty());
+}
+
+#[test]
+fn query_shape_hits_and_misses_by_shape_and_layer() {
+    let (mut world, [circle, other_layer]) = query_world();
+    let mut query = |coll: Collider, position: m::Vec2| -> Vec<graph::Node<Collider>> {
+        let pose = m::Pose::new(position, Default::default());
+        let hits = (world.physics).query_shape(
+            &world.graph,
+            &world.l_pose,
+            &world.l_collider,
+            &coll,
+            &pose,
+        );
+        hits.iter()
+            .map(|hit| graph::NodeRef::as_node(hit, &world.graph))
+            .collect()
+    };
+    let probe = Collider::new_circle(0.1);
+    assert_eq!(query(probe, m::Vec2::new(-0.5, 0.0)), vec![circle]);
+    assert_eq!(
+        query(probe.with_layer(1), m::Vec2::new(0.7, 0.0)),
+        vec![other_layer]
+    );
+    assert!(query(probe, m::Vec2::new(-0.5, 0.5)).is_empty());
+}
+
+//
+// contact modification
+//
+
+#[test]
+fn physics_is_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<Physics>();
+}
+
+/// A ball jumping up through a one-way platform passes through it and then lands on top of it.
+#[test]
+fn one_way_platform_lets_through_from_below_only() {
+    let mut world = World::new();
+    world.spawn_static(
+        Collider::new_rect(2.0, 0.1).with_one_way(m::Vec2::unit_y()),
+        m::Pose::identity(),
+    );
+    let coll = Collider::new_circle(0.1);
+    let ball = world.spawn_body(
+        Body::new_dynamic(&coll, 1.0).with_velocity(Velocity {
+            linear: m::Vec2::new(0.0, 4.0),
+            angular: 0.0,
+        }),
+        coll,
+        m::Pose::new(m::Vec2::new(0.0, -0.5), Default::default()),
+    );
+    let mut max_height = f64::MIN;
+    for _ in 0..120 {
+        world.tick();
+        max_height = max_height.max(world.pose(ball).translation.y);
+    }
+    assert!(max_height > 0.2, "ball only got to {}", max_height);
+    let rest = world.pose(ball).translation.y;
+    assert!((rest - 0.15).abs() < 0.01, "ball is at {}", rest);
+}